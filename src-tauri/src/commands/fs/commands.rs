@@ -1,6 +1,7 @@
 use super::filesystem::{FileEntry, FileSystem};
 use super::session::get_sftp_session_arc;
 use super::sftp_impl::SftpFileSystem;
+use crate::commands::ssh::utils::resolve_encoding;
 use crate::commands::ssh::SshState;
 use crate::utils::ssh_log;
 use serde::Serialize;
@@ -17,7 +18,7 @@ struct SftpTransferProgressPayload {
     speed: u64,
 }
 
-fn emit_sftp_transfer_progress<R: Runtime>(
+pub(crate) fn emit_sftp_transfer_progress<R: Runtime>(
     app: &AppHandle<R>,
     transfer_id: &str,
     transferred: u64,
@@ -69,7 +70,7 @@ macro_rules! run_sftp {
                 loop {
                     if sftp_session.is_none() {
                         let session_arc = get_sftp_session_arc($ssh_state, &session_id)?;
-                        let channel = session_arc.channel_open_session().await
+                        let channel = session_arc.read().await.channel_open_session().await
                             .map_err(|e| format!("Failed to open SFTP channel: {}", e))?;
                         channel.request_subsystem(true, "sftp").await
                             .map_err(|e| format!("Failed to request SFTP subsystem: {}", e))?;
@@ -83,7 +84,8 @@ macro_rules! run_sftp {
                     }
                     
                     let active_sftp = sftp_session.as_ref().unwrap();
-                    let $fs = SftpFileSystem::new(active_sftp);
+                    let $fs = SftpFileSystem::new(active_sftp)
+                        .with_encoding(resolve_encoding(conn.config.encoding.as_deref()));
                     
                     match $block.await {
                         Ok(res) => return Ok(res),
@@ -112,12 +114,28 @@ macro_rules! run_sftp {
     }};
 }
 
+// 🟢 开启终端目录联动时，空路径解析为 shell 当前目录 (OSC 7)
+fn resolve_listing_path(ssh_state: &SshState, id: &str, path: String) -> String {
+    if !path.trim().is_empty() {
+        return path;
+    }
+    let map = match ssh_state.sessions.lock() {
+        Ok(g) => g,
+        Err(p) => p.into_inner(),
+    };
+    map.get(id)
+        .filter(|conn| conn.cwd_sync_enabled())
+        .and_then(|conn| conn.cwd())
+        .unwrap_or(path)
+}
+
 #[tauri::command]
 pub async fn list_ssh_files(
     ssh_state: State<'_, SshState>,
     id: String,
     path: String,
 ) -> Result<Vec<FileEntry>, String> {
+    let path = resolve_listing_path(&ssh_state, &id, path);
     let res = run_sftp!(
         &ssh_state,
         id,
@@ -13,6 +13,7 @@ pub struct FileEntry {
     pub owner: String,
     pub group: String,
     pub extension: String,
+    pub undecodable_name: bool, // Name was lossily decoded; it may not round-trip to the server
 }
 
 // === Core Abstract Interface ===
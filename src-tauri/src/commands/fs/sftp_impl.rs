@@ -1,4 +1,5 @@
 use super::filesystem::{FileEntry, FileSystem};
+use encoding_rs::{Encoding, UTF_8};
 use russh_sftp::client::SftpSession;
 use russh_sftp::protocol::OpenFlags;
 use std::time::{Duration, Instant};
@@ -10,11 +11,23 @@ const TRANSFER_PROGRESS_INTERVAL: Duration = Duration::from_millis(150);
 
 pub struct SftpFileSystem<'a> {
     sftp: &'a SftpSession,
+    encoding: &'static Encoding,
 }
 
 impl<'a> SftpFileSystem<'a> {
     pub fn new(sftp: &'a SftpSession) -> Self {
-        Self { sftp }
+        Self {
+            sftp,
+            encoding: UTF_8,
+        }
+    }
+
+    // 🟢 文本读写按服务器编码转换；文件名不做转换。
+    // russh-sftp 以 UTF-8 String 收发路径 (非法字节按有损解码)，拿不到原始字节，也无法回传非 UTF-8 路径；
+    // 列目录时把含替换字符的文件名标记为 undecodable_name，由前端提示该条目可能无法打开/重命名/删除。
+    pub fn with_encoding(mut self, encoding: &'static Encoding) -> Self {
+        self.encoding = encoding;
+        self
     }
 
     async fn copy_with_progress<R, W, F>(
@@ -188,6 +201,7 @@ impl<'a> FileSystem for SftpFileSystem<'a> {
                 owner: attrs.uid.unwrap_or(0).to_string(),
                 group: attrs.gid.unwrap_or(0).to_string(),
                 extension,
+                undecodable_name: file_name.contains(char::REPLACEMENT_CHARACTER),
             });
         }
 
@@ -317,16 +331,36 @@ impl<'a> FileSystem for SftpFileSystem<'a> {
             return Err("File too large (>5MB)".to_string());
         }
 
-        let mut content = String::new();
+        let mut bytes = Vec::new();
         remote_file
-            .read_to_string(&mut content)
+            .read_to_end(&mut bytes)
             .await
-            .map_err(|e| format!("Read text failed (Binary?): {}", e))?;
+            .map_err(|e| format!("Read text failed: {}", e))?;
 
-        Ok(content)
+        if self.encoding == UTF_8 {
+            return String::from_utf8(bytes)
+                .map_err(|e| format!("Read text failed (Binary?): {}", e));
+        }
+        self.encoding
+            .decode_without_bom_handling_and_without_replacement(&bytes)
+            .map(|text| text.into_owned())
+            .ok_or_else(|| {
+                format!(
+                    "Read text failed (Binary or not {}?)",
+                    self.encoding.name()
+                )
+            })
     }
 
     async fn write_text(&self, path: &str, content: &str) -> Result<(), String> {
+        let (encoded, _, had_unmappable) = self.encoding.encode(content);
+        if had_unmappable {
+            return Err(format!(
+                "Content contains characters that cannot be encoded as {}",
+                self.encoding.name()
+            ));
+        }
+
         let mut open_flags = OpenFlags::empty();
         open_flags.set(OpenFlags::WRITE, true);
         open_flags.set(OpenFlags::CREATE, true);
@@ -335,7 +369,7 @@ impl<'a> FileSystem for SftpFileSystem<'a> {
         let mut remote_file = self.sftp.open_with_flags(path, open_flags).await.map_err(|e| e.to_string())?;
 
         remote_file
-            .write_all(content.as_bytes())
+            .write_all(&encoded)
             .await
             .map_err(|e| e.to_string())?;
         remote_file.flush().await.map_err(|e| e.to_string())?;
@@ -1,7 +1,9 @@
+use crate::commands::ssh::core::ShellCommandEvent;
+use crate::commands::ssh::SshState;
 use crate::models::{CommandHistoryItem, HistoryFilterConfig};
 use crate::state::AppState;
 use serde::Serialize;
-use sqlx::{FromRow, Row};
+use sqlx::{FromRow, Row, SqlitePool};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::State;
 // 辅助函数：判断是否应该记录
@@ -82,28 +84,101 @@ fn is_nonsense(cmd: &str) -> bool {
     false
 }
 
+/// 单条命令流水的附加信息 (shell 集成可提供退出码、耗时与工作目录)
+#[derive(Default)]
+struct CommandEventDetails {
+    exit_code: Option<i32>,
+    duration_ms: Option<i64>,
+    cwd: Option<String>,
+    session_id: Option<String>,
+}
+
 /// 记录一条命令 (核心事务逻辑)
 #[tauri::command]
 pub async fn record_command_history(
     state: State<'_, AppState>,
+    ssh_state: State<'_, SshState>,
     server_id: String,
     command: String,
     source: Option<String>, // default 'user'
+    session_id: Option<String>,
 ) -> Result<(), String> {
-    let config = HistoryFilterConfig::default(); // 这里后期可以从 DB 读取配置
-
-    if !should_record(&command, &config) {
-        return Ok(());
+    let source_str = source.unwrap_or_else(|| "user".to_string());
+    // 🟢 该会话已启用 shell 集成时命令由 OSC 133 记录，跳过前端按键推断的重复记录
+    if source_str == "user" {
+        if let Some(session_id) = session_id.as_deref() {
+            let integrated = match ssh_state.sessions.lock() {
+                Ok(map) => map
+                    .get(session_id)
+                    .map(|conn| conn.shell_integration_active()),
+                Err(p) => p
+                    .into_inner()
+                    .get(session_id)
+                    .map(|conn| conn.shell_integration_active()),
+            };
+            if integrated == Some(true) {
+                return Ok(());
+            }
+        }
     }
 
-    let normalized = command.trim().to_string();
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs() as i64;
-    let source_str = source.unwrap_or_else(|| "user".to_string());
 
-    let mut tx = state.db.begin().await.map_err(|e| e.to_string())?;
+    insert_command_event(
+        &state.db,
+        &server_id,
+        &command,
+        &source_str,
+        now,
+        CommandEventDetails::default(),
+    )
+    .await
+}
+
+/// 记录 shell 集成 (OSC 133) 识别出的命令，source 固定为 'shell-integration'
+pub async fn record_shell_command(
+    db: &SqlitePool,
+    server_id: &str,
+    session_id: &str,
+    event: &ShellCommandEvent,
+) -> Result<(), String> {
+    insert_command_event(
+        db,
+        server_id,
+        &event.command,
+        "shell-integration",
+        event.executed_at,
+        CommandEventDetails {
+            exit_code: event.exit_code,
+            duration_ms: Some(event.duration_ms as i64),
+            cwd: event.cwd.clone(),
+            session_id: Some(session_id.to_string()),
+        },
+    )
+    .await
+}
+
+async fn insert_command_event(
+    db: &SqlitePool,
+    server_id: &str,
+    command: &str,
+    source: &str,
+    executed_at: i64,
+    details: CommandEventDetails,
+) -> Result<(), String> {
+    let config = HistoryFilterConfig::default(); // 这里后期可以从 DB 读取配置
+
+    if !should_record(command, &config) {
+        return Ok(());
+    }
+
+    let normalized = command.trim().to_string();
+    let now = executed_at;
+
+    let mut tx = db.begin().await.map_err(|e| e.to_string())?;
 
     // 1. 插入或更新 command_history (Global Dictionary)
     // 使用 ON CONFLICT 更新时间和全局计数
@@ -118,7 +193,7 @@ pub async fn record_command_history(
         "#
     )
     .bind(&normalized)
-    .bind(&command)
+    .bind(command)
     .bind(now)
     .bind(now)
     .fetch_one(&mut *tx)
@@ -138,7 +213,7 @@ pub async fn record_command_history(
         "#,
     )
     .bind(history_id)
-    .bind(&server_id)
+    .bind(server_id)
     .bind(now)
     .bind(now)
     .execute(&mut *tx)
@@ -148,14 +223,20 @@ pub async fn record_command_history(
     // 3. 插入 command_events (Audit Log)
     sqlx::query(
         r#"
-        INSERT INTO command_events (command_id, server_id, source, executed_at)
-        VALUES (?, ?, ?, ?)
+        INSERT INTO command_events (
+            command_id, server_id, source, executed_at, exit_code, duration_ms, cwd, session_id
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(history_id)
-    .bind(&server_id)
-    .bind(source_str)
+    .bind(server_id)
+    .bind(source)
     .bind(now)
+    .bind(details.exit_code)
+    .bind(details.duration_ms)
+    .bind(details.cwd)
+    .bind(details.session_id)
     .execute(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
@@ -234,6 +315,11 @@ pub struct ServerHistoryDto {
     pub id: i64,         // 事件 ID (用于删除)
     pub command: String, // 实际命令内容
     pub created_at: i64, // 执行时间戳
+    pub source: Option<String>,
+    // 以下字段仅 shell 集成记录的命令才有
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<i64>,
+    pub cwd: Option<String>,
 }
 
 /// 获取特定服务器的命令历史 (时间倒序)
@@ -249,7 +335,11 @@ pub async fn get_command_history(
         SELECT 
             e.id as id, 
             h.display_command as command, 
-            e.executed_at as created_at
+            e.executed_at as created_at,
+            e.source as source,
+            e.exit_code as exit_code,
+            e.duration_ms as duration_ms,
+            e.cwd as cwd
         FROM command_events e
         JOIN command_history h ON e.command_id = h.id
         WHERE e.server_id = ?
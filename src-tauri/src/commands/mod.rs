@@ -4,9 +4,11 @@ pub mod highlight;
 pub mod history;
 pub mod monitor;
 pub mod proxy;
+pub mod recording;
 pub mod server;
 pub mod settings;
 pub mod snippet;
 pub mod ssh;
+pub mod ssh_config;
 pub mod system;
 pub mod vault;
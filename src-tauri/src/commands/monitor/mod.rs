@@ -1,4 +1,4 @@
-use crate::commands::ssh::{SshState, SshSession};
+use crate::commands::ssh::{SharedSshSession, SshState};
 use crate::utils::ssh_log::{self, SshLogRecord};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -45,7 +45,7 @@ pub(crate) fn get_monitor_session(
     ssh_state: &State<'_, SshState>,
     id: &str,
     operation: &'static str,
-) -> Result<Arc<SshSession>, String> {
+) -> Result<SharedSshSession, String> {
     let map = ssh_state.sessions.lock().map_err(|e| e.to_string())?;
     let conn = map.get(id).ok_or_else(|| {
         ssh_log::warn(
@@ -89,10 +89,13 @@ pub(crate) fn get_monitor_session(
 }
 
 pub async fn exec_ssh_command(
-    session: &SshSession,
+    session: &SharedSshSession,
     command: &str,
 ) -> Result<String, String> {
+    // 打开通道只取读锁，不与其他通道互相等待
     let channel = session
+        .read()
+        .await
         .channel_open_session()
         .await
         .map_err(|e| format!("Failed to open channel: {}", e))?;
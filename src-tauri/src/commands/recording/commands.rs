@@ -0,0 +1,235 @@
+use std::fs;
+use std::path::Path;
+
+use tauri::{AppHandle, State};
+
+use crate::commands::ssh::{SshConnection, SshState};
+use crate::models::DEFAULT_TERM_TYPE;
+use crate::utils::ssh_log::{self, SshLogRecord};
+
+use super::recorder::{
+    apply_retention, load_policy, read_recording_info, recording_path, recordings_dir, save_policy,
+    scan_recordings, RecordingInfo, RecordingPolicy, RecordingTarget, SessionRecorder,
+};
+
+fn active_recording_ids(state: &SshState) -> Vec<String> {
+    let map = match state.sessions.lock() {
+        Ok(g) => g,
+        Err(p) => p.into_inner(),
+    };
+    map.values()
+        .filter_map(SshConnection::recording_id)
+        .collect()
+}
+
+fn find_connection(state: &SshState, session_id: &str) -> Result<SshConnection, String> {
+    let map = match state.sessions.lock() {
+        Ok(g) => g,
+        Err(p) => p.into_inner(),
+    };
+    map.get(session_id)
+        .cloned()
+        .ok_or_else(|| "SSH connection not active".to_string())
+}
+
+// 🟢 为会话开始录制：先按策略清理旧录像，再创建新的 .cast 文件挂到连接上
+pub fn start_connection_recording(
+    app: &AppHandle,
+    conn: &SshConnection,
+    session_id: &str,
+    include_input: bool,
+    active_ids: &[String],
+) -> Result<String, String> {
+    if let Some(id) = conn.recording_id() {
+        return Err(format!(
+            "RECORDING_ACTIVE: Session is already being recorded ({})",
+            id
+        ));
+    }
+
+    let dir = recordings_dir(app)?;
+    let policy = load_policy(&dir);
+    let removed = apply_retention(&dir, &policy, active_ids);
+
+    let (cols, rows) = conn.pty_size();
+    let term = conn
+        .config
+        .shell_options
+        .as_ref()
+        .map_or(DEFAULT_TERM_TYPE, |options| options.term_type.as_str());
+    let title = conn
+        .config
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("{}@{}", conn.config.username, conn.config.host));
+    let recorder = SessionRecorder::create(
+        &dir,
+        &RecordingTarget {
+            session_id,
+            server_id: &conn.config.id,
+            title: &title,
+            term,
+            cols,
+            rows,
+            include_input,
+        },
+        &policy,
+    )?;
+    let recording_id = recorder.id().to_string();
+
+    if let Err(recorder) = conn.attach_recording(recorder) {
+        recorder.finish();
+        let _ = fs::remove_file(recording_path(&dir, &recording_id)?);
+        return Err("RECORDING_ACTIVE: Session is already being recorded".to_string());
+    }
+
+    ssh_log::info(
+        SshLogRecord::new("ssh.recording", "started", "Started session recording")
+            .session_id(session_id.to_string())
+            .server_id(conn.config.id.clone())
+            .instance_id(conn.instance_id)
+            .field("recording_id", recording_id.clone())
+            .field("include_input", include_input)
+            .field("pruned_recordings", removed),
+    );
+    Ok(recording_id)
+}
+
+// 服务器开启了自动录制时在建立连接后调用，失败只记录日志
+pub fn start_auto_recording(app: &AppHandle, state: &SshState, session_id: &str) {
+    let Ok(conn) = find_connection(state, session_id) else {
+        return;
+    };
+    if !conn.config.record_sessions || conn.recording_id().is_some() {
+        return;
+    }
+
+    let active_ids = active_recording_ids(state);
+    if let Err(err) = start_connection_recording(
+        app,
+        &conn,
+        session_id,
+        conn.config.record_input,
+        &active_ids,
+    ) {
+        ssh_log::warn(
+            SshLogRecord::new(
+                "ssh.recording",
+                "auto_start_failed",
+                "Failed to start automatic session recording",
+            )
+            .session_id(session_id.to_string())
+            .server_id(conn.config.id.clone())
+            .field("error", err),
+        );
+    }
+}
+
+#[tauri::command]
+pub fn start_session_recording(
+    app: AppHandle,
+    state: State<'_, SshState>,
+    session_id: String,
+    include_input: Option<bool>,
+) -> Result<RecordingInfo, String> {
+    let conn = find_connection(&state, &session_id)?;
+    let active_ids = active_recording_ids(&state);
+    let include_input = include_input.unwrap_or(conn.config.record_input);
+    let recording_id =
+        start_connection_recording(&app, &conn, &session_id, include_input, &active_ids)?;
+
+    let dir = recordings_dir(&app)?;
+    let mut info = read_recording_info(&recording_path(&dir, &recording_id)?)
+        .ok_or_else(|| "Failed to read recording header".to_string())?;
+    info.active = true;
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn stop_session_recording(
+    state: State<'_, SshState>,
+    session_id: String,
+) -> Result<Option<String>, String> {
+    let conn = find_connection(&state, &session_id)?;
+    let stopped = conn.stop_recording();
+    if let Some(recording_id) = &stopped {
+        ssh_log::info(
+            SshLogRecord::new("ssh.recording", "stopped", "Stopped session recording")
+                .session_id(session_id)
+                .server_id(conn.config.id.clone())
+                .field("recording_id", recording_id.clone()),
+        );
+    }
+    Ok(stopped)
+}
+
+#[tauri::command]
+pub fn list_recordings(
+    app: AppHandle,
+    state: State<'_, SshState>,
+    server_id: Option<String>,
+) -> Result<Vec<RecordingInfo>, String> {
+    let dir = recordings_dir(&app)?;
+    let active_ids = active_recording_ids(&state);
+    Ok(scan_recordings(&dir)
+        .into_iter()
+        .filter(|recording| {
+            server_id
+                .as_deref()
+                .map(|id| recording.server_id == id)
+                .unwrap_or(true)
+        })
+        .map(|mut recording| {
+            recording.active = active_ids.contains(&recording.id);
+            recording
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn delete_recording(
+    app: AppHandle,
+    state: State<'_, SshState>,
+    id: String,
+) -> Result<(), String> {
+    if active_recording_ids(&state).contains(&id) {
+        return Err("RECORDING_ACTIVE: Stop the recording before deleting it".to_string());
+    }
+    let path = recording_path(&recordings_dir(&app)?, &id)?;
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete recording: {}", e))
+}
+
+// 🟢 导出为标准 .cast 文件 (asciinema play / asciinema-player 可直接播放)
+#[tauri::command]
+pub fn export_recording(app: AppHandle, id: String, destination: String) -> Result<u64, String> {
+    let source = recording_path(&recordings_dir(&app)?, &id)?;
+    if !source.exists() {
+        return Err(format!("Recording not found: {}", id));
+    }
+    let destination = Path::new(&destination);
+    if destination == source {
+        return Err("Export destination is the recording itself".to_string());
+    }
+    fs::copy(&source, destination).map_err(|e| format!("Failed to export recording: {}", e))
+}
+
+#[tauri::command]
+pub fn get_recording_policy(app: AppHandle) -> Result<RecordingPolicy, String> {
+    Ok(load_policy(&recordings_dir(&app)?))
+}
+
+// 保存策略后立即执行一次清理，返回删除的录像数量
+#[tauri::command]
+pub fn set_recording_policy(
+    app: AppHandle,
+    state: State<'_, SshState>,
+    policy: RecordingPolicy,
+) -> Result<usize, String> {
+    let dir = recordings_dir(&app)?;
+    save_policy(&dir, &policy)?;
+    Ok(apply_retention(
+        &dir,
+        &policy,
+        &active_recording_ids(&state),
+    ))
+}
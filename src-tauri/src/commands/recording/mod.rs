@@ -0,0 +1,12 @@
+pub mod commands;
+pub mod playback;
+pub mod recorder;
+pub mod search;
+
+pub use commands::{
+    delete_recording, export_recording, get_recording_policy, list_recordings,
+    set_recording_policy, start_auto_recording, start_session_recording, stop_session_recording,
+};
+pub use playback::{control_recording_playback, start_recording_playback, PlaybackRegistry};
+pub use recorder::{RecordingInfo, RecordingPolicy, SessionRecorder};
+pub use search::search_recordings;
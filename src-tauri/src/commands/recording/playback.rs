@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, State};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use super::recorder::{recording_path, recordings_dir};
+
+const PLAYBACK_MIN_SPEED: f64 = 0.1;
+const PLAYBACK_MAX_SPEED: f64 = 16.0;
+const PLAYBACK_CONTROL_CAPACITY: usize = 32;
+// 跳转时补发的历史输出按块发送，避免单条 IPC 消息过大
+const PLAYBACK_SEEK_CHUNK_BYTES: usize = 64 * 1024;
+
+pub struct CastEvent {
+    pub time: f64,
+    pub code: String,
+    pub data: String,
+}
+
+pub struct CastRecording {
+    pub width: u32,
+    pub height: u32,
+    pub events: Vec<CastEvent>,
+}
+
+impl CastRecording {
+    pub fn duration(&self) -> f64 {
+        self.events.last().map(|event| event.time).unwrap_or(0.0)
+    }
+
+    // 指定时间点生效的终端尺寸 (最后一个 resize 事件或头部尺寸)
+    fn size_at(&self, index: usize) -> (u32, u32) {
+        self.events[..index]
+            .iter()
+            .rev()
+            .find(|event| event.code == "r")
+            .and_then(|event| parse_size(&event.data))
+            .unwrap_or((self.width, self.height))
+    }
+}
+
+fn parse_size(data: &str) -> Option<(u32, u32)> {
+    let (cols, rows) = data.split_once('x')?;
+    Some((cols.trim().parse().ok()?, rows.trim().parse().ok()?))
+}
+
+// 🟢 读取 asciicast v2 文件；无法解析的事件行跳过 (录制中途崩溃时最后一行可能不完整)
+pub fn load_cast(path: &Path) -> Result<CastRecording, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open recording: {}", e))?;
+    let mut lines = BufReader::new(file).lines();
+    let header_line = lines
+        .next()
+        .ok_or_else(|| "Recording is empty".to_string())?
+        .map_err(|e| e.to_string())?;
+    let header: Value = serde_json::from_str(&header_line)
+        .map_err(|e| format!("Invalid asciicast header: {}", e))?;
+    if header.get("version").and_then(Value::as_u64) != Some(2) {
+        return Err("Unsupported asciicast version (expected v2)".to_string());
+    }
+
+    let mut events = Vec::new();
+    for line in lines {
+        let Ok(line) = line else {
+            break;
+        };
+        let Ok(Value::Array(fields)) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if let [Value::Number(time), Value::String(code), Value::String(data)] = fields.as_slice() {
+            events.push(CastEvent {
+                time: time.as_f64().unwrap_or(0.0),
+                code: code.clone(),
+                data: data.clone(),
+            });
+        }
+    }
+
+    Ok(CastRecording {
+        width: header.get("width").and_then(Value::as_u64).unwrap_or(80) as u32,
+        height: header.get("height").and_then(Value::as_u64).unwrap_or(24) as u32,
+        events,
+    })
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PlaybackEvent {
+    Output { time: f64, data: String },
+    Input { time: f64, data: String },
+    Resize { time: f64, cols: u32, rows: u32 },
+    Marker { time: f64, label: String },
+    // 跳转后前端应清屏并按给定尺寸重置终端，随后收到补发的历史输出
+    Reset { time: f64, cols: u32, rows: u32 },
+    State { time: f64, paused: bool, speed: f64 },
+    Finished { time: f64 },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PlaybackAction {
+    Pause,
+    Resume,
+    Seek { time: f64 },
+    Speed { speed: f64 },
+    Stop,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackInfo {
+    pub playback_id: String,
+    pub duration: f64,
+    pub width: u32,
+    pub height: u32,
+    pub event_count: usize,
+}
+
+// 🟢 正在进行的回放，playback_id -> 控制通道
+#[derive(Default)]
+pub struct PlaybackRegistry {
+    controls: Arc<Mutex<HashMap<String, mpsc::Sender<PlaybackAction>>>>,
+}
+
+fn clamp_speed(speed: f64) -> f64 {
+    if speed.is_finite() {
+        speed.clamp(PLAYBACK_MIN_SPEED, PLAYBACK_MAX_SPEED)
+    } else {
+        1.0
+    }
+}
+
+fn to_playback_event(event: &CastEvent) -> Option<PlaybackEvent> {
+    let time = event.time;
+    match event.code.as_str() {
+        "o" => Some(PlaybackEvent::Output {
+            time,
+            data: event.data.clone(),
+        }),
+        "i" => Some(PlaybackEvent::Input {
+            time,
+            data: event.data.clone(),
+        }),
+        "r" => {
+            parse_size(&event.data).map(|(cols, rows)| PlaybackEvent::Resize { time, cols, rows })
+        }
+        "m" => Some(PlaybackEvent::Marker {
+            time,
+            label: event.data.clone(),
+        }),
+        _ => None,
+    }
+}
+
+struct PlaybackCursor {
+    recording: CastRecording,
+    channel: Channel<PlaybackEvent>,
+    index: usize,
+    position: f64,
+    speed: f64,
+    paused: bool,
+    finished: bool,
+    idle_time_limit: Option<f64>,
+}
+
+impl PlaybackCursor {
+    fn send(&self, event: PlaybackEvent) -> bool {
+        self.channel.send(event).is_ok()
+    }
+
+    fn send_state(&self) -> bool {
+        self.send(PlaybackEvent::State {
+            time: self.position,
+            paused: self.paused,
+            speed: self.speed,
+        })
+    }
+
+    // 跳转：重置终端后把目标时间点之前的全部输出一次性补发，终端状态即与原会话一致
+    fn seek(&mut self, time: f64) -> bool {
+        let time = time.clamp(0.0, self.recording.duration());
+        let events = &self.recording.events;
+        let index = events.partition_point(|event| event.time <= time);
+        let (cols, rows) = self.recording.size_at(index);
+        if !self.send(PlaybackEvent::Reset { time, cols, rows }) {
+            return false;
+        }
+
+        let mut chunk = String::new();
+        for event in events[..index].iter().filter(|event| event.code == "o") {
+            chunk.push_str(&event.data);
+            if chunk.len() >= PLAYBACK_SEEK_CHUNK_BYTES {
+                let data = std::mem::take(&mut chunk);
+                if !self.send(PlaybackEvent::Output { time, data }) {
+                    return false;
+                }
+            }
+        }
+        if !chunk.is_empty() && !self.send(PlaybackEvent::Output { time, data: chunk }) {
+            return false;
+        }
+
+        self.index = index;
+        self.position = time;
+        self.finished = false;
+        true
+    }
+
+    // 返回 false 表示结束回放
+    fn apply(&mut self, action: PlaybackAction) -> bool {
+        match action {
+            PlaybackAction::Pause => self.paused = true,
+            PlaybackAction::Resume => self.paused = false,
+            PlaybackAction::Speed { speed } => self.speed = clamp_speed(speed),
+            PlaybackAction::Seek { time } => {
+                if !self.seek(time) {
+                    return false;
+                }
+            }
+            PlaybackAction::Stop => return false,
+        }
+        self.send_state()
+    }
+
+    async fn run(mut self, mut control_rx: mpsc::Receiver<PlaybackAction>) {
+        loop {
+            if self.index >= self.recording.events.len() && !self.finished {
+                self.finished = true;
+                if !self.send(PlaybackEvent::Finished {
+                    time: self.position,
+                }) {
+                    return;
+                }
+            }
+
+            // 暂停或播放完毕：等待控制指令 (播放完毕后仍可跳转重播)
+            if self.paused || self.finished {
+                let Some(action) = control_rx.recv().await else {
+                    return;
+                };
+                if !self.apply(action) {
+                    return;
+                }
+                continue;
+            }
+
+            let event_time = self.recording.events[self.index].time;
+            let mut gap = (event_time - self.position).max(0.0);
+            if let Some(limit) = self.idle_time_limit {
+                gap = gap.min(limit);
+            }
+            let wait_started = Instant::now();
+
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs_f64(gap / self.speed)) => {
+                    self.position = event_time;
+                    let event = to_playback_event(&self.recording.events[self.index]);
+                    self.index += 1;
+                    if let Some(event) = event {
+                        if !self.send(event) {
+                            return;
+                        }
+                    }
+                }
+                action = control_rx.recv() => {
+                    let Some(action) = action else {
+                        return;
+                    };
+                    let waited = wait_started.elapsed().as_secs_f64() * self.speed;
+                    self.position = (self.position + waited).min(event_time);
+                    if !self.apply(action) {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn start_recording_playback(
+    app: AppHandle,
+    registry: State<'_, PlaybackRegistry>,
+    id: String,
+    on_event: Channel<PlaybackEvent>,
+    speed: Option<f64>,
+    start_at: Option<f64>,
+    idle_time_limit: Option<f64>,
+) -> Result<PlaybackInfo, String> {
+    let path = recording_path(&recordings_dir(&app)?, &id)?;
+    let recording = tokio::task::spawn_blocking(move || load_cast(&path))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let playback_id = Uuid::new_v4().to_string();
+    let info = PlaybackInfo {
+        playback_id: playback_id.clone(),
+        duration: recording.duration(),
+        width: recording.width,
+        height: recording.height,
+        event_count: recording.events.len(),
+    };
+
+    let (control_tx, control_rx) = mpsc::channel(PLAYBACK_CONTROL_CAPACITY);
+    match registry.controls.lock() {
+        Ok(mut map) => map.insert(playback_id.clone(), control_tx),
+        Err(poisoned) => poisoned
+            .into_inner()
+            .insert(playback_id.clone(), control_tx),
+    };
+
+    let mut cursor = PlaybackCursor {
+        recording,
+        channel: on_event,
+        index: 0,
+        position: 0.0,
+        speed: clamp_speed(speed.unwrap_or(1.0)),
+        paused: false,
+        finished: false,
+        idle_time_limit: idle_time_limit.filter(|limit| *limit > 0.0),
+    };
+    let controls = registry.controls.clone();
+    tokio::spawn(async move {
+        let started = match start_at.filter(|time| *time > 0.0) {
+            Some(time) => cursor.seek(time),
+            None => cursor.send_state(),
+        };
+        if started {
+            cursor.run(control_rx).await;
+        }
+        match controls.lock() {
+            Ok(mut map) => map.remove(&playback_id),
+            Err(poisoned) => poisoned.into_inner().remove(&playback_id),
+        };
+    });
+
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn control_recording_playback(
+    registry: State<'_, PlaybackRegistry>,
+    playback_id: String,
+    action: PlaybackAction,
+) -> Result<(), String> {
+    let control_tx = match registry.controls.lock() {
+        Ok(map) => map.get(&playback_id).cloned(),
+        Err(poisoned) => poisoned.into_inner().get(&playback_id).cloned(),
+    }
+    .ok_or_else(|| "Playback is not active".to_string())?;
+
+    control_tx
+        .send(action)
+        .await
+        .map_err(|_| "Playback is not active".to_string())
+}
@@ -0,0 +1,338 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{Local, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+pub const RECORDINGS_DIR_NAME: &str = "recordings";
+pub const RECORDING_EXTENSION: &str = "cast";
+const POLICY_FILE_NAME: &str = "policy.json";
+const RECORDING_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+pub const DEFAULT_MAX_RECORDING_BYTES: u64 = 100 * 1024 * 1024;
+pub const DEFAULT_MAX_TOTAL_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+// 🟢 录像策略：单个文件上限、目录总量上限与保留天数 (0 表示不限制)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingPolicy {
+    #[serde(default = "default_max_recording_bytes")]
+    pub max_recording_bytes: u64,
+    #[serde(default = "default_max_total_bytes")]
+    pub max_total_bytes: u64,
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_max_recording_bytes() -> u64 {
+    DEFAULT_MAX_RECORDING_BYTES
+}
+
+fn default_max_total_bytes() -> u64 {
+    DEFAULT_MAX_TOTAL_BYTES
+}
+
+fn default_retention_days() -> u32 {
+    DEFAULT_RETENTION_DAYS
+}
+
+impl Default for RecordingPolicy {
+    fn default() -> Self {
+        Self {
+            max_recording_bytes: DEFAULT_MAX_RECORDING_BYTES,
+            max_total_bytes: DEFAULT_MAX_TOTAL_BYTES,
+            retention_days: DEFAULT_RETENTION_DAYS,
+        }
+    }
+}
+
+// 录像列表项，元数据取自 asciicast 头部的 piterm 扩展字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingInfo {
+    pub id: String,
+    pub file_name: String,
+    pub session_id: String,
+    pub server_id: String,
+    pub title: String,
+    pub started_at: i64,
+    pub updated_at: i64,
+    pub size_bytes: u64,
+    pub include_input: bool,
+    pub active: bool,
+}
+
+pub struct RecordingTarget<'a> {
+    pub session_id: &'a str,
+    pub server_id: &'a str,
+    pub title: &'a str,
+    // 录制时 PTY 请求的终端类型，写入头部 env.TERM
+    pub term: &'a str,
+    pub cols: u32,
+    pub rows: u32,
+    pub include_input: bool,
+}
+
+// 🟢 asciicast v2 写入器：首行为头部 JSON，之后每行一个 [time, code, data] 事件
+pub struct SessionRecorder {
+    id: String,
+    writer: BufWriter<File>,
+    started: Instant,
+    last_flush: Instant,
+    bytes_written: u64,
+    max_bytes: u64,
+    include_input: bool,
+    // 达到大小上限后不再写入，但保持挂载以免自动重新开始录制
+    stopped: bool,
+}
+
+impl SessionRecorder {
+    pub fn create(
+        dir: &Path,
+        target: &RecordingTarget<'_>,
+        policy: &RecordingPolicy,
+    ) -> Result<Self, String> {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+
+        let short_session: String = target
+            .session_id
+            .chars()
+            .filter(|ch| ch.is_ascii_alphanumeric())
+            .take(8)
+            .collect();
+        let id = format!(
+            "{}-{}",
+            Local::now().format("%Y%m%d-%H%M%S%3f"),
+            if short_session.is_empty() {
+                "session".to_string()
+            } else {
+                short_session
+            }
+        );
+        let path = dir.join(format!("{}.{}", id, RECORDING_EXTENSION));
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to create recording {}: {}", path.display(), e))?;
+
+        let header = json!({
+            "version": 2,
+            "width": target.cols,
+            "height": target.rows,
+            "timestamp": Utc::now().timestamp(),
+            "title": target.title,
+            "env": { "TERM": target.term },
+            "piterm": {
+                "sessionId": target.session_id,
+                "serverId": target.server_id,
+                "includeInput": target.include_input,
+            },
+        });
+
+        let mut recorder = Self {
+            id,
+            writer: BufWriter::new(file),
+            started: Instant::now(),
+            last_flush: Instant::now(),
+            bytes_written: 0,
+            max_bytes: policy.max_recording_bytes,
+            include_input: target.include_input,
+            stopped: false,
+        };
+        recorder.write_line(&header.to_string())?;
+        recorder.flush();
+        Ok(recorder)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn record_output(&mut self, text: &str) {
+        self.record_event("o", text);
+    }
+
+    pub fn record_input(&mut self, text: &str) {
+        if self.include_input {
+            self.record_event("i", text);
+        }
+    }
+
+    pub fn record_resize(&mut self, cols: u32, rows: u32) {
+        self.record_event("r", &format!("{}x{}", cols, rows));
+    }
+
+    pub fn finish(mut self) {
+        self.flush();
+    }
+
+    fn record_event(&mut self, code: &str, data: &str) {
+        if self.stopped || data.is_empty() {
+            return;
+        }
+
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let line = Value::Array(vec![
+            json!((elapsed * 1_000_000.0).round() / 1_000_000.0),
+            Value::String(code.to_string()),
+            Value::String(data.to_string()),
+        ])
+        .to_string();
+
+        // 超过上限时写入一个标记事件并停止录制
+        if self.max_bytes > 0 && self.bytes_written + line.len() as u64 + 1 > self.max_bytes {
+            self.stopped = true;
+            let marker = json!([elapsed, "m", "recording size limit reached"]).to_string();
+            let _ = self.write_line(&marker);
+            self.flush();
+            return;
+        }
+
+        if self.write_line(&line).is_err() {
+            self.stopped = true;
+            return;
+        }
+        if self.last_flush.elapsed() >= RECORDING_FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), String> {
+        self.writer
+            .write_all(line.as_bytes())
+            .and_then(|_| self.writer.write_all(b"\n"))
+            .map_err(|e| e.to_string())?;
+        self.bytes_written += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        let _ = self.writer.flush();
+        self.last_flush = Instant::now();
+    }
+}
+
+pub fn recordings_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(app_dir.join(RECORDINGS_DIR_NAME))
+}
+
+// 录像 ID 直接映射为文件名，只允许安全字符以防路径穿越
+pub fn recording_path(dir: &Path, id: &str) -> Result<PathBuf, String> {
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    if !valid {
+        return Err(format!("Invalid recording id: {}", id));
+    }
+    Ok(dir.join(format!("{}.{}", id, RECORDING_EXTENSION)))
+}
+
+pub fn load_policy(dir: &Path) -> RecordingPolicy {
+    fs::read_to_string(dir.join(POLICY_FILE_NAME))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_policy(dir: &Path, policy: &RecordingPolicy) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let content = serde_json::to_string_pretty(policy).map_err(|e| e.to_string())?;
+    fs::write(dir.join(POLICY_FILE_NAME), content).map_err(|e| e.to_string())
+}
+
+pub fn read_recording_info(path: &Path) -> Option<RecordingInfo> {
+    let id = path.file_stem()?.to_str()?.to_string();
+    let metadata = fs::metadata(path).ok()?;
+    let mut header_line = String::new();
+    BufReader::new(File::open(path).ok()?)
+        .read_line(&mut header_line)
+        .ok()?;
+    let header: Value = serde_json::from_str(&header_line).ok()?;
+    let extension = header.get("piterm");
+    let text_field = |value: Option<&Value>, key: &str| {
+        value
+            .and_then(|v| v.get(key))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let updated_at = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default();
+
+    Some(RecordingInfo {
+        file_name: path.file_name()?.to_string_lossy().into_owned(),
+        id,
+        session_id: text_field(extension, "sessionId"),
+        server_id: text_field(extension, "serverId"),
+        title: text_field(Some(&header), "title"),
+        started_at: header
+            .get("timestamp")
+            .and_then(Value::as_i64)
+            .unwrap_or_default()
+            * 1000,
+        updated_at,
+        size_bytes: metadata.len(),
+        include_input: extension
+            .and_then(|v| v.get("includeInput"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        active: false,
+    })
+}
+
+pub fn scan_recordings(dir: &Path) -> Vec<RecordingInfo> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut recordings: Vec<RecordingInfo> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(RECORDING_EXTENSION))
+        .filter_map(|path| read_recording_info(&path))
+        .collect();
+    recordings.sort_by(|a, b| b.started_at.cmp(&a.started_at).then(b.id.cmp(&a.id)));
+    recordings
+}
+
+// 🟢 按保留天数与总量上限清理旧录像，正在录制的文件不删除；返回删除数量
+pub fn apply_retention(dir: &Path, policy: &RecordingPolicy, active_ids: &[String]) -> usize {
+    let now_ms = Utc::now().timestamp_millis();
+    let max_age_ms = i64::from(policy.retention_days) * 24 * 60 * 60 * 1000;
+    let mut removed = 0;
+    let mut kept_bytes = 0u64;
+
+    // 新的在前：累计到超出总量后，更旧的都删除
+    for recording in scan_recordings(dir) {
+        if active_ids.contains(&recording.id) {
+            kept_bytes += recording.size_bytes;
+            continue;
+        }
+        let expired = policy.retention_days > 0 && now_ms - recording.updated_at > max_age_ms;
+        let over_quota = policy.max_total_bytes > 0
+            && kept_bytes + recording.size_bytes > policy.max_total_bytes;
+        if expired || over_quota {
+            if fs::remove_file(dir.join(&recording.file_name)).is_ok() {
+                removed += 1;
+            }
+            continue;
+        }
+        kept_bytes += recording.size_bytes;
+    }
+    removed
+}
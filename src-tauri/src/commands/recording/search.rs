@@ -0,0 +1,146 @@
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::utils::ansi::AnsiStripper;
+
+use super::playback::{load_cast, CastRecording};
+use super::recorder::{recordings_dir, scan_recordings, RecordingInfo};
+
+const DEFAULT_SEARCH_LIMIT: usize = 200;
+const SNIPPET_MAX_CHARS: usize = 160;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingSearchHit {
+    pub recording_id: String,
+    pub server_id: String,
+    pub title: String,
+    pub started_at: i64,
+    // 命中文本所在输出事件的录像时间 (秒)，回放时据此跳转
+    pub time: f64,
+    pub snippet: String,
+}
+
+// 去除控制序列后的纯文本，附带每段文本起始偏移对应的事件时间
+struct SearchCorpus {
+    text: String,
+    offsets: Vec<(usize, f64)>,
+}
+
+impl SearchCorpus {
+    fn time_at(&self, offset: usize) -> f64 {
+        let index = self.offsets.partition_point(|(start, _)| *start <= offset);
+        self.offsets[index.saturating_sub(1)].1
+    }
+
+    // 命中位置所在的整行，过长时截取命中附近的部分
+    fn snippet_at(&self, offset: usize) -> String {
+        let line_start = self.text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = self.text[offset..]
+            .find('\n')
+            .map(|i| offset + i)
+            .unwrap_or(self.text.len());
+        let line = &self.text[line_start..line_end];
+        let before = self.text[line_start..offset].chars().count();
+        line.chars()
+            .skip(before.saturating_sub(SNIPPET_MAX_CHARS / 2))
+            .take(SNIPPET_MAX_CHARS)
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+}
+
+fn build_corpus(recording: &CastRecording) -> SearchCorpus {
+    let mut stripper = AnsiStripper::default();
+    let mut corpus = SearchCorpus {
+        text: String::new(),
+        offsets: Vec::new(),
+    };
+    for event in recording.events.iter().filter(|event| event.code == "o") {
+        let start = corpus.text.len();
+        stripper.push(&event.data, &mut corpus.text);
+        if corpus.text.len() > start {
+            corpus.offsets.push((start, event.time));
+        }
+    }
+    corpus
+}
+
+// 🟢 同一行内的多次命中只报告一次，避免刷屏输出产生大量重复结果
+fn search_recording(
+    info: &RecordingInfo,
+    corpus: &SearchCorpus,
+    pattern: &Regex,
+    hits: &mut Vec<RecordingSearchHit>,
+    limit: usize,
+) {
+    let mut last_line_start = None;
+    for found in pattern.find_iter(&corpus.text) {
+        if hits.len() >= limit {
+            return;
+        }
+        let offset = found.start();
+        let line_start = corpus.text[..offset]
+            .rfind('\n')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        if last_line_start == Some(line_start) {
+            continue;
+        }
+        last_line_start = Some(line_start);
+        hits.push(RecordingSearchHit {
+            recording_id: info.id.clone(),
+            server_id: info.server_id.clone(),
+            title: info.title.clone(),
+            started_at: info.started_at,
+            time: corpus.time_at(offset),
+            snippet: corpus.snippet_at(offset),
+        });
+    }
+}
+
+#[tauri::command]
+pub async fn search_recordings(
+    app: AppHandle,
+    query: String,
+    server_id: Option<String>,
+    case_sensitive: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<RecordingSearchHit>, String> {
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let dir = recordings_dir(&app)?;
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT).max(1);
+    // 按字面量匹配，不区分大小写时交给正则引擎处理 (偏移始终对应原文)
+    let pattern = RegexBuilder::new(&regex::escape(&query))
+        .case_insensitive(!case_sensitive.unwrap_or(false))
+        .build()
+        .map_err(|e| format!("Invalid search query: {}", e))?;
+
+    tokio::task::spawn_blocking(move || {
+        let mut hits = Vec::new();
+        for info in scan_recordings(&dir) {
+            if server_id
+                .as_deref()
+                .is_some_and(|server_id| server_id != info.server_id)
+            {
+                continue;
+            }
+            let Ok(recording) = load_cast(&dir.join(&info.file_name)) else {
+                continue;
+            };
+            let corpus = build_corpus(&recording);
+            search_recording(&info, &corpus, &pattern, &mut hits, limit);
+            if hits.len() >= limit {
+                break;
+            }
+        }
+        hits
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
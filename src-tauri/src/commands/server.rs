@@ -1,8 +1,13 @@
+use crate::commands::ssh::core::{validate_session_log_template, validate_shell_options};
+use crate::commands::ssh::utils::normalize_encoding_label;
 use crate::commands::vault::{internal_record_usage, VaultState}; // 🟢 引入 internal_record_usage
-use crate::models::{AuthType, ConnectionType, OsType, ServerConfig};
+use crate::models::{
+    AuthType, ConnectionType, OsType, PersistentSessionMode, ServerConfig, SessionLogConfig,
+    ShellOptions,
+};
 use crate::state::AppState;
 use chrono::Utc;
-use sqlx::Row;
+use sqlx::{Row, SqliteConnection};
 use tauri::{command, State};
 
 // =========================================================
@@ -24,6 +29,19 @@ pub async fn list_servers(state: State<'_, AppState>) -> Result<Vec<ServerConfig
     for row in rows {
         let tags_str: String = row.try_get("tags").unwrap_or("[]".to_string());
         let tags: Vec<String> = serde_json::from_str(&tags_str).unwrap_or_default();
+        let jump_host_ids_str: String = row.try_get("jump_host_ids").unwrap_or("[]".to_string());
+        let jump_host_ids: Vec<String> =
+            serde_json::from_str(&jump_host_ids_str).unwrap_or_default();
+        let session_log: Option<SessionLogConfig> = row
+            .try_get::<Option<String>, _>("session_log")
+            .ok()
+            .flatten()
+            .and_then(|raw| serde_json::from_str(&raw).ok());
+        let shell_options: Option<ShellOptions> = row
+            .try_get::<Option<String>, _>("shell_options")
+            .ok()
+            .flatten()
+            .and_then(|raw| serde_json::from_str(&raw).ok());
 
         servers.push(ServerConfig {
             id: row.try_get("id").unwrap_or_default(),
@@ -65,6 +83,19 @@ pub async fn list_servers(state: State<'_, AppState>) -> Result<Vec<ServerConfig
             keep_alive_interval: row.try_get("keep_alive_interval").ok(),
             auto_reconnect: row.try_get("auto_reconnect").ok(),
             max_reconnects: row.try_get("max_reconnects").ok(),
+            host_key_policy: row.try_get("host_key_policy").ok(),
+            jump_host_ids,
+            agent_forwarding: row.try_get("agent_forwarding").unwrap_or(false),
+            split_transport: row.try_get("split_transport").unwrap_or(false),
+            encoding: row.try_get("encoding").ok(),
+            record_sessions: row.try_get("record_sessions").unwrap_or(false),
+            record_input: row.try_get("record_input").unwrap_or(false),
+            session_log,
+            persistent_session: row
+                .try_get::<Option<PersistentSessionMode>, _>("persistent_session")
+                .ok()
+                .flatten(),
+            shell_options,
         });
     }
 
@@ -303,18 +334,52 @@ pub async fn save_server(
     server.updated_at = now;
 
     // 4. 存入数据库
+    if server.jump_host_ids.iter().any(|id| id == &server.id) {
+        return Err("A server cannot use itself as a jump host".to_string());
+    }
+    server.encoding = normalize_encoding_label(server.encoding.as_deref())?;
+    if let Some(session_log) = &server.session_log {
+        validate_session_log_template(&session_log.path_template)?;
+    }
+    if let Some(shell_options) = &server.shell_options {
+        validate_shell_options(shell_options)?;
+    }
+    upsert_server_row(&mut tx, server).await?;
+
+    tx.commit().await.map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(())
+}
+
+// 🟢 不能用 INSERT OR REPLACE：SQLite 会先删除旧行，触发 port_forwards / triggers 等子表的级联删除
+async fn upsert_server_row(
+    conn: &mut SqliteConnection,
+    server: ServerConfig,
+) -> Result<(), String> {
     let tags_json = serde_json::to_string(&server.tags).unwrap_or("[]".to_string());
+    let jump_host_ids_json =
+        serde_json::to_string(&server.jump_host_ids).unwrap_or("[]".to_string());
+    let session_log_json = server
+        .session_log
+        .as_ref()
+        .and_then(|config| serde_json::to_string(config).ok());
+    let shell_options_json = server
+        .shell_options
+        .as_ref()
+        .and_then(|options| serde_json::to_string(options).ok());
 
     sqlx::query(
         r#"
-        INSERT OR REPLACE INTO servers (
+        INSERT INTO servers (
             id, name, icon, provider, theme, sort, ip, port, tags, 
             connection_type, proxy_id, auth_type, username, 
             password, private_key, passphrase, 
             password_id, password_source, key_id, key_source, private_key_remark,
             os, is_pinned, enable_expiration, expire_date,
             created_at, updated_at, last_connected_at,
-            connect_timeout, keep_alive_interval, auto_reconnect, max_reconnects
+            connect_timeout, keep_alive_interval, auto_reconnect, max_reconnects,
+            host_key_policy, jump_host_ids, agent_forwarding, split_transport, encoding,
+            record_sessions, record_input, session_log, persistent_session, shell_options
         ) VALUES (
             ?, ?, ?, ?, ?, ?, ?, ?, ?, 
             ?, ?, ?, ?, 
@@ -322,8 +387,52 @@ pub async fn save_server(
             ?, ?, ?, ?, ?,
             ?, ?, ?, ?,
             ?, ?, ?,
-            ?, ?, ?, ?
+            ?, ?, ?, ?,
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?
         )
+        ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            icon = excluded.icon,
+            provider = excluded.provider,
+            theme = excluded.theme,
+            sort = excluded.sort,
+            ip = excluded.ip,
+            port = excluded.port,
+            tags = excluded.tags,
+            connection_type = excluded.connection_type,
+            proxy_id = excluded.proxy_id,
+            auth_type = excluded.auth_type,
+            username = excluded.username,
+            password = excluded.password,
+            private_key = excluded.private_key,
+            passphrase = excluded.passphrase,
+            password_id = excluded.password_id,
+            password_source = excluded.password_source,
+            key_id = excluded.key_id,
+            key_source = excluded.key_source,
+            private_key_remark = excluded.private_key_remark,
+            os = excluded.os,
+            is_pinned = excluded.is_pinned,
+            enable_expiration = excluded.enable_expiration,
+            expire_date = excluded.expire_date,
+            created_at = excluded.created_at,
+            updated_at = excluded.updated_at,
+            last_connected_at = excluded.last_connected_at,
+            connect_timeout = excluded.connect_timeout,
+            keep_alive_interval = excluded.keep_alive_interval,
+            auto_reconnect = excluded.auto_reconnect,
+            max_reconnects = excluded.max_reconnects,
+            host_key_policy = excluded.host_key_policy,
+            jump_host_ids = excluded.jump_host_ids,
+            agent_forwarding = excluded.agent_forwarding,
+            split_transport = excluded.split_transport,
+            encoding = excluded.encoding,
+            record_sessions = excluded.record_sessions,
+            record_input = excluded.record_input,
+            session_log = excluded.session_log,
+            persistent_session = excluded.persistent_session,
+            shell_options = excluded.shell_options
         "#,
     )
     .bind(server.id)
@@ -358,12 +467,20 @@ pub async fn save_server(
     .bind(server.keep_alive_interval)
     .bind(server.auto_reconnect)
     .bind(server.max_reconnects)
-    .execute(&mut *tx)
+    .bind(server.host_key_policy.unwrap_or_default())
+    .bind(jump_host_ids_json)
+    .bind(server.agent_forwarding)
+    .bind(server.split_transport)
+    .bind(server.encoding)
+    .bind(server.record_sessions)
+    .bind(server.record_input)
+    .bind(session_log_json)
+    .bind(server.persistent_session)
+    .bind(shell_options_json)
+    .execute(conn)
     .await
     .map_err(|e| format!("保存服务器失败: {}", e))?;
 
-    tx.commit().await.map_err(|e| format!("Failed to commit transaction: {}", e))?;
-
     Ok(())
 }
 
@@ -419,3 +536,82 @@ pub async fn update_last_connected(state: State<'_, AppState>, id: String) -> Re
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::{Pool, Sqlite};
+
+    async fn test_pool() -> Pool<Sqlite> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    fn test_server(name: &str) -> ServerConfig {
+        serde_json::from_value(serde_json::json!({
+            "id": "srv-1",
+            "name": name,
+            "ip": "10.0.0.1",
+        }))
+        .unwrap()
+    }
+
+    async fn save(pool: &Pool<Sqlite>, server: ServerConfig) {
+        let mut conn = pool.acquire().await.unwrap();
+        upsert_server_row(&mut conn, server).await.unwrap();
+    }
+
+    async fn count_rows(pool: &Pool<Sqlite>, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {} WHERE server_id = 'srv-1'", table))
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn resaving_server_keeps_port_forwards() {
+        let pool = test_pool().await;
+        save(&pool, test_server("web")).await;
+        sqlx::query(
+            "INSERT INTO port_forwards (
+                id, server_id, bind_port, target_host, target_port, created_at, updated_at
+            ) VALUES ('fwd-1', 'srv-1', 8080, 'localhost', 80, 0, 0)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        save(&pool, test_server("web-renamed")).await;
+        assert_eq!(count_rows(&pool, "port_forwards").await, 1);
+
+        // 外键级联仍然生效：删除服务器时清理转发规则
+        sqlx::query("DELETE FROM servers WHERE id = 'srv-1'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count_rows(&pool, "port_forwards").await, 0);
+    }
+
+    #[tokio::test]
+    async fn resaving_server_keeps_triggers() {
+        let pool = test_pool().await;
+        save(&pool, test_server("db")).await;
+        sqlx::query(
+            "INSERT INTO triggers (
+                id, server_id, pattern, action, payload, created_at, updated_at
+            ) VALUES ('trg-1', 'srv-1', '\\(yes/no\\)', 'send_text', 'yes', 0, 0)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        save(&pool, test_server("db-renamed")).await;
+        assert_eq!(count_rows(&pool, "triggers").await, 1);
+    }
+}
@@ -1,7 +1,18 @@
 use crate::models::{Snippet, SnippetDto};
 use crate::state::AppState;
+use sqlx::{Pool, Sqlite};
 use tauri::State;
 
+// 🟢 供触发器 / 启动命令读取片段内容
+pub async fn internal_get_snippet_code(pool: &Pool<Sqlite>, id: &str) -> Result<String, String> {
+    sqlx::query_scalar::<_, String>("SELECT code FROM snippets WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Snippet {} not found", id))
+}
+
 #[tauri::command]
 pub async fn get_all_snippets(state: State<'_, AppState>) -> Result<Vec<SnippetDto>, String> {
     // 1. 从数据库查询所有记录 (Snippet 结构体)
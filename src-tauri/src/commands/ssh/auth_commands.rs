@@ -0,0 +1,49 @@
+use tauri::State;
+
+use crate::utils::ssh_log::{self, SshLogRecord};
+
+use super::state::AuthPromptRegistry;
+
+// 🟢 前端回答 keyboard-interactive 提示；responses 为 None 表示用户取消
+#[tauri::command]
+pub async fn respond_auth_prompt(
+    registry: State<'_, AuthPromptRegistry>,
+    request_id: String,
+    responses: Option<Vec<String>>,
+) -> Result<(), String> {
+    let pending = {
+        let mut pending = match registry.pending.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        let entry = pending
+            .get(&request_id)
+            .ok_or_else(|| "Authentication prompt expired or already answered.".to_string())?;
+        if let Some(answers) = responses.as_ref() {
+            if answers.len() != entry.prompt_count {
+                return Err(format!(
+                    "Expected {} answers, got {}",
+                    entry.prompt_count,
+                    answers.len()
+                ));
+            }
+        }
+        pending.remove(&request_id)
+    }
+    .ok_or_else(|| "Authentication prompt expired or already answered.".to_string())?;
+
+    ssh_log::debug(
+        SshLogRecord::new(
+            "ssh.auth",
+            "keyboard_interactive_answered",
+            "Received keyboard-interactive answers from the frontend",
+        )
+        .field("request_id", request_id)
+        .field("cancelled", responses.is_none()),
+    );
+
+    pending
+        .response_tx
+        .send(responses)
+        .map_err(|_| "Authentication attempt is no longer waiting for answers.".to_string())
+}
@@ -8,7 +8,10 @@ use crate::models::SshConfig;
 use crate::utils::ssh_log::{self, SshLogRecord};
 
 use super::core;
-use super::state::{get_ssh_session_if_instance, BackgroundSessionEvent, SshConnection};
+use super::state::{
+    disconnect_jump_sessions, get_ssh_session_if_instance, BackgroundSessionEvent, JumpSessions,
+    SshConnection, SshSession,
+};
 
 const SSH_BACKGROUND_CONNECT_RETRY_DELAY: Duration = Duration::from_secs(3);
 const SSH_BACKGROUND_CONNECT_MAX_ATTEMPTS: u8 = 3;
@@ -28,7 +31,41 @@ fn emit_background_session_event(
     );
 }
 
-pub(super) fn spawn_background_session_connector(
+// 🟢 单传输模式直接复用 shell 传输；拆分模式另建一条后台传输
+pub(super) fn start_background_session(
+    app: AppHandle,
+    sessions: Arc<Mutex<HashMap<String, SshConnection>>>,
+    config: SshConfig,
+    session_id: String,
+    server_id: String,
+    instance_id: u64,
+) {
+    if config.split_transport {
+        spawn_background_session_connector(
+            app,
+            sessions,
+            config,
+            session_id,
+            server_id,
+            instance_id,
+        );
+        return;
+    }
+
+    ssh_log::info(
+        SshLogRecord::new(
+            "ssh.command",
+            "background_session_shared",
+            "Background channels share the shell SSH transport",
+        )
+        .session_id(session_id.clone())
+        .server_id(server_id)
+        .instance_id(instance_id),
+    );
+    emit_background_session_event(&app, &session_id, "ready", None);
+}
+
+fn spawn_background_session_connector(
     app: AppHandle,
     sessions: Arc<Mutex<HashMap<String, SshConnection>>>,
     config: SshConfig,
@@ -71,9 +108,9 @@ pub(super) fn spawn_background_session_connector(
                 .field("max_attempts", SSH_BACKGROUND_CONNECT_MAX_ATTEMPTS),
             );
 
-            let base_sess_res: Result<crate::commands::ssh::state::SshSession, String> = core::establish_base_session(&config, Some(&session_id), "background").await;
+            let base_sess_res: Result<(SshSession, JumpSessions), String> = core::establish_base_session(&app, &config, Some(&session_id), "background").await;
             match base_sess_res {
-                Ok(bg_session) => {
+                Ok((bg_session, bg_jump_sessions)) => {
                     let Some(conn) =
                         get_ssh_session_if_instance(&sessions, &session_id, instance_id)
                     else {
@@ -82,6 +119,10 @@ pub(super) fn spawn_background_session_connector(
                             "PiTerm background session abandoned",
                             "en",
                         ).await;
+                        disconnect_jump_sessions(
+                            bg_jump_sessions,
+                            "PiTerm background session abandoned",
+                        ).await;
                         ssh_log::debug(
                             SshLogRecord::new(
                                 "ssh.command",
@@ -96,7 +137,7 @@ pub(super) fn spawn_background_session_connector(
                         return;
                     };
 
-                    conn.install_bg_session(Arc::new(bg_session));
+                    conn.install_bg_session(bg_session, bg_jump_sessions);
                     ssh_log::info(
                         SshLogRecord::new(
                             "ssh.command",
@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use futures_util::future::join_all;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::state::AppState;
+use crate::utils::ssh_log::{self, SshLogRecord};
+
+use super::runtime::queue_shell_write;
+use super::state::SshState;
+
+// 带有这些标签的服务器视为生产环境 (不区分大小写)
+const PRODUCTION_TAGS: &[&str] = &["production", "prod"];
+// 安全模式下确认一次后的有效期，成员变化时立即失效
+const BROADCAST_CONFIRMATION_TTL: Duration = Duration::from_secs(15 * 60);
+
+struct BroadcastMember {
+    session_id: String,
+    server_id: String,
+    server_name: String,
+    production: bool,
+    // 临时退出广播，仍保留在组内
+    paused: bool,
+}
+
+struct BroadcastGroup {
+    id: String,
+    name: String,
+    members: Vec<BroadcastMember>,
+    safety_mode: bool,
+    confirmed_at: Option<Instant>,
+}
+
+impl BroadcastGroup {
+    fn requires_confirmation(&self) -> bool {
+        self.safety_mode
+            && self
+                .members
+                .iter()
+                .any(|member| member.production && !member.paused)
+    }
+
+    fn is_confirmed(&self) -> bool {
+        self.confirmed_at
+            .map(|at| at.elapsed() < BROADCAST_CONFIRMATION_TTL)
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Default)]
+pub struct BroadcastRegistry {
+    groups: Mutex<HashMap<String, BroadcastGroup>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastMemberInfo {
+    pub session_id: String,
+    pub server_id: String,
+    pub server_name: String,
+    pub production: bool,
+    pub paused: bool,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastGroupInfo {
+    pub id: String,
+    pub name: String,
+    pub members: Vec<BroadcastMemberInfo>,
+    pub safety_mode: bool,
+    pub requires_confirmation: bool,
+    pub confirmed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastFailure {
+    pub session_id: String,
+    pub server_name: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastWriteResult {
+    pub delivered: Vec<String>,
+    pub skipped: Vec<String>,
+    pub failures: Vec<BroadcastFailure>,
+}
+
+fn lock_groups(
+    registry: &BroadcastRegistry,
+) -> std::sync::MutexGuard<'_, HashMap<String, BroadcastGroup>> {
+    match registry.groups.lock() {
+        Ok(g) => g,
+        Err(p) => p.into_inner(),
+    }
+}
+
+fn group_info(group: &BroadcastGroup, ssh_state: &SshState) -> BroadcastGroupInfo {
+    let map = match ssh_state.sessions.lock() {
+        Ok(g) => g,
+        Err(p) => p.into_inner(),
+    };
+    BroadcastGroupInfo {
+        id: group.id.clone(),
+        name: group.name.clone(),
+        members: group
+            .members
+            .iter()
+            .map(|member| BroadcastMemberInfo {
+                session_id: member.session_id.clone(),
+                server_id: member.server_id.clone(),
+                server_name: member.server_name.clone(),
+                production: member.production,
+                paused: member.paused,
+                active: map
+                    .get(&member.session_id)
+                    .map(|conn| conn.shell_is_active())
+                    .unwrap_or(false),
+            })
+            .collect(),
+        safety_mode: group.safety_mode,
+        requires_confirmation: group.requires_confirmation(),
+        confirmed: group.is_confirmed(),
+    }
+}
+
+async fn is_production_server(db_pool: &SqlitePool, server_id: &str) -> bool {
+    let tags: Vec<String> = sqlx::query("SELECT tags FROM servers WHERE id = ?")
+        .bind(server_id)
+        .fetch_optional(db_pool)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|row| row.try_get::<Option<String>, _>("tags").ok().flatten())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    tags.iter().any(|tag| {
+        PRODUCTION_TAGS
+            .iter()
+            .any(|production| tag.trim().eq_ignore_ascii_case(production))
+    })
+}
+
+// 🟢 根据会话 ID 解析成员，并按服务器标签判断是否为生产环境
+async fn resolve_members(
+    db_pool: &SqlitePool,
+    ssh_state: &SshState,
+    session_ids: &[String],
+) -> Result<Vec<BroadcastMember>, String> {
+    let configs: Vec<(String, String, String)> = {
+        let map = match ssh_state.sessions.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        let mut configs = Vec::with_capacity(session_ids.len());
+        for session_id in session_ids {
+            if configs.iter().any(|(id, _, _)| id == session_id) {
+                continue;
+            }
+            let conn = map
+                .get(session_id)
+                .ok_or_else(|| format!("SSH connection not active: {}", session_id))?;
+            let server_name = conn
+                .config
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{}@{}", conn.config.username, conn.config.host));
+            configs.push((session_id.clone(), conn.config.id.clone(), server_name));
+        }
+        configs
+    };
+
+    let mut members = Vec::with_capacity(configs.len());
+    for (session_id, server_id, server_name) in configs {
+        members.push(BroadcastMember {
+            production: is_production_server(db_pool, &server_id).await,
+            session_id,
+            server_id,
+            server_name,
+            paused: false,
+        });
+    }
+    Ok(members)
+}
+
+#[tauri::command]
+pub async fn create_broadcast_group(
+    app_state: State<'_, AppState>,
+    ssh_state: State<'_, SshState>,
+    registry: State<'_, BroadcastRegistry>,
+    name: String,
+    session_ids: Vec<String>,
+    safety_mode: Option<bool>,
+) -> Result<BroadcastGroupInfo, String> {
+    let members = resolve_members(&app_state.db, &ssh_state, &session_ids).await?;
+    let group = BroadcastGroup {
+        id: Uuid::new_v4().to_string(),
+        name: name.trim().to_string(),
+        members,
+        safety_mode: safety_mode.unwrap_or(true),
+        confirmed_at: None,
+    };
+    let info = group_info(&group, &ssh_state);
+    ssh_log::info(
+        SshLogRecord::new("ssh.broadcast", "group_created", "Created broadcast group")
+            .field("group_id", group.id.clone())
+            .field("members", group.members.len())
+            .field("requires_confirmation", info.requires_confirmation),
+    );
+    lock_groups(&registry).insert(group.id.clone(), group);
+    Ok(info)
+}
+
+// 替换成员列表；保留原有成员的暂停状态，成员变化后需要重新确认
+#[tauri::command]
+pub async fn update_broadcast_members(
+    app_state: State<'_, AppState>,
+    ssh_state: State<'_, SshState>,
+    registry: State<'_, BroadcastRegistry>,
+    group_id: String,
+    session_ids: Vec<String>,
+) -> Result<BroadcastGroupInfo, String> {
+    let mut members = resolve_members(&app_state.db, &ssh_state, &session_ids).await?;
+    let mut groups = lock_groups(&registry);
+    let group = groups
+        .get_mut(&group_id)
+        .ok_or_else(|| "Broadcast group not found".to_string())?;
+    for member in &mut members {
+        member.paused = group
+            .members
+            .iter()
+            .any(|existing| existing.session_id == member.session_id && existing.paused);
+    }
+    group.members = members;
+    group.confirmed_at = None;
+    Ok(group_info(group, &ssh_state))
+}
+
+#[tauri::command]
+pub fn set_broadcast_member_paused(
+    ssh_state: State<'_, SshState>,
+    registry: State<'_, BroadcastRegistry>,
+    group_id: String,
+    session_id: String,
+    paused: bool,
+) -> Result<BroadcastGroupInfo, String> {
+    let mut groups = lock_groups(&registry);
+    let group = groups
+        .get_mut(&group_id)
+        .ok_or_else(|| "Broadcast group not found".to_string())?;
+    let member = group
+        .members
+        .iter_mut()
+        .find(|member| member.session_id == session_id)
+        .ok_or_else(|| "Session is not a member of this broadcast group".to_string())?;
+    // 重新加入生产服务器时需要再次确认
+    if member.paused && !paused && member.production {
+        group.confirmed_at = None;
+    }
+    member.paused = paused;
+    Ok(group_info(group, &ssh_state))
+}
+
+#[tauri::command]
+pub fn set_broadcast_safety_mode(
+    ssh_state: State<'_, SshState>,
+    registry: State<'_, BroadcastRegistry>,
+    group_id: String,
+    enabled: bool,
+) -> Result<BroadcastGroupInfo, String> {
+    let mut groups = lock_groups(&registry);
+    let group = groups
+        .get_mut(&group_id)
+        .ok_or_else(|| "Broadcast group not found".to_string())?;
+    group.safety_mode = enabled;
+    group.confirmed_at = None;
+    Ok(group_info(group, &ssh_state))
+}
+
+// 🟢 用户确认向包含生产服务器的组广播，有效期内的后续输入不再询问
+#[tauri::command]
+pub fn confirm_broadcast_group(
+    ssh_state: State<'_, SshState>,
+    registry: State<'_, BroadcastRegistry>,
+    group_id: String,
+) -> Result<BroadcastGroupInfo, String> {
+    let mut groups = lock_groups(&registry);
+    let group = groups
+        .get_mut(&group_id)
+        .ok_or_else(|| "Broadcast group not found".to_string())?;
+    group.confirmed_at = Some(Instant::now());
+    ssh_log::info(
+        SshLogRecord::new(
+            "ssh.broadcast",
+            "group_confirmed",
+            "Confirmed broadcasting to production servers",
+        )
+        .field("group_id", group.id.clone()),
+    );
+    Ok(group_info(group, &ssh_state))
+}
+
+#[tauri::command]
+pub fn list_broadcast_groups(
+    ssh_state: State<'_, SshState>,
+    registry: State<'_, BroadcastRegistry>,
+) -> Result<Vec<BroadcastGroupInfo>, String> {
+    let groups = lock_groups(&registry);
+    let mut infos: Vec<BroadcastGroupInfo> = groups
+        .values()
+        .map(|group| group_info(group, &ssh_state))
+        .collect();
+    infos.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(infos)
+}
+
+#[tauri::command]
+pub fn delete_broadcast_group(
+    registry: State<'_, BroadcastRegistry>,
+    group_id: String,
+) -> Result<(), String> {
+    lock_groups(&registry)
+        .remove(&group_id)
+        .map(|_| ())
+        .ok_or_else(|| "Broadcast group not found".to_string())
+}
+
+// 🟢 向组内每个未暂停的成员并发写入，单个成员失败不影响其他成员
+#[tauri::command]
+pub async fn broadcast_write(
+    ssh_state: State<'_, SshState>,
+    registry: State<'_, BroadcastRegistry>,
+    group_id: String,
+    data: String,
+) -> Result<BroadcastWriteResult, String> {
+    let (targets, skipped) = {
+        let groups = lock_groups(&registry);
+        let group = groups
+            .get(&group_id)
+            .ok_or_else(|| "Broadcast group not found".to_string())?;
+        if group.requires_confirmation() && !group.is_confirmed() {
+            let production: Vec<&str> = group
+                .members
+                .iter()
+                .filter(|member| member.production && !member.paused)
+                .map(|member| member.server_name.as_str())
+                .collect();
+            return Err(format!(
+                "BROADCAST_CONFIRMATION_REQUIRED: Group includes production servers: {}",
+                production.join(", ")
+            ));
+        }
+        let targets: Vec<(String, String)> = group
+            .members
+            .iter()
+            .filter(|member| !member.paused)
+            .map(|member| (member.session_id.clone(), member.server_name.clone()))
+            .collect();
+        let skipped: Vec<String> = group
+            .members
+            .iter()
+            .filter(|member| member.paused)
+            .map(|member| member.session_id.clone())
+            .collect();
+        (targets, skipped)
+    };
+
+    let writes = targets.into_iter().map(|(session_id, server_name)| {
+        let write_tx = {
+            let map = match ssh_state.sessions.lock() {
+                Ok(g) => g,
+                Err(p) => p.into_inner(),
+            };
+            match map.get(&session_id) {
+                Some(conn) if conn.shell_is_active() => {
+                    conn.touch_client_heartbeat();
+                    Ok(conn.shell_write_tx.clone())
+                }
+                Some(_) => Err("SSH shell not active".to_string()),
+                None => Err("SSH connection not active".to_string()),
+            }
+        };
+        let data = data.clone();
+        async move {
+            let result = match write_tx {
+                Ok(write_tx) => queue_shell_write(write_tx, data).await,
+                Err(err) => Err(err),
+            };
+            (session_id, server_name, result)
+        }
+    });
+
+    let mut result = BroadcastWriteResult {
+        delivered: Vec::new(),
+        skipped,
+        failures: Vec::new(),
+    };
+    for (session_id, server_name, outcome) in join_all(writes).await {
+        match outcome {
+            Ok(()) => result.delivered.push(session_id),
+            Err(error) => result.failures.push(BroadcastFailure {
+                session_id,
+                server_name,
+                error,
+            }),
+        }
+    }
+
+    if !result.failures.is_empty() {
+        ssh_log::warn(
+            SshLogRecord::new(
+                "ssh.broadcast",
+                "write_partial_failure",
+                "Broadcast input failed for some group members",
+            )
+            .field("group_id", group_id)
+            .field("delivered", result.delivered.len())
+            .field("failed", result.failures.len()),
+        );
+    }
+    Ok(result)
+}
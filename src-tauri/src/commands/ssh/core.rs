@@ -2,21 +2,64 @@ use std::time::Duration;
 
 use crate::utils::ssh_log::SshLogRecord;
 
+mod agent_forward;
 mod auth;
 mod client;
+mod persistent_session;
 mod proxy;
+mod scrollback;
+mod session_log;
+mod shell_integration;
 mod shell_io;
+mod shell_startup;
+mod socks_server;
+mod terminal_output;
+mod terminal_transfer;
 mod transport;
+mod triggers;
+mod trzsz;
+mod zmodem;
 
-pub use client::PiTermClientHandler;
+pub use client::{PiTermClientHandler, TransportLink};
+pub use persistent_session::{
+    parse_tmux_sessions, tmux_attach_input, tmux_list_sessions_command, validate_session_name,
+    PersistentShell, RemoteTmuxSession,
+};
+pub use scrollback::{
+    ScrollbackLine, ScrollbackLines, ScrollbackMatch, ScrollbackPage, ScrollbackSearchResult,
+};
+pub use session_log::{validate_session_log_template, SessionLogWriter, SESSION_LOGS_DIR_NAME};
+pub use shell_integration::{ShellCommandEvent, SHELL_INTEGRATION_SCRIPT};
 pub use shell_io::{spawn_shell_reader_thread, spawn_shell_writer_thread};
-pub use transport::{create_shell_channel, establish_base_session};
+pub use shell_startup::{shell_input_lines, validate_shell_options};
+pub use terminal_output::{
+    encode_terminal_input, ScrollbackBuffer, TerminalDecoder, Utf8ChunkDecoder,
+};
+pub use terminal_transfer::{
+    prepare_transfer_selection, TerminalTransferDirection, TerminalTransferProtocol,
+    TransferSelection,
+};
+pub use triggers::{validate_trigger_rule, TriggerEngine, TriggerMatch};
+pub use transport::{
+    create_shell_channel, establish_base_session, establish_jump_chain, open_jump_tunnel,
+    open_shell_channel,
+};
 pub use proxy::establish_tcp_stream;
+pub use socks_server::{
+    accept_socks5_request, send_socks5_reply, Socks5Target, SOCKS5_REPLY_GENERAL_FAILURE,
+    SOCKS5_REPLY_HOST_UNREACHABLE, SOCKS5_REPLY_SUCCEEDED,
+};
 
 const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
 const DEFAULT_IO_TIMEOUT_SECS: u64 = 60;
 const HTTP_PROXY_RESPONSE_LIMIT: usize = 16 * 1024;
 const SHELL_WRITE_BATCH_LIMIT: usize = 64 * 1024;
+pub const SHELL_SCROLLBACK_LIMIT_BYTES: usize = 512 * 1024;
+// 按行索引的纯文本回滚 (搜索用)，与原始字节缓冲分开计算上限
+pub const SHELL_SCROLLBACK_MAX_LINES: usize = 20_000;
+pub const SHELL_SCROLLBACK_TEXT_LIMIT_BYTES: usize = 4 * 1024 * 1024;
+const KEYBOARD_INTERACTIVE_TIMEOUT_SECS: u64 = 120;
+const KEYBOARD_INTERACTIVE_MAX_ROUNDS: usize = 8;
 
 fn with_connection_context(
     record: SshLogRecord,
@@ -0,0 +1,291 @@
+use std::sync::Arc;
+
+use russh::{ChannelId, CryptoVec};
+use russh_keys::encoding::{Encoding, Reader};
+use russh_keys::key::{KeyPair, SignatureHash};
+use russh_keys::PublicKeyBase64;
+#[cfg(unix)]
+use tokio::sync::mpsc;
+
+#[cfg(unix)]
+use crate::utils::ssh_log;
+use crate::utils::ssh_log::SshLogRecord;
+
+use super::client::TransportLink;
+
+// ssh-agent 协议消息 (draft-miller-ssh-agent)
+const SSH_AGENT_FAILURE: u8 = 5;
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+const SSH_AGENT_SIGN_RESPONSE: u8 = 14;
+const SSH_AGENT_RSA_SHA2_256: u32 = 2;
+const SSH_AGENT_RSA_SHA2_512: u32 = 4;
+
+const AGENT_FRAME_LIMIT: usize = 256 * 1024;
+#[cfg(unix)]
+const LOCAL_AGENT_TIMEOUT_SECS: u64 = 10;
+const VAULT_KEY_COMMENT: &str = "PiTerm Vault";
+
+// 远端通过 auth-agent@openssh.com 通道发来的单个请求
+pub struct AgentRequest {
+    pub message_type: u8,
+    // 本地 agent 转发时为 None，应答由转发任务经 Handle 写回
+    pub response: Option<Vec<u8>>,
+}
+
+enum AgentBackend {
+    // 🟢 用户标记为可转发的 Vault 私钥，直接在本进程内签名
+    Vault(Arc<Vec<KeyPair>>),
+    // 🟢 透传给本地 SSH_AUTH_SOCK；读写在独立任务中进行，不阻塞 handler 回调
+    #[cfg(unix)]
+    Local(mpsc::UnboundedSender<Vec<u8>>),
+}
+
+pub struct AgentForwardChannel {
+    backend: AgentBackend,
+    buffer: Vec<u8>,
+}
+
+impl AgentForwardChannel {
+    // 有可转发的 Vault 私钥时只暴露这些私钥，否则转发本地 ssh-agent
+    pub async fn open(
+        vault_keys: Arc<Vec<KeyPair>>,
+        link: Arc<TransportLink>,
+        channel: ChannelId,
+        relay_failed: SshLogRecord,
+    ) -> Result<Self, String> {
+        let backend = if vault_keys.is_empty() {
+            open_local_agent(link, channel, relay_failed).await?
+        } else {
+            AgentBackend::Vault(vault_keys)
+        };
+        Ok(Self {
+            backend,
+            buffer: Vec::new(),
+        })
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        match self.backend {
+            AgentBackend::Vault(_) => "vault",
+            #[cfg(unix)]
+            AgentBackend::Local(_) => "local",
+        }
+    }
+
+    // 通道数据可能拆包/粘包，按 4 字节长度前缀切出完整请求后逐个应答
+    pub fn feed(&mut self, data: &[u8]) -> Result<Vec<AgentRequest>, String> {
+        self.buffer.extend_from_slice(data);
+
+        let mut requests = Vec::new();
+        while self.buffer.len() >= 4 {
+            let len = u32::from_be_bytes([
+                self.buffer[0],
+                self.buffer[1],
+                self.buffer[2],
+                self.buffer[3],
+            ]) as usize;
+            if len == 0 || len > AGENT_FRAME_LIMIT {
+                return Err(format!("Invalid agent message length {}", len));
+            }
+            if self.buffer.len() < 4 + len {
+                break;
+            }
+            let frame: Vec<u8> = self.buffer.drain(..4 + len).collect();
+            let message_type = frame[4];
+            let response = match &mut self.backend {
+                AgentBackend::Vault(keys) => {
+                    Some(frame_message(&answer_from_vault(keys, &frame[4..])))
+                }
+                #[cfg(unix)]
+                AgentBackend::Local(frames) => {
+                    frames
+                        .send(frame)
+                        .map_err(|_| "Local ssh-agent relay stopped".to_string())?;
+                    None
+                }
+            };
+            requests.push(AgentRequest {
+                message_type,
+                response,
+            });
+        }
+        Ok(requests)
+    }
+}
+
+pub fn is_sign_request(message_type: u8) -> bool {
+    message_type == SSH_AGENTC_SIGN_REQUEST
+}
+
+// 🟢 解析可转发的私钥，无法解析的私钥跳过并返回错误说明
+pub fn decode_forward_keys(keys: &[(String, Option<String>)]) -> (Vec<KeyPair>, Vec<String>) {
+    let mut decoded = Vec::with_capacity(keys.len());
+    let mut errors = Vec::new();
+    for (private_key, passphrase) in keys {
+        match russh_keys::decode_secret_key(private_key, passphrase.as_deref()) {
+            Ok(key) => decoded.push(key),
+            Err(e) => errors.push(e.to_string()),
+        }
+    }
+    (decoded, errors)
+}
+
+#[cfg(unix)]
+async fn open_local_agent(
+    link: Arc<TransportLink>,
+    channel: ChannelId,
+    relay_failed: SshLogRecord,
+) -> Result<AgentBackend, String> {
+    let path = std::env::var("SSH_AUTH_SOCK")
+        .map_err(|_| "AGENT_UNAVAILABLE: SSH_AUTH_SOCK is not set".to_string())?;
+    let stream = tokio::net::UnixStream::connect(&path)
+        .await
+        .map_err(|e| format!("AGENT_UNAVAILABLE: Failed to connect to {}: {}", path, e))?;
+    let (frames_tx, frames_rx) = mpsc::unbounded_channel();
+    tokio::spawn(run_local_agent_relay(
+        stream,
+        frames_rx,
+        link,
+        channel,
+        relay_failed,
+    ));
+    Ok(AgentBackend::Local(frames_tx))
+}
+
+#[cfg(not(unix))]
+async fn open_local_agent(
+    _link: Arc<TransportLink>,
+    _channel: ChannelId,
+    _relay_failed: SshLogRecord,
+) -> Result<AgentBackend, String> {
+    Err("AGENT_UNAVAILABLE: Forwarding the local ssh-agent is only supported on Unix".to_string())
+}
+
+// 🟢 按顺序把请求交给本地 agent，应答经传输的 Handle 写回通道；出错即退出，
+// 之后的请求在 handler 中发送失败并关闭通道
+#[cfg(unix)]
+async fn run_local_agent_relay(
+    mut stream: tokio::net::UnixStream,
+    mut frames: mpsc::UnboundedReceiver<Vec<u8>>,
+    link: Arc<TransportLink>,
+    channel: ChannelId,
+    relay_failed: SshLogRecord,
+) {
+    while let Some(frame) = frames.recv().await {
+        let result = match relay_to_local_agent(&mut stream, &frame).await {
+            Ok(response) => match link.session().await {
+                Some(session) => session
+                    .read()
+                    .await
+                    .data(channel, CryptoVec::from_slice(&response))
+                    .await
+                    .map_err(|_| "SSH transport closed".to_string()),
+                None => Err("SSH transport is not available".to_string()),
+            },
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            ssh_log::warn(relay_failed.field("error", err));
+            return;
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn relay_to_local_agent(
+    stream: &mut tokio::net::UnixStream,
+    frame: &[u8],
+) -> Result<Vec<u8>, String> {
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let exchange = async {
+        stream.write_all(frame).await?;
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len).await?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len > AGENT_FRAME_LIMIT {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "agent reply too large",
+            ));
+        }
+        let mut response = vec![0u8; 4 + len];
+        response[..4].copy_from_slice(&(len as u32).to_be_bytes());
+        stream.read_exact(&mut response[4..]).await?;
+        Ok(response)
+    };
+
+    tokio::time::timeout(Duration::from_secs(LOCAL_AGENT_TIMEOUT_SECS), exchange)
+        .await
+        .map_err(|_| "Local ssh-agent did not answer in time".to_string())?
+        .map_err(|e| format!("Local ssh-agent error: {}", e))
+}
+
+fn frame_message(body: &CryptoVec) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(4 + body.len());
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(body);
+    frame
+}
+
+fn failure() -> CryptoVec {
+    CryptoVec::from_slice(&[SSH_AGENT_FAILURE])
+}
+
+fn answer_from_vault(keys: &[KeyPair], message: &[u8]) -> CryptoVec {
+    match message[0] {
+        SSH_AGENTC_REQUEST_IDENTITIES => {
+            let mut reply = CryptoVec::new();
+            reply.push(SSH_AGENT_IDENTITIES_ANSWER);
+            let blobs: Vec<Vec<u8>> = keys
+                .iter()
+                .filter_map(|key| key.clone_public_key().ok())
+                .map(|public| public.public_key_bytes())
+                .collect();
+            reply.push_u32_be(blobs.len() as u32);
+            for blob in blobs {
+                reply.extend_ssh_string(&blob);
+                reply.extend_ssh_string(VAULT_KEY_COMMENT.as_bytes());
+            }
+            reply
+        }
+        SSH_AGENTC_SIGN_REQUEST => sign_with_vault_key(keys, message).unwrap_or_else(failure),
+        // 只读代理：不支持添加/删除/锁定等管理操作
+        _ => failure(),
+    }
+}
+
+fn sign_with_vault_key(keys: &[KeyPair], message: &[u8]) -> Option<CryptoVec> {
+    let mut reader = message.reader(1);
+    let blob = reader.read_string().ok()?;
+    let data = reader.read_string().ok()?;
+    let flags = reader.read_u32().unwrap_or(0);
+
+    let key = keys.iter().find(|key| {
+        key.clone_public_key()
+            .map(|public| public.public_key_bytes() == blob)
+            .unwrap_or(false)
+    })?;
+
+    // RSA 密钥按请求标志选择签名算法 (默认 ssh-rsa / SHA1)
+    let hash = if flags & SSH_AGENT_RSA_SHA2_512 != 0 {
+        SignatureHash::SHA2_512
+    } else if flags & SSH_AGENT_RSA_SHA2_256 != 0 {
+        SignatureHash::SHA2_256
+    } else {
+        SignatureHash::SHA1
+    };
+    let signer = key.with_signature_hash(hash);
+
+    let mut reply = CryptoVec::new();
+    reply.push(SSH_AGENT_SIGN_RESPONSE);
+    signer
+        .as_ref()
+        .unwrap_or(key)
+        .add_signature(&mut reply, data)
+        .ok()?;
+    Some(reply)
+}
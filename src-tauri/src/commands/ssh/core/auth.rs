@@ -1,10 +1,518 @@
-use crate::commands::ssh::state::SshSession;
+use crate::commands::ssh::state::{
+    AuthPromptClosedEvent, AuthPromptEvent, AuthPromptItem, AuthPromptRegistry, PendingAuthPrompt,
+    SshSession,
+};
+use crate::commands::vault::{certificate_expired, parse_certificate};
 use crate::models::SshConfig;
 use crate::utils::ssh_log::{self, SshLogRecord};
-use super::with_connection_context;
+use super::{
+    with_connection_context, KEYBOARD_INTERACTIVE_MAX_ROUNDS, KEYBOARD_INTERACTIVE_TIMEOUT_SECS,
+};
+use russh::client::{KeyboardInteractiveAuthResponse, Prompt};
+use russh_keys::agent::client::AgentClient;
 use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// 只有用户正在等待的交互式 shell 连接会弹出 keyboard-interactive 提示
+const KEYBOARD_INTERACTIVE_PROMPT_ROLE: &str = "shell";
+
+// 🟢 通过 SSH_AUTH_SOCK 指向的 ssh-agent 逐个尝试身份
+async fn authenticate_with_agent(
+    sess: &mut SshSession,
+    config: &SshConfig,
+    session_id: Option<&str>,
+    role: &'static str,
+) -> Result<(), String> {
+    ssh_log::info(with_connection_context(
+        SshLogRecord::new(
+            "ssh.auth",
+            "agent_attempt",
+            "Attempting SSH agent authentication",
+        )
+        .field("host", config.host.clone())
+        .field("port", config.port)
+        .field("username", config.username.clone()),
+        session_id,
+        role,
+    ));
+
+    let mut agent = AgentClient::connect_env().await.map_err(|err| {
+        ssh_log::warn(with_connection_context(
+            SshLogRecord::new(
+                "ssh.auth",
+                "agent_connect_failed",
+                "Failed to connect to SSH agent",
+            )
+            .field("error", err.to_string()),
+            session_id,
+            role,
+        ));
+        format!("SSH Agent Error: {}", err)
+    })?;
+
+    let identities = agent.request_identities().await.map_err(|err| {
+        ssh_log::warn(with_connection_context(
+            SshLogRecord::new(
+                "ssh.auth",
+                "agent_identities_failed",
+                "Failed to list SSH agent identities",
+            )
+            .field("error", err.to_string()),
+            session_id,
+            role,
+        ));
+        format!("SSH Agent Error: {}", err)
+    })?;
+
+    if identities.is_empty() {
+        ssh_log::warn(with_connection_context(
+            SshLogRecord::new(
+                "ssh.auth",
+                "agent_no_identities",
+                "SSH agent holds no identities",
+            ),
+            session_id,
+            role,
+        ));
+        return Err("SSH Agent has no identities loaded".to_string());
+    }
+
+    let total = identities.len();
+    let mut last_error = None;
+    for (index, identity) in identities.into_iter().enumerate() {
+        let key_type = identity.name().to_string();
+        let fingerprint = identity.fingerprint();
+        ssh_log::debug(with_connection_context(
+            SshLogRecord::new(
+                "ssh.auth",
+                "agent_identity_attempt",
+                "Offering SSH agent identity",
+            )
+            .field("identity_index", index + 1)
+            .field("identity_count", total)
+            .field("key_type", key_type.clone())
+            .field("fingerprint", fingerprint.clone()),
+            session_id,
+            role,
+        ));
+
+        let (returned_agent, result) = sess
+            .authenticate_future(config.username.as_str(), identity, agent)
+            .await;
+        agent = returned_agent;
+
+        match result {
+            Ok(true) => {
+                ssh_log::info(with_connection_context(
+                    SshLogRecord::new(
+                        "ssh.auth",
+                        "agent_success",
+                        "SSH agent authentication succeeded",
+                    )
+                    .field("host", config.host.clone())
+                    .field("port", config.port)
+                    .field("username", config.username.clone())
+                    .field("key_type", key_type)
+                    .field("fingerprint", fingerprint),
+                    session_id,
+                    role,
+                ));
+                return Ok(());
+            }
+            Ok(false) => {
+                ssh_log::debug(with_connection_context(
+                    SshLogRecord::new(
+                        "ssh.auth",
+                        "agent_identity_rejected",
+                        "SSH agent identity rejected by server",
+                    )
+                    .field("identity_index", index + 1)
+                    .field("key_type", key_type)
+                    .field("fingerprint", fingerprint),
+                    session_id,
+                    role,
+                ));
+                last_error = Some("SSH Agent identities rejected by server".to_string());
+            }
+            Err(err) => {
+                ssh_log::warn(with_connection_context(
+                    SshLogRecord::new(
+                        "ssh.auth",
+                        "agent_identity_failed",
+                        "SSH agent identity failed to sign",
+                    )
+                    .field("identity_index", index + 1)
+                    .field("key_type", key_type)
+                    .field("fingerprint", fingerprint)
+                    .field("error", err.to_string()),
+                    session_id,
+                    role,
+                ));
+                last_error = Some(format!("SSH Agent Auth Error: {}", err));
+            }
+        }
+    }
+
+    ssh_log::warn(with_connection_context(
+        SshLogRecord::new(
+            "ssh.auth",
+            "agent_failed",
+            "SSH agent authentication failed: no identity accepted",
+        )
+        .field("host", config.host.clone())
+        .field("port", config.port)
+        .field("username", config.username.clone())
+        .field("identity_count", total),
+        session_id,
+        role,
+    ));
+    Err(last_error.unwrap_or_else(|| "SSH Agent authentication failed".to_string()))
+}
+
+// 🟢 OpenSSH 用户证书认证 (<type>-cert-v01@openssh.com)，证书过期只告警，仍交给服务器判定
+async fn authenticate_with_certificate(
+    sess: &mut SshSession,
+    config: &SshConfig,
+    key_pair: Arc<russh_keys::key::KeyPair>,
+    certificate: &str,
+    session_id: Option<&str>,
+    role: &'static str,
+) -> Result<(), String> {
+    let cert = parse_certificate(certificate).inspect_err(|err| {
+        ssh_log::warn(with_connection_context(
+            SshLogRecord::new(
+                "ssh.auth",
+                "certificate_parse_failed",
+                "Failed to parse attached OpenSSH certificate",
+            )
+            .field("error", err.clone()),
+            session_id,
+            role,
+        ));
+    })?;
+    let cert_type = cert.algorithm().to_certificate_type();
+
+    if certificate_expired(&cert) {
+        ssh_log::warn(with_connection_context(
+            SshLogRecord::new(
+                "ssh.auth",
+                "certificate_expired",
+                "Attached OpenSSH certificate is outside its validity window",
+            )
+            .field("key_id", cert.key_id().to_string())
+            .field("valid_after", cert.valid_after())
+            .field("valid_before", cert.valid_before()),
+            session_id,
+            role,
+        ));
+    }
+
+    ssh_log::info(with_connection_context(
+        SshLogRecord::new(
+            "ssh.auth",
+            "certificate_attempt",
+            "Attempting OpenSSH certificate authentication",
+        )
+        .field("host", config.host.clone())
+        .field("port", config.port)
+        .field("username", config.username.clone())
+        .field("cert_type", cert_type.clone())
+        .field("key_id", cert.key_id().to_string())
+        .field("principals", cert.valid_principals().len()),
+        session_id,
+        role,
+    ));
+
+    match sess
+        .authenticate_openssh_cert(config.username.as_str(), key_pair, cert)
+        .await
+    {
+        Ok(true) => {
+            ssh_log::info(with_connection_context(
+                SshLogRecord::new(
+                    "ssh.auth",
+                    "certificate_success",
+                    "OpenSSH certificate authentication succeeded",
+                )
+                .field("host", config.host.clone())
+                .field("port", config.port)
+                .field("username", config.username.clone())
+                .field("cert_type", cert_type),
+                session_id,
+                role,
+            ));
+            Ok(())
+        }
+        Ok(false) => {
+            ssh_log::warn(with_connection_context(
+                SshLogRecord::new(
+                    "ssh.auth",
+                    "certificate_failed",
+                    "OpenSSH certificate authentication failed: rejected by server",
+                )
+                .field("host", config.host.clone())
+                .field("port", config.port)
+                .field("username", config.username.clone())
+                .field("cert_type", cert_type),
+                session_id,
+                role,
+            ));
+            Err("Certificate rejected by server".to_string())
+        }
+        Err(err) => {
+            ssh_log::warn(with_connection_context(
+                SshLogRecord::new(
+                    "ssh.auth",
+                    "certificate_failed",
+                    "OpenSSH certificate authentication failed",
+                )
+                .field("host", config.host.clone())
+                .field("port", config.port)
+                .field("username", config.username.clone())
+                .field("error", err.to_string()),
+                session_id,
+                role,
+            ));
+            Err(format!("Certificate Auth Error: {}", err))
+        }
+    }
+}
+
+// 🟢 把服务器提示转发给前端 (ssh-auth-prompt)，在 oneshot 上等待回答
+async fn request_prompt_answers(
+    app: &AppHandle,
+    config: &SshConfig,
+    session_id: Option<&str>,
+    role: &'static str,
+    name: String,
+    instructions: String,
+    prompts: &[Prompt],
+) -> Result<Vec<String>, String> {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+    let registry = app.state::<AuthPromptRegistry>();
+    {
+        let mut pending = match registry.pending.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        pending.insert(
+            request_id.clone(),
+            PendingAuthPrompt {
+                prompt_count: prompts.len(),
+                response_tx,
+            },
+        );
+    }
+
+    ssh_log::info(with_connection_context(
+        SshLogRecord::new(
+            "ssh.auth",
+            "keyboard_interactive_prompt",
+            "Forwarding keyboard-interactive prompts to the frontend",
+        )
+        .field("request_id", request_id.clone())
+        .field("prompt_count", prompts.len()),
+        session_id,
+        role,
+    ));
+
+    let _ = app.emit(
+        "ssh-auth-prompt",
+        AuthPromptEvent {
+            request_id: request_id.clone(),
+            session_id: session_id.map(str::to_string),
+            server_id: config.id.clone(),
+            host: config.host.clone(),
+            username: config.username.clone(),
+            name,
+            instructions,
+            prompts: prompts
+                .iter()
+                .map(|prompt| AuthPromptItem {
+                    prompt: prompt.prompt.clone(),
+                    echo: prompt.echo,
+                })
+                .collect(),
+        },
+    );
+
+    let outcome = tokio::time::timeout(
+        Duration::from_secs(KEYBOARD_INTERACTIVE_TIMEOUT_SECS),
+        response_rx,
+    )
+    .await;
+
+    let (result, reason) = match outcome {
+        Ok(Ok(Some(answers))) => return Ok(answers),
+        Ok(Ok(None)) => (
+            Err("Keyboard-interactive authentication cancelled by user".to_string()),
+            "cancelled",
+        ),
+        Ok(Err(_)) => (
+            Err("Keyboard-interactive prompt was dropped".to_string()),
+            "dropped",
+        ),
+        Err(_) => (
+            Err(format!(
+                "Keyboard-interactive prompt timed out after {}s",
+                KEYBOARD_INTERACTIVE_TIMEOUT_SECS
+            )),
+            "timeout",
+        ),
+    };
+
+    {
+        let mut pending = match registry.pending.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        pending.remove(&request_id);
+    }
+    let _ = app.emit(
+        "ssh-auth-prompt-closed",
+        AuthPromptClosedEvent {
+            request_id: request_id.clone(),
+            reason: reason.to_string(),
+        },
+    );
+    ssh_log::warn(with_connection_context(
+        SshLogRecord::new(
+            "ssh.auth",
+            "keyboard_interactive_prompt_closed",
+            "Keyboard-interactive prompt closed without answers",
+        )
+        .field("request_id", request_id)
+        .field("reason", reason),
+        session_id,
+        role,
+    ));
+    result
+}
+
+// 单个不回显的密码提示可以直接用已保存的密码回答 (仅一次，避免错误密码循环)
+fn is_password_prompt(prompts: &[Prompt]) -> bool {
+    prompts.len() == 1
+        && !prompts[0].echo
+        && prompts[0].prompt.to_lowercase().contains("password")
+}
+
+async fn authenticate_keyboard_interactive(
+    app: &AppHandle,
+    sess: &mut SshSession,
+    config: &SshConfig,
+    session_id: Option<&str>,
+    role: &'static str,
+    prompts_allowed: bool,
+) -> Result<(), String> {
+    ssh_log::info(with_connection_context(
+        SshLogRecord::new(
+            "ssh.auth",
+            "keyboard_interactive_attempt",
+            "Attempting keyboard-interactive authentication",
+        )
+        .field("host", config.host.clone())
+        .field("port", config.port)
+        .field("username", config.username.clone()),
+        session_id,
+        role,
+    ));
+
+    let saved_password = config
+        .password
+        .as_deref()
+        .filter(|value| !value.trim().is_empty());
+    let mut password_offered = false;
+
+    let mut response = sess
+        .authenticate_keyboard_interactive_start(config.username.as_str(), None)
+        .await
+        .map_err(|e| format!("Keyboard-Interactive Auth Error: {}", e))?;
+
+    for round in 1..=KEYBOARD_INTERACTIVE_MAX_ROUNDS {
+        let (name, instructions, prompts) = match response {
+            KeyboardInteractiveAuthResponse::Success => {
+                ssh_log::info(with_connection_context(
+                    SshLogRecord::new(
+                        "ssh.auth",
+                        "keyboard_interactive_success",
+                        "Keyboard-interactive authentication succeeded",
+                    )
+                    .field("host", config.host.clone())
+                    .field("port", config.port)
+                    .field("username", config.username.clone())
+                    .field("rounds", round - 1),
+                    session_id,
+                    role,
+                ));
+                return Ok(());
+            }
+            KeyboardInteractiveAuthResponse::Failure => {
+                ssh_log::warn(with_connection_context(
+                    SshLogRecord::new(
+                        "ssh.auth",
+                        "keyboard_interactive_failed",
+                        "Keyboard-interactive authentication failed: rejected by server",
+                    )
+                    .field("host", config.host.clone())
+                    .field("port", config.port)
+                    .field("username", config.username.clone()),
+                    session_id,
+                    role,
+                ));
+                return Err(
+                    "Keyboard-interactive authentication failed: rejected by server".to_string(),
+                );
+            }
+            KeyboardInteractiveAuthResponse::InfoRequest {
+                name,
+                instructions,
+                prompts,
+            } => (name, instructions, prompts),
+        };
+
+        let answers = if prompts.is_empty() {
+            Vec::new()
+        } else if let Some(password) =
+            saved_password.filter(|_| !password_offered && is_password_prompt(&prompts))
+        {
+            password_offered = true;
+            ssh_log::debug(with_connection_context(
+                SshLogRecord::new(
+                    "ssh.auth",
+                    "keyboard_interactive_password_autofill",
+                    "Answered keyboard-interactive password prompt with saved password",
+                ),
+                session_id,
+                role,
+            ));
+            vec![password.to_string()]
+        } else if prompts_allowed {
+            request_prompt_answers(app, config, session_id, role, name, instructions, &prompts)
+                .await?
+        } else {
+            // 后台 / 跳板 / 自动重连等无人值守连接不弹出前端提示，直接失败
+            return Err(format!(
+                "KEYBOARD_INTERACTIVE_UNAVAILABLE: Server requested interactive prompts, which are only answered for interactive shell connections (role: {})",
+                role
+            ));
+        };
+
+        response = sess
+            .authenticate_keyboard_interactive_respond(answers)
+            .await
+            .map_err(|e| format!("Keyboard-Interactive Auth Error: {}", e))?;
+    }
+
+    Err(format!(
+        "Keyboard-interactive authentication exceeded {} rounds",
+        KEYBOARD_INTERACTIVE_MAX_ROUNDS
+    ))
+}
 
 pub(super) async fn authenticate_session(
+    app: &AppHandle,
     sess: &mut SshSession,
     config: &SshConfig,
     session_id: Option<&str>,
@@ -12,6 +520,13 @@ pub(super) async fn authenticate_session(
 ) -> Result<(), String> {
     let mut last_error = None;
 
+    if config.use_agent {
+        match authenticate_with_agent(sess, config, session_id, role).await {
+            Ok(()) => return Ok(()),
+            Err(err) => last_error = Some(err),
+        }
+    }
+
     if let Some(key_content) = &config.private_key {
         if !key_content.trim().is_empty() {
             ssh_log::info(with_connection_context(
@@ -34,6 +549,26 @@ pub(super) async fn authenticate_session(
             match russh_keys::decode_secret_key(key_content, passphrase) {
                 Ok(key_pair) => {
                     let key_pair = Arc::new(key_pair);
+                    if let Some(certificate) = config
+                        .certificate
+                        .as_deref()
+                        .filter(|value| !value.trim().is_empty())
+                    {
+                        // 证书失败已记录日志，回退到普通公钥认证
+                        if authenticate_with_certificate(
+                            sess,
+                            config,
+                            key_pair.clone(),
+                            certificate,
+                            session_id,
+                            role,
+                        )
+                        .await
+                        .is_ok()
+                        {
+                            return Ok(());
+                        }
+                    }
                     match sess.authenticate_publickey(&config.username, key_pair).await {
                         Ok(true) => {
                             ssh_log::info(with_connection_context(
@@ -166,6 +701,29 @@ pub(super) async fn authenticate_session(
         }
     }
 
+    // 🟢 其它方法都未成功时尝试 keyboard-interactive；
+    // russh 把 partial success 也报告为失败，publickey/password + OTP 的链式认证会在这里完成。
+    // russh 不向客户端暴露服务端的可用方法列表，不支持该方法的服务端会立即返回失败。
+    // 只有交互式 shell 连接会向前端请求输入；其它角色仅在有已保存密码可自动应答时尝试
+    let prompts_allowed = role == KEYBOARD_INTERACTIVE_PROMPT_ROLE;
+    let has_saved_password = config
+        .password
+        .as_deref()
+        .is_some_and(|value| !value.trim().is_empty());
+    if prompts_allowed || has_saved_password {
+        match authenticate_keyboard_interactive(app, sess, config, session_id, role, prompts_allowed)
+            .await
+        {
+            Ok(()) => return Ok(()),
+            Err(err) => {
+                last_error = Some(match last_error {
+                    Some(previous) => format!("{}; {}", previous, err),
+                    None => err,
+                });
+            }
+        }
+    }
+
     Err(last_error
         .unwrap_or_else(|| "Auth failed: No usable private key or password provided.".to_string()))
 }
@@ -1,20 +1,482 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
 use async_trait::async_trait;
-use russh::client;
-use russh_keys::key::PublicKey;
+use russh::client::{self, Msg, Session};
+use russh::{Channel, ChannelId, CryptoVec};
+use russh_keys::key::{KeyPair, PublicKey};
+use russh_keys::PublicKeyBase64;
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+
+use crate::commands::ssh::forwarding::bridge_forwarded_channel;
+use crate::commands::ssh::host_key::{self, HostKeyCheckStatus};
+use crate::commands::ssh::state::{
+    HostKeyVerificationCache, PendingHostKey, SharedSshSession, SshSession,
+};
+use crate::commands::ssh::utils::compute_fingerprint;
+use crate::models::{HostKeyPolicy, SshConfig};
+use crate::utils::ssh_log::{self, SshLogRecord};
+
+use super::agent_forward::{decode_forward_keys, is_sign_request, AgentForwardChannel};
+use super::with_connection_context;
+
+#[derive(Debug)]
+pub enum PiTermClientError {
+    Ssh(russh::Error),
+    HostKeyMismatch {
+        host: String,
+        port: u16,
+        key_type: String,
+        fingerprint: String,
+    },
+    HostKeyUnknown {
+        host: String,
+        port: u16,
+        key_type: String,
+        fingerprint: String,
+    },
+    HostKeyRevoked {
+        host: String,
+        port: u16,
+        key_type: String,
+        fingerprint: String,
+    },
+    HostKeyCheckFailed(String),
+}
+
+impl From<russh::Error> for PiTermClientError {
+    fn from(err: russh::Error) -> Self {
+        Self::Ssh(err)
+    }
+}
+
+impl std::fmt::Display for PiTermClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ssh(err) => write!(f, "{}", err),
+            Self::HostKeyMismatch {
+                host,
+                port,
+                key_type,
+                fingerprint,
+            } => write!(
+                f,
+                "HOST_KEY_MISMATCH: Host key for {}:{} does not match known_hosts (server offered {} {})",
+                host, port, key_type, fingerprint
+            ),
+            Self::HostKeyUnknown {
+                host,
+                port,
+                key_type,
+                fingerprint,
+            } => write!(
+                f,
+                "HOST_KEY_UNKNOWN: Host {}:{} is not trusted yet (server offered {} {})",
+                host, port, key_type, fingerprint
+            ),
+            Self::HostKeyRevoked {
+                host,
+                port,
+                key_type,
+                fingerprint,
+            } => write!(
+                f,
+                "HOST_KEY_REVOKED: Host key for {}:{} is marked @revoked in known_hosts ({} {})",
+                host, port, key_type, fingerprint
+            ),
+            Self::HostKeyCheckFailed(err) => write!(f, "HOST_KEY_CHECK_FAILED: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for PiTermClientError {}
+
+static NEXT_TRANSPORT_ID: AtomicU64 = AtomicU64::new(1);
 
-#[derive(Clone, Copy)]
-pub struct PiTermClientHandler;
+// 传输建立前就可能有请求需要回写，最多等待这么久
+const TRANSPORT_BIND_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 🟢 handler 与其 SSH 传输之间的身份：复制标签页共享传输，不能再用 session_id 区分
+pub struct TransportLink {
+    pub id: u64,
+    // handler 回调中拿不到 Handle；传输建立后回填，供后台任务经 Handle 写回通道
+    session: watch::Sender<Option<Weak<tokio::sync::RwLock<SshSession>>>>,
+}
+
+impl TransportLink {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            id: NEXT_TRANSPORT_ID.fetch_add(1, Ordering::Relaxed),
+            session: watch::Sender::new(None),
+        })
+    }
+
+    pub fn bind(&self, session: &SharedSshSession) {
+        self.session.send_replace(Some(Arc::downgrade(session)));
+    }
+
+    pub async fn session(&self) -> Option<SharedSshSession> {
+        let mut bound = self.session.subscribe();
+        let session = tokio::time::timeout(TRANSPORT_BIND_TIMEOUT, bound.wait_for(Option::is_some))
+            .await
+            .ok()?
+            .ok()?
+            .clone()?;
+        session.upgrade()
+    }
+}
+
+#[derive(Clone)]
+pub struct PiTermClientHandler {
+    app: AppHandle,
+    server_id: String,
+    host: String,
+    port: u16,
+    policy: HostKeyPolicy,
+    session_id: Option<String>,
+    role: &'static str,
+    agent_forwarding: bool,
+    agent_keys: Arc<Vec<KeyPair>>,
+    agent_channels: Arc<tokio::sync::Mutex<HashMap<ChannelId, AgentForwardChannel>>>,
+    link: Arc<TransportLink>,
+}
+
+impl PiTermClientHandler {
+    pub fn new(
+        app: AppHandle,
+        config: &SshConfig,
+        session_id: Option<&str>,
+        role: &'static str,
+    ) -> Self {
+        let mut handler = Self {
+            app,
+            server_id: config.id.clone(),
+            host: config.host.clone(),
+            port: config.port,
+            policy: config.host_key_policy.unwrap_or_default(),
+            session_id: session_id.map(str::to_string),
+            role,
+            agent_forwarding: config.agent_forwarding,
+            agent_keys: Arc::new(Vec::new()),
+            agent_channels: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            link: TransportLink::new(),
+        };
+        if config.agent_forwarding && !config.agent_forward_keys.is_empty() {
+            let (keys, errors) = decode_forward_keys(&config.agent_forward_keys);
+            for err in errors {
+                ssh_log::warn(handler.log_record(
+                    SshLogRecord::new(
+                        "ssh.agent_forward",
+                        "key_skipped",
+                        "Skipped a forwardable vault key that could not be decoded",
+                    )
+                    .field("error", err),
+                ));
+            }
+            handler.agent_keys = Arc::new(keys);
+        }
+        handler
+    }
+
+    pub fn link(&self) -> Arc<TransportLink> {
+        self.link.clone()
+    }
+
+    fn log_record(&self, record: SshLogRecord) -> SshLogRecord {
+        with_connection_context(
+            record
+                .server_id(self.server_id.clone())
+                .field("host", self.host.clone())
+                .field("port", self.port)
+                .field("host_key_policy", format!("{:?}", self.policy)),
+            self.session_id.as_deref(),
+            self.role,
+        )
+    }
+
+    fn cache_pending_host_key(&self, key_type: &str, fingerprint: &str, host_key: Vec<u8>) {
+        let cache = self.app.state::<HostKeyVerificationCache>();
+        let mut entries = match cache.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        entries.retain(|_, entry| !entry.is_expired());
+        entries.insert(
+            self.server_id.clone(),
+            PendingHostKey {
+                host: self.host.clone(),
+                port: self.port,
+                key_type: key_type.to_string(),
+                fingerprint: fingerprint.to_string(),
+                host_key,
+                cached_at: Instant::now(),
+            },
+        );
+    }
+}
 
 #[async_trait]
 impl client::Handler for PiTermClientHandler {
-    type Error = russh::Error;
+    type Error = PiTermClientError;
 
     async fn check_server_key(
         &mut self,
-        _server_public_key: &PublicKey,
+        server_public_key: &PublicKey,
     ) -> Result<bool, Self::Error> {
-        // Trust all keys at transport layer. Host verification is handled manually
-        // at the Tauri layer via database known_hosts lookup.
-        Ok(true)
+        let key_type = server_public_key.name().to_string();
+        let host_key = server_public_key.public_key_bytes();
+        let fingerprint = compute_fingerprint(&host_key);
+
+        let status =
+            host_key::check_local_host_key(&self.app, &self.host, self.port, &key_type, &host_key)
+                .map_err(|err| {
+                    ssh_log::error(self.log_record(
+                        SshLogRecord::new(
+                            "ssh.host_key",
+                            "check_failed",
+                            "Failed to read known_hosts during SSH handshake",
+                        )
+                        .field("error", err.clone()),
+                    ));
+                    PiTermClientError::HostKeyCheckFailed(err)
+                })?;
+
+        match status {
+            HostKeyCheckStatus::Match => {
+                ssh_log::debug(self.log_record(
+                    SshLogRecord::new(
+                        "ssh.host_key",
+                        "verified",
+                        "Server host key matches known_hosts",
+                    )
+                    .field("key_type", key_type),
+                ));
+                Ok(true)
+            }
+            HostKeyCheckStatus::Mismatch => {
+                ssh_log::error(self.log_record(
+                    SshLogRecord::new(
+                        "ssh.host_key",
+                        "mismatch",
+                        "Server host key does not match known_hosts; aborting handshake",
+                    )
+                    .field("key_type", key_type.clone())
+                    .field("fingerprint", fingerprint.clone()),
+                ));
+                Err(PiTermClientError::HostKeyMismatch {
+                    host: self.host.clone(),
+                    port: self.port,
+                    key_type,
+                    fingerprint,
+                })
+            }
+            HostKeyCheckStatus::Revoked => {
+                ssh_log::error(self.log_record(
+                    SshLogRecord::new(
+                        "ssh.host_key",
+                        "revoked",
+                        "Server host key is revoked in known_hosts; aborting handshake",
+                    )
+                    .field("key_type", key_type.clone())
+                    .field("fingerprint", fingerprint.clone()),
+                ));
+                Err(PiTermClientError::HostKeyRevoked {
+                    host: self.host.clone(),
+                    port: self.port,
+                    key_type,
+                    fingerprint,
+                })
+            }
+            HostKeyCheckStatus::NotFound => match self.policy {
+                HostKeyPolicy::AcceptNew => {
+                    host_key::save_host_key_to_disk(
+                        &self.app,
+                        &self.host,
+                        self.port,
+                        &key_type,
+                        &host_key,
+                        host_key::known_hosts_uses_hashing(&self.app),
+                    )
+                    .map_err(PiTermClientError::HostKeyCheckFailed)?;
+                    ssh_log::info(self.log_record(
+                        SshLogRecord::new(
+                            "ssh.host_key",
+                            "accepted_new",
+                            "Unknown host key accepted and written to known_hosts",
+                        )
+                        .field("key_type", key_type)
+                        .field("fingerprint", fingerprint),
+                    ));
+                    Ok(true)
+                }
+                HostKeyPolicy::Prompt | HostKeyPolicy::Strict => {
+                    if self.policy == HostKeyPolicy::Prompt {
+                        self.cache_pending_host_key(&key_type, &fingerprint, host_key);
+                    }
+                    ssh_log::warn(self.log_record(
+                        SshLogRecord::new(
+                            "ssh.host_key",
+                            "unknown_rejected",
+                            "Server host key is not in known_hosts; aborting handshake",
+                        )
+                        .field("key_type", key_type.clone())
+                        .field("fingerprint", fingerprint.clone()),
+                    ));
+                    Err(PiTermClientError::HostKeyUnknown {
+                        host: self.host.clone(),
+                        port: self.port,
+                        key_type,
+                        fingerprint,
+                    })
+                }
+            },
+        }
+    }
+
+    // 🟢 远程端口转发 (tcpip-forward) 的入站连接
+    async fn server_channel_open_forwarded_tcpip(
+        &mut self,
+        channel: Channel<Msg>,
+        connected_address: &str,
+        connected_port: u32,
+        originator_address: &str,
+        originator_port: u32,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        let Some(session_id) = self.session_id.as_deref() else {
+            let _ = channel.close().await;
+            return Ok(());
+        };
+        ssh_log::debug(self.log_record(
+            SshLogRecord::new(
+                "ssh.forward",
+                "forwarded_channel_opened",
+                "Server opened a forwarded-tcpip channel",
+            )
+            .field("connected", format!("{}:{}", connected_address, connected_port))
+            .field("originator", format!("{}:{}", originator_address, originator_port)),
+        ));
+        bridge_forwarded_channel(
+            &self.app,
+            self.link.id,
+            session_id,
+            channel,
+            connected_port,
+            format!("{}:{}", originator_address, originator_port),
+        );
+        Ok(())
+    }
+
+    // 🟢 Agent 转发：远端 (例如 git/ssh) 通过 auth-agent@openssh.com 通道访问本地身份
+    async fn server_channel_open_agent_forward(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if !self.agent_forwarding {
+            ssh_log::warn(self.log_record(SshLogRecord::new(
+                "ssh.agent_forward",
+                "unrequested_channel",
+                "Server opened an agent channel without agent forwarding enabled",
+            )));
+            session.close(channel);
+            return Ok(());
+        }
+
+        let relay_failed = self.log_record(SshLogRecord::new(
+            "ssh.agent_forward",
+            "local_relay_failed",
+            "Local ssh-agent relay stopped",
+        ));
+        match AgentForwardChannel::open(
+            self.agent_keys.clone(),
+            self.link.clone(),
+            channel,
+            relay_failed,
+        )
+        .await
+        {
+            Ok(agent) => {
+                ssh_log::debug(self.log_record(
+                    SshLogRecord::new(
+                        "ssh.agent_forward",
+                        "channel_opened",
+                        "Server opened an agent forwarding channel",
+                    )
+                    .field("backend", agent.backend_name()),
+                ));
+                self.agent_channels.lock().await.insert(channel, agent);
+            }
+            Err(err) => {
+                ssh_log::warn(self.log_record(
+                    SshLogRecord::new(
+                        "ssh.agent_forward",
+                        "backend_unavailable",
+                        "No agent backend available for forwarding",
+                    )
+                    .field("error", err),
+                ));
+                session.close(channel);
+            }
+        }
+        Ok(())
+    }
+
+    async fn data(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        let mut channels = self.agent_channels.lock().await;
+        let Some(agent) = channels.get_mut(&channel) else {
+            return Ok(());
+        };
+
+        match agent.feed(data) {
+            Ok(requests) => {
+                for request in requests {
+                    if is_sign_request(request.message_type) {
+                        ssh_log::info(self.log_record(
+                            SshLogRecord::new(
+                                "ssh.agent_forward",
+                                "sign_request",
+                                "Remote host requested a signature through the forwarded agent",
+                            )
+                            .field("backend", agent.backend_name()),
+                        ));
+                    }
+                    // 本地 agent 的应答由转发任务异步写回
+                    if let Some(response) = request.response {
+                        session.data(channel, CryptoVec::from_slice(&response));
+                    }
+                }
+            }
+            Err(err) => {
+                ssh_log::warn(self.log_record(
+                    SshLogRecord::new(
+                        "ssh.agent_forward",
+                        "request_failed",
+                        "Agent forwarding request failed; closing channel",
+                    )
+                    .field("error", err),
+                ));
+                channels.remove(&channel);
+                session.close(channel);
+            }
+        }
+        Ok(())
+    }
+
+    async fn channel_close(
+        &mut self,
+        channel: ChannelId,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.agent_channels.lock().await.remove(&channel);
+        Ok(())
     }
 }
@@ -0,0 +1,196 @@
+use serde::Serialize;
+
+use crate::commands::ssh::utils::shell_quote;
+use crate::models::PersistentSessionMode;
+
+const SESSION_NAME_MAX_LEN: usize = 64;
+const DEFAULT_SESSION_NAME_PREFIX: &str = "piterm-";
+// 远端未安装 tmux 时 list 命令输出该标记
+const TMUX_MISSING_MARKER: &str = "__PITERM_NO_TMUX__";
+// 远端已有同名 tmux/screen 会话时 exists 命令输出该标记
+const SESSION_EXISTS_MARKER: &str = "__PITERM_SESSION_EXISTS__";
+
+// 🟢 PiTerm 会话对应的远端 tmux/screen 会话，随自动重连移交给新连接
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistentShell {
+    pub mode: PersistentSessionMode,
+    pub name: String,
+    // PiTerm 按会话 ID 创建的会话，关闭标签页时一并结束；手动附加的已有会话保留
+    pub owned: bool,
+    // 本次启动前远端还没有该会话、由 PiTerm 新建；只有新建时才输入工作目录与启动命令
+    #[serde(skip)]
+    pub created: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteTmuxSession {
+    pub name: String,
+    pub windows: u32,
+    pub attached: bool,
+    pub created_at: Option<i64>,
+    // 当前 PiTerm 会话记录的就是该 tmux 会话
+    pub current: bool,
+}
+
+// 名称会拼进远端命令，只允许不需要转义的字符；tmux 以 ':' '.' 分隔目标，同样禁止
+pub fn validate_session_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > SESSION_NAME_MAX_LEN {
+        return Err(format!(
+            "Session name must be 1-{} characters",
+            SESSION_NAME_MAX_LEN
+        ));
+    }
+    if !name
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_'))
+    {
+        return Err(format!(
+            "Session name may only contain letters, digits, '-' and '_': {}",
+            name
+        ));
+    }
+    Ok(())
+}
+
+// 由 PiTerm 会话 ID 派生稳定的远端会话名，前端以同一 ID 重新连接时也能附加回去
+pub fn default_session_name(session_id: &str) -> String {
+    let suffix: String = session_id
+        .chars()
+        .filter(|ch| ch.is_ascii_alphanumeric())
+        .take(SESSION_NAME_MAX_LEN - DEFAULT_SESSION_NAME_PREFIX.len())
+        .collect();
+    if suffix.is_empty() {
+        return format!("{}session", DEFAULT_SESSION_NAME_PREFIX);
+    }
+    format!("{}{}", DEFAULT_SESSION_NAME_PREFIX, suffix)
+}
+
+impl PersistentShell {
+    pub fn for_session(mode: PersistentSessionMode, session_id: &str) -> Self {
+        Self {
+            mode,
+            name: default_session_name(session_id),
+            owned: true,
+            created: false,
+        }
+    }
+
+    pub fn program(&self) -> &'static str {
+        match self.mode {
+            PersistentSessionMode::Tmux => "tmux",
+            PersistentSessionMode::Screen => "screen",
+        }
+    }
+
+    // 🟢 作为 exec 请求发送的启动命令；远端缺少 tmux/screen 时提示后退回登录 shell
+    // command 为固定命令，只在新建会话时作为会话内的程序运行，附加到已有会话时被忽略
+    pub fn launch_command(&self, command: Option<&str>) -> String {
+        let mut attach = match self.mode {
+            PersistentSessionMode::Tmux => format!("tmux new-session -A -s '{}'", self.name),
+            PersistentSessionMode::Screen => format!("screen -xRR -S '{}'", self.name),
+        };
+        if let Some(command) = command {
+            let command = shell_quote(command);
+            match self.mode {
+                PersistentSessionMode::Tmux => attach.push_str(&format!(" {}", command)),
+                PersistentSessionMode::Screen => attach.push_str(&format!(" sh -c {}", command)),
+            }
+        }
+        format!(
+            "if command -v {program} >/dev/null 2>&1; then exec {attach}; else echo 'PiTerm: {program} not found, starting a login shell' >&2; exec \"${{SHELL:-/bin/sh}}\" -l; fi",
+            program = self.program(),
+            attach = attach,
+        )
+    }
+
+    // 🟢 启动前探测同名会话是否已存在 (tmux 以 '=' 要求精确匹配，screen 按 pid.name 匹配)
+    pub fn exists_command(&self) -> String {
+        match self.mode {
+            PersistentSessionMode::Tmux => format!(
+                "tmux has-session -t '={}' 2>/dev/null && echo {}",
+                self.name, SESSION_EXISTS_MARKER
+            ),
+            PersistentSessionMode::Screen => format!(
+                "screen -ls 2>/dev/null | grep -q '[0-9]\\.{}[[:space:]]' && echo {}",
+                self.name, SESSION_EXISTS_MARKER
+            ),
+        }
+    }
+
+    pub fn exists_in_output(output: &str) -> bool {
+        output
+            .lines()
+            .any(|line| line.trim() == SESSION_EXISTS_MARKER)
+    }
+
+    // 🟢 关闭标签页时结束远端会话；会话已不存在时不报错
+    pub fn kill_command(&self) -> String {
+        match self.mode {
+            PersistentSessionMode::Tmux => {
+                format!("tmux kill-session -t '{}' 2>/dev/null; true", self.name)
+            }
+            PersistentSessionMode::Screen => {
+                format!("screen -S '{}' -X quit >/dev/null 2>&1; true", self.name)
+            }
+        }
+    }
+}
+
+// tmux list-sessions 的输出格式：名称、窗口数、已附加客户端数、创建时间 (Unix 秒)；没有会话时输出为空
+pub fn tmux_list_sessions_command() -> String {
+    format!(
+        "command -v tmux >/dev/null 2>&1 || {{ echo {}; exit 0; }}; tmux list-sessions -F '#{{session_name}}\t#{{session_windows}}\t#{{session_attached}}\t#{{session_created}}' 2>/dev/null",
+        TMUX_MISSING_MARKER
+    )
+}
+
+// 在已运行的交互式 shell 中输入的附加命令；已处于 tmux 内时切换客户端而不是嵌套
+pub fn tmux_attach_input(name: &str) -> String {
+    format!(
+        "if [ -n \"$TMUX\" ]; then tmux switch-client -t '{name}'; else tmux attach-session -t '{name}'; fi\r",
+        name = name
+    )
+}
+
+pub fn parse_tmux_sessions(
+    output: &str,
+    current: Option<&str>,
+) -> Result<Vec<RemoteTmuxSession>, String> {
+    if output
+        .lines()
+        .any(|line| line.trim() == TMUX_MISSING_MARKER)
+    {
+        return Err("TMUX_NOT_FOUND: tmux is not installed on the remote host".to_string());
+    }
+    let mut sessions: Vec<RemoteTmuxSession> = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim_end_matches('\r').split('\t');
+            let name = fields.next()?.trim();
+            if name.is_empty() {
+                return None;
+            }
+            let windows = fields
+                .next()
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(0);
+            let attached = fields
+                .next()
+                .and_then(|v| v.trim().parse::<u32>().ok())
+                .unwrap_or(0)
+                > 0;
+            let created_at = fields.next().and_then(|v| v.trim().parse().ok());
+            Some(RemoteTmuxSession {
+                name: name.to_string(),
+                windows,
+                attached,
+                created_at,
+                current: current == Some(name),
+            })
+        })
+        .collect();
+    sessions.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(sessions)
+}
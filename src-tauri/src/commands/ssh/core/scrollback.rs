@@ -0,0 +1,207 @@
+use std::collections::VecDeque;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::utils::ansi::AnsiStripper;
+
+// 没有换行的超长输出 (进度条等) 超过此长度时强制断行
+const SCROLLBACK_MAX_LINE_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackLine {
+    pub number: u64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackPage {
+    pub lines: Vec<ScrollbackLine>,
+    // 当前仍保留的最旧/最新行号；没有任何输出时 last_line 为 first_line - 1
+    pub first_line: u64,
+    pub last_line: u64,
+    pub has_more_before: bool,
+    pub has_more_after: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackMatch {
+    pub line: u64,
+    pub start_line: u64,
+    pub end_line: u64,
+    pub lines: Vec<ScrollbackLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackSearchResult {
+    // 从新到旧排列
+    pub matches: Vec<ScrollbackMatch>,
+    // 结果被截断时，作为下一页的 before_line 继续向更早的行搜索
+    pub next_before_line: Option<u64>,
+    pub first_line: u64,
+    pub last_line: u64,
+}
+
+// 🟢 按行索引的回滚缓冲：去除控制序列后的纯文本，行号单调递增，超出上限时丢弃最旧的行
+pub struct ScrollbackLines {
+    lines: VecDeque<String>,
+    first_number: u64,
+    // 尚未换行的当前行，查询时作为最后一行返回
+    pending: String,
+    stripper: AnsiStripper,
+    bytes: usize,
+    max_lines: usize,
+    max_bytes: usize,
+}
+
+impl ScrollbackLines {
+    pub fn new(max_lines: usize, max_bytes: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            first_number: 1,
+            pending: String::new(),
+            stripper: AnsiStripper::default(),
+            bytes: 0,
+            max_lines: max_lines.max(1),
+            max_bytes,
+        }
+    }
+
+    pub fn push(&mut self, text: &str) {
+        self.stripper.push(text, &mut self.pending);
+        while let Some(pos) = self.pending.find('\n') {
+            let mut line: String = self.pending.drain(..=pos).collect();
+            line.pop();
+            self.commit_line(line);
+        }
+        if self.pending.len() >= SCROLLBACK_MAX_LINE_BYTES {
+            let line = std::mem::take(&mut self.pending);
+            self.commit_line(line);
+        }
+    }
+
+    fn commit_line(&mut self, line: String) {
+        self.bytes += line.len();
+        self.lines.push_back(line);
+        while self.lines.len() > self.max_lines
+            || (self.bytes > self.max_bytes && self.lines.len() > 1)
+        {
+            if let Some(dropped) = self.lines.pop_front() {
+                self.bytes -= dropped.len();
+                self.first_number += 1;
+            }
+        }
+    }
+
+    pub fn first_line(&self) -> u64 {
+        self.first_number
+    }
+
+    // 最新一行的行号 (包含未换行的当前行)
+    pub fn last_line(&self) -> u64 {
+        let committed = self.first_number + self.lines.len() as u64;
+        if self.pending.is_empty() {
+            committed - 1
+        } else {
+            committed
+        }
+    }
+
+    fn line(&self, number: u64) -> Option<&str> {
+        if number < self.first_number {
+            return None;
+        }
+        let index = (number - self.first_number) as usize;
+        match index.cmp(&self.lines.len()) {
+            std::cmp::Ordering::Less => Some(self.lines[index].as_str()),
+            std::cmp::Ordering::Equal if !self.pending.is_empty() => Some(self.pending.as_str()),
+            _ => None,
+        }
+    }
+
+    fn collect(&self, start: u64, end: u64) -> Vec<ScrollbackLine> {
+        (start..=end)
+            .filter_map(|number| {
+                self.line(number).map(|text| ScrollbackLine {
+                    number,
+                    text: text.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    // start 为空时返回最后 limit 行；超出保留范围的起始行号会被夹到最旧一行
+    pub fn page(&self, start: Option<u64>, limit: usize) -> ScrollbackPage {
+        let first = self.first_line();
+        let last = self.last_line();
+        let limit = limit.max(1) as u64;
+        let start = match start {
+            Some(start) => start.max(first),
+            None => last.saturating_sub(limit - 1).max(first),
+        };
+        let end = start.saturating_add(limit - 1).min(last);
+
+        ScrollbackPage {
+            lines: if start <= end {
+                self.collect(start, end)
+            } else {
+                Vec::new()
+            },
+            first_line: first,
+            last_line: last,
+            has_more_before: start > first,
+            has_more_after: end < last,
+        }
+    }
+
+    // 🟢 从新到旧逐行匹配正则，每个命中附带前后 context 行
+    pub fn search(
+        &self,
+        pattern: &Regex,
+        context: usize,
+        limit: usize,
+        before_line: Option<u64>,
+    ) -> ScrollbackSearchResult {
+        let first = self.first_line();
+        let last = self.last_line();
+        let upper = before_line
+            .map(|before| before.saturating_sub(1).min(last))
+            .unwrap_or(last);
+        let context = context as u64;
+        let limit = limit.max(1);
+
+        let mut matches = Vec::new();
+        let mut next_before_line = None;
+        let mut number = upper;
+        while number >= first && number > 0 {
+            if let Some(text) = self.line(number) {
+                if pattern.is_match(text) {
+                    if matches.len() == limit {
+                        next_before_line = matches.last().map(|found: &ScrollbackMatch| found.line);
+                        break;
+                    }
+                    let start_line = number.saturating_sub(context).max(first);
+                    let end_line = (number + context).min(last);
+                    matches.push(ScrollbackMatch {
+                        line: number,
+                        start_line,
+                        end_line,
+                        lines: self.collect(start_line, end_line),
+                    });
+                }
+            }
+            number -= 1;
+        }
+
+        ScrollbackSearchResult {
+            matches,
+            next_before_line,
+            first_line: first,
+            last_line: last,
+        }
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, NaiveDate};
+
+use crate::models::{SessionLogConfig, SessionLogRotation, SshConfig};
+use crate::utils::ansi::AnsiStripper;
+use crate::utils::ssh_log::{
+    self, has_unterminated_pem_block, redact_secrets, SshLogRecord, REDACTED_VALUE,
+};
+
+pub const SESSION_LOGS_DIR_NAME: &str = "session-logs";
+// 长时间没有换行的输出 (进度条等) 超过此长度时强制落盘
+const SESSION_LOG_PENDING_LIMIT: usize = 16 * 1024;
+const SESSION_LOG_MAX_ROTATED_FILES: u32 = 9;
+const SESSION_LOG_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+// 脱敏时等待 PEM 块结束行的最大长度，超过后整块按密钥遮蔽
+const SESSION_LOG_PEM_BLOCK_LIMIT: usize = 64 * 1024;
+// 超长块先输出遮蔽行，只保留开始标记继续遮蔽，直到 END 行
+const SESSION_LOG_PEM_CONTINUED: &str = "-----BEGIN PEM BLOCK-----";
+
+// 🟢 路径模板必须是相对路径，且不能通过 .. 跳出 session-logs 目录
+pub fn validate_session_log_template(template: &str) -> Result<(), String> {
+    let template = template.trim();
+    if template.is_empty() {
+        return Err("Session log path template is empty".to_string());
+    }
+    let path = Path::new(template);
+    let escapes = path.components().any(|component| {
+        matches!(
+            component,
+            Component::ParentDir | Component::RootDir | Component::Prefix(_)
+        )
+    });
+    if escapes || path.is_absolute() {
+        return Err(format!(
+            "Session log path template must stay inside the log directory: {}",
+            template
+        ));
+    }
+    if template.ends_with('/') || template.ends_with('\\') {
+        return Err("Session log path template must end with a file name".to_string());
+    }
+    Ok(())
+}
+
+// 占位符的值可能来自服务器名称，替换掉路径分隔符等不安全字符
+fn sanitize_component(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || matches!(ch, '-' | '_' | '.' | '@') {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let cleaned = cleaned.trim_matches('.').to_string();
+    if cleaned.is_empty() {
+        "_".to_string()
+    } else {
+        cleaned
+    }
+}
+
+struct TemplateContext {
+    server: String,
+    host: String,
+    user: String,
+    session: String,
+    opened_at: DateTime<Local>,
+}
+
+impl TemplateContext {
+    fn render(&self, template: &str, now: DateTime<Local>) -> PathBuf {
+        let rendered = template
+            .trim()
+            .replace("{server}", &self.server)
+            .replace("{host}", &self.host)
+            .replace("{user}", &self.user)
+            .replace("{session}", &self.session)
+            .replace("{date}", &now.format("%Y-%m-%d").to_string())
+            .replace("{time}", &self.opened_at.format("%H%M%S").to_string());
+        rendered
+            .split(['/', '\\'])
+            .filter(|part| !part.is_empty())
+            .map(sanitize_component)
+            .collect()
+    }
+}
+
+fn open_append(path: &Path) -> Result<(BufWriter<File>, u64), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open session log {}: {}", path.display(), e))?;
+    let size = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+    Ok((BufWriter::new(file), size))
+}
+
+// name.log -> name.<suffix>.log
+fn rotated_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file_name = match path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, suffix, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, suffix),
+    };
+    path.with_file_name(file_name)
+}
+
+// 🟢 同一路径的日志文件由所有标签页共享一个句柄：整行写入不会交错，轮转也只执行一次
+struct SessionLogFile {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    file_date: NaiveDate,
+    file_bytes: u64,
+}
+
+type SharedSessionLogFile = Arc<Mutex<SessionLogFile>>;
+
+fn open_session_log_files() -> &'static Mutex<HashMap<PathBuf, Weak<Mutex<SessionLogFile>>>> {
+    static FILES: OnceLock<Mutex<HashMap<PathBuf, Weak<Mutex<SessionLogFile>>>>> = OnceLock::new();
+    FILES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn acquire_session_log_file(path: &Path) -> Result<SharedSessionLogFile, String> {
+    let mut files = match open_session_log_files().lock() {
+        Ok(g) => g,
+        Err(p) => p.into_inner(),
+    };
+    files.retain(|_, file| file.strong_count() > 0);
+    if let Some(file) = files.get(path).and_then(Weak::upgrade) {
+        return Ok(file);
+    }
+    let (writer, file_bytes) = open_append(path)?;
+    let file = Arc::new(Mutex::new(SessionLogFile {
+        path: path.to_path_buf(),
+        writer: Some(writer),
+        file_date: Local::now().date_naive(),
+        file_bytes,
+    }));
+    files.insert(path.to_path_buf(), Arc::downgrade(&file));
+    Ok(file)
+}
+
+impl SessionLogFile {
+    fn write_entry(&mut self, config: &SessionLogConfig, entry: &str) -> Result<(), String> {
+        let rotated = self.rotate_if_needed(config, entry.len() as u64);
+        if let Some(writer) = self.writer.as_mut() {
+            if writer.write_all(entry.as_bytes()).is_ok() {
+                self.file_bytes += entry.len() as u64;
+            }
+        }
+        rotated
+    }
+
+    fn flush(&mut self) {
+        if let Some(writer) = self.writer.as_mut() {
+            let _ = writer.flush();
+        }
+    }
+
+    fn reopen(&mut self) -> Result<(), String> {
+        let (writer, file_bytes) = open_append(&self.path)?;
+        self.writer = Some(writer);
+        self.file_bytes = file_bytes;
+        Ok(())
+    }
+
+    // 关闭当前文件后执行改名，Windows 上打开中的文件无法改名
+    fn close_current(&mut self) {
+        if let Some(mut writer) = self.writer.take() {
+            let _ = writer.flush();
+        }
+    }
+
+    fn rotate_if_needed(&mut self, config: &SessionLogConfig, incoming: u64) -> Result<(), String> {
+        let today = Local::now().date_naive();
+
+        if today != self.file_date {
+            let previous_date = self.file_date;
+            self.file_date = today;
+            if config.rotation == SessionLogRotation::Daily && self.file_bytes > 0 {
+                self.close_current();
+                let archived =
+                    rotated_path(&self.path, &previous_date.format("%Y-%m-%d").to_string());
+                let rename_result = fs::rename(&self.path, &archived);
+                self.reopen()?;
+                return rename_result.map_err(|e| e.to_string());
+            }
+        }
+
+        let over_limit = config.rotation == SessionLogRotation::Size
+            && config.max_bytes > 0
+            && self.file_bytes > 0
+            && self.file_bytes + incoming > config.max_bytes;
+        if over_limit {
+            self.close_current();
+            let _ = fs::remove_file(rotated_path(
+                &self.path,
+                &SESSION_LOG_MAX_ROTATED_FILES.to_string(),
+            ));
+            for index in (1..SESSION_LOG_MAX_ROTATED_FILES).rev() {
+                let from = rotated_path(&self.path, &index.to_string());
+                if from.exists() {
+                    let _ = fs::rename(&from, rotated_path(&self.path, &(index + 1).to_string()));
+                }
+            }
+            let rename_result = fs::rename(&self.path, rotated_path(&self.path, "1"));
+            self.reopen()?;
+            rename_result.map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+// 🟢 会话日志写入器：由 shell 读取任务驱动，按行加时间戳写入纯文本
+pub struct SessionLogWriter {
+    base_dir: PathBuf,
+    config: SessionLogConfig,
+    context: TemplateContext,
+    path: PathBuf,
+    file: SharedSessionLogFile,
+    path_date: NaiveDate,
+    stripper: AnsiStripper,
+    pending: String,
+    pending_started: DateTime<Local>,
+    // 脱敏模式下尚未收到 END 行的 PEM 块及其开始时间
+    pem_block: Option<(DateTime<Local>, String)>,
+    last_flush: Instant,
+}
+
+impl SessionLogWriter {
+    // 服务器未开启会话日志时返回 None
+    pub fn open(
+        app_data_dir: &Path,
+        config: &SshConfig,
+        session_id: &str,
+    ) -> Result<Option<Self>, String> {
+        let Some(log_config) = config.session_log.as_ref().filter(|log| log.enabled) else {
+            return Ok(None);
+        };
+        validate_session_log_template(&log_config.path_template)?;
+
+        let now = Local::now();
+        let context = TemplateContext {
+            server: sanitize_component(config.name.as_deref().unwrap_or(&config.id)),
+            host: sanitize_component(&config.host),
+            user: sanitize_component(&config.username),
+            session: sanitize_component(session_id),
+            opened_at: now,
+        };
+        let base_dir = app_data_dir.join(SESSION_LOGS_DIR_NAME);
+        let path = base_dir.join(context.render(&log_config.path_template, now));
+        let file = acquire_session_log_file(&path)?;
+
+        let mut log = Self {
+            base_dir,
+            config: log_config.clone(),
+            context,
+            path,
+            file,
+            path_date: now.date_naive(),
+            stripper: AnsiStripper::default(),
+            pending: String::new(),
+            pending_started: now,
+            pem_block: None,
+            last_flush: Instant::now(),
+        };
+        let banner = format!(
+            "--- session {} started: {}@{}:{} ---",
+            session_id, config.username, config.host, config.port
+        );
+        log.write_line(now, &banner);
+        Ok(Some(log))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write(&mut self, text: &str) {
+        if self.pending.is_empty() {
+            self.pending_started = Local::now();
+        }
+        self.stripper.push(text, &mut self.pending);
+
+        while let Some(pos) = self.pending.find('\n') {
+            let line: String = self.pending.drain(..=pos).collect();
+            let started = self.pending_started;
+            self.write_line(started, line.trim_end_matches('\n'));
+            self.pending_started = Local::now();
+        }
+        if self.pending.len() >= SESSION_LOG_PENDING_LIMIT {
+            let line = std::mem::take(&mut self.pending);
+            let started = self.pending_started;
+            self.write_line(started, &line);
+        }
+
+        if self.last_flush.elapsed() >= SESSION_LOG_FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    pub fn close(mut self, reason: &str) {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            let started = self.pending_started;
+            self.write_line(started, &line);
+        }
+        if let Some((started, _)) = self.pem_block.take() {
+            self.write_entry(started, REDACTED_VALUE);
+        }
+        self.write_line(Local::now(), &format!("--- session closed: {} ---", reason));
+        self.flush();
+    }
+
+    fn write_line(&mut self, at: DateTime<Local>, line: &str) {
+        if !self.config.redact {
+            self.write_entry(at, line);
+            return;
+        }
+
+        // 🟢 私钥等 PEM 块跨多行，收齐 END 行后整体脱敏
+        let (started, block) = match self.pem_block.take() {
+            Some((started, mut block)) => {
+                block.push('\n');
+                block.push_str(line);
+                (started, block)
+            }
+            None => (at, line.to_string()),
+        };
+        if has_unterminated_pem_block(&block) {
+            if block.len() > SESSION_LOG_PEM_BLOCK_LIMIT {
+                self.write_entry(started, REDACTED_VALUE);
+                self.pem_block = Some((started, SESSION_LOG_PEM_CONTINUED.to_string()));
+            } else {
+                self.pem_block = Some((started, block));
+            }
+            return;
+        }
+        for redacted in redact_secrets(&block).split('\n') {
+            self.write_entry(started, redacted);
+        }
+    }
+
+    fn write_entry(&mut self, at: DateTime<Local>, line: &str) {
+        let entry = format!("[{}] {}\n", at.format("%Y-%m-%d %H:%M:%S"), line);
+
+        let result = self.switch_dated_path().and_then(|_| {
+            let mut file = match self.file.lock() {
+                Ok(g) => g,
+                Err(p) => p.into_inner(),
+            };
+            file.write_entry(&self.config, &entry)
+        });
+        if let Err(err) = result {
+            ssh_log::warn(
+                SshLogRecord::new(
+                    "ssh.session_log",
+                    "rotate_failed",
+                    "Session log rotation failed",
+                )
+                .session_id(self.context.session.clone())
+                .field("path", ssh_log::mask_path(&self.path.to_string_lossy()))
+                .field("error", err),
+            );
+        }
+    }
+
+    // 模板含 {date} 时跨天切换到新文件 (同样与其他标签页共享)
+    fn switch_dated_path(&mut self) -> Result<(), String> {
+        let now = Local::now();
+        if now.date_naive() == self.path_date {
+            return Ok(());
+        }
+        self.path_date = now.date_naive();
+        let next_path = self
+            .base_dir
+            .join(self.context.render(&self.config.path_template, now));
+        if next_path != self.path {
+            self.file = acquire_session_log_file(&next_path)?;
+            self.path = next_path;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        match self.file.lock() {
+            Ok(mut file) => file.flush(),
+            Err(p) => p.into_inner().flush(),
+        }
+        self.last_flush = Instant::now();
+    }
+}
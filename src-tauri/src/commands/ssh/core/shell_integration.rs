@@ -0,0 +1,332 @@
+use std::time::Instant;
+
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::utils::ansi::strip_ansi;
+
+// 超长的 OSC 负载 (例如异常输出) 直接丢弃，避免无限累积
+const OSC_PAYLOAD_LIMIT: usize = 8 * 1024;
+// 未带 cmdline 参数时，从提示符后的回显中截取命令文本
+const PROMPT_INPUT_LIMIT: usize = 4 * 1024;
+
+// 🟢 注入到远端 bash/zsh 的集成脚本：提示符前后输出 OSC 133 标记，命令行通过 cmdline_url 参数上报，cwd 通过 OSC 7 上报
+// bash 以 DEBUG trap 在提示符后的第一条命令前上报 133;C (保留已有的 DEBUG trap)；
+// 历史没有新增条目时 (HISTCONTROL=ignoredups/ignorespace) 改用 $BASH_COMMAND 作为命令文本
+pub const SHELL_INTEGRATION_SCRIPT: &str = r#"if [ -z "$PITERM_SHELL_INTEGRATION" ] && { [ -n "$BASH_VERSION" ] || [ -n "$ZSH_VERSION" ]; }; then
+PITERM_SHELL_INTEGRATION=1
+__piterm_urlencode() {
+  local LC_ALL=C s="$1" o="" c i
+  for (( i = 0; i < ${#s}; i++ )); do
+    c="${s:$i:1}"
+    case "$c" in
+      [a-zA-Z0-9._~/-]) o+="$c" ;;
+      *) o+=$(printf '%%%02X' "'$c") ;;
+    esac
+  done
+  printf '%s' "$o"
+}
+__piterm_report_cwd() {
+  printf '\e]7;file://%s%s\a' "${HOSTNAME:-$HOST}" "$(__piterm_urlencode "$PWD")"
+}
+if [ -n "$ZSH_VERSION" ]; then
+  __piterm_precmd() {
+    printf '\e]133;D;%s\a' "$?"
+    __piterm_report_cwd
+    printf '\e]133;A\a'
+  }
+  __piterm_preexec() {
+    printf '\e]133;C;cmdline_url=%s\a' "$(__piterm_urlencode "$1")"
+  }
+  autoload -Uz add-zsh-hook
+  add-zsh-hook precmd __piterm_precmd
+  add-zsh-hook preexec __piterm_preexec
+  PS1="$PS1"$'%{\e]133;B\a%}'
+else
+  __piterm_last_history=""
+  __piterm_at_prompt=""
+  __piterm_prompt() {
+    local ret=$?
+    __piterm_at_prompt=""
+    printf '\e]133;D;%s\a' "$ret"
+    __piterm_report_cwd
+    printf '\e]133;A\a'
+    __piterm_last_history=$(HISTTIMEFORMAT= builtin history 1)
+    return $ret
+  }
+  __piterm_prompt_ready() {
+    __piterm_at_prompt=1
+  }
+  __piterm_preexec() {
+    [ -n "$__piterm_at_prompt" ] && [ -z "$COMP_LINE" ] || return
+    [ "$BASH_COMMAND" = "__piterm_prompt" ] && return
+    __piterm_at_prompt=""
+    local entry cmd
+    entry=$(HISTTIMEFORMAT= builtin history 1)
+    if [ "$entry" != "$__piterm_last_history" ]; then
+      cmd=$(printf '%s' "$entry" | sed 's/^ *[0-9]* *//')
+    else
+      cmd=$BASH_COMMAND
+    fi
+    printf '\e]133;C;cmdline_url=%s\a' "$(__piterm_urlencode "$cmd")"
+  }
+  __piterm_prev_debug=$(trap -p DEBUG)
+  __piterm_prev_debug=${__piterm_prev_debug#"trap -- '"}
+  __piterm_prev_debug=${__piterm_prev_debug%"' DEBUG"}
+  PROMPT_COMMAND="__piterm_prompt${PROMPT_COMMAND:+;$PROMPT_COMMAND};__piterm_prompt_ready"
+  trap '__piterm_preexec; eval "$__piterm_prev_debug"' DEBUG
+  PS1="$PS1"'\[\e]133;B\a\]'
+fi
+fi"#;
+
+// 🟢 一条由 shell 集成标记界定的命令：OSC 133;C 开始执行，OSC 133;D 结束并带退出码
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellCommandEvent {
+    pub command: String,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    pub cwd: Option<String>,
+    // Unix 秒，命令开始执行的时间
+    pub executed_at: i64,
+}
+
+pub enum ShellIntegrationEvent {
+    Command(ShellCommandEvent),
+    // OSC 7 上报的工作目录发生变化
+    Cwd(String),
+}
+
+struct RunningCommand {
+    command: String,
+    cwd: Option<String>,
+    started: Instant,
+    executed_at: i64,
+}
+
+enum ScanState {
+    Ground,
+    Escape,
+    Osc,
+    // OSC 内遇到 ESC，等待 ST (ESC \)
+    OscEscape,
+}
+
+// 🟢 从 PTY 输出流中识别 OSC 133 (提示符/命令/退出码) 与 OSC 7 (工作目录)，跨数据块保持状态
+pub struct ShellIntegrationParser {
+    state: ScanState,
+    payload: String,
+    payload_overflow: bool,
+    // 处于提示符 (133;B) 与执行 (133;C) 之间时收集回显的输入
+    capturing_input: bool,
+    prompt_input: String,
+    cwd: Option<String>,
+    running: Option<RunningCommand>,
+    // 收到过任意 133 标记
+    active: bool,
+    // 133;C 之后、133;D (或下一个提示符) 之前
+    executing: bool,
+    // 收到 133;A 后置位，由 take_prompt 取走
+    prompt_shown: bool,
+}
+
+impl Default for ShellIntegrationParser {
+    fn default() -> Self {
+        Self {
+            state: ScanState::Ground,
+            payload: String::new(),
+            payload_overflow: false,
+            capturing_input: false,
+            prompt_input: String::new(),
+            cwd: None,
+            running: None,
+            active: false,
+            executing: false,
+            prompt_shown: false,
+        }
+    }
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' && index + 3 <= bytes.len() {
+            let hex = std::str::from_utf8(&bytes[index + 1..index + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                index += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// file://host/path -> /path (忽略主机名，路径经百分号解码)
+fn parse_file_url(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    let path = percent_decode(path);
+    (!path.is_empty()).then_some(path)
+}
+
+impl ShellIntegrationParser {
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn command_running(&self) -> bool {
+        self.executing
+    }
+
+    // 上次调用以来是否出现过新的提示符
+    pub fn take_prompt(&mut self) -> bool {
+        std::mem::take(&mut self.prompt_shown)
+    }
+
+    pub fn push(&mut self, text: &str) -> Vec<ShellIntegrationEvent> {
+        let mut events = Vec::new();
+        for ch in text.chars() {
+            match self.state {
+                ScanState::Ground => {
+                    if ch == '\x1b' {
+                        self.state = ScanState::Escape;
+                    } else {
+                        self.capture(ch);
+                    }
+                }
+                ScanState::Escape => {
+                    if ch == ']' {
+                        self.payload.clear();
+                        self.payload_overflow = false;
+                        self.state = ScanState::Osc;
+                    } else {
+                        self.capture('\x1b');
+                        self.capture(ch);
+                        self.state = ScanState::Ground;
+                    }
+                }
+                ScanState::Osc => match ch {
+                    '\x07' => self.finish_osc(&mut events),
+                    '\x1b' => self.state = ScanState::OscEscape,
+                    _ => {
+                        if self.payload.len() < OSC_PAYLOAD_LIMIT {
+                            self.payload.push(ch);
+                        } else {
+                            self.payload_overflow = true;
+                        }
+                    }
+                },
+                ScanState::OscEscape => {
+                    if ch == '\\' {
+                        self.finish_osc(&mut events);
+                    } else {
+                        self.state = ScanState::Ground;
+                    }
+                }
+            }
+        }
+        events
+    }
+
+    fn capture(&mut self, ch: char) {
+        if self.capturing_input && self.prompt_input.len() < PROMPT_INPUT_LIMIT {
+            self.prompt_input.push(ch);
+        }
+    }
+
+    fn finish_osc(&mut self, events: &mut Vec<ShellIntegrationEvent>) {
+        self.state = ScanState::Ground;
+        if self.payload_overflow {
+            return;
+        }
+        let payload = std::mem::take(&mut self.payload);
+        if let Some(url) = payload.strip_prefix("7;") {
+            if let Some(cwd) = parse_file_url(url) {
+                if self.cwd.as_deref() != Some(cwd.as_str()) {
+                    self.cwd = Some(cwd.clone());
+                    events.push(ShellIntegrationEvent::Cwd(cwd));
+                }
+            }
+            return;
+        }
+        let Some(mark) = payload.strip_prefix("133;") else {
+            return;
+        };
+        self.active = true;
+        let mut parts = mark.split(';');
+        match parts.next() {
+            Some("A") => {
+                self.capturing_input = false;
+                self.executing = false;
+                self.prompt_shown = true;
+            }
+            Some("B") => {
+                self.capturing_input = true;
+                self.prompt_input.clear();
+            }
+            Some("C") => {
+                self.executing = true;
+                self.start_command(parts);
+            }
+            Some("D") => {
+                self.executing = false;
+                let exit_code = parts.next().and_then(|code| code.trim().parse().ok());
+                if let Some(event) = self.finish_command(exit_code) {
+                    events.push(ShellIntegrationEvent::Command(event));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn start_command<'a>(&mut self, params: impl Iterator<Item = &'a str>) {
+        self.capturing_input = false;
+        let mut command = None;
+        for param in params {
+            if let Some(value) = param.strip_prefix("cmdline_url=") {
+                command = Some(percent_decode(value));
+            } else if let Some(value) = param.strip_prefix("cmdline=") {
+                command = Some(value.to_string());
+            }
+        }
+        // 未携带命令行参数的集成脚本：取回显的最后一行作为命令
+        let command = command.unwrap_or_else(|| {
+            strip_ansi(&self.prompt_input)
+                .lines()
+                .map(str::trim)
+                .rev()
+                .find(|line| !line.is_empty())
+                .unwrap_or_default()
+                .to_string()
+        });
+        self.prompt_input.clear();
+
+        let command = command.trim().to_string();
+        if command.is_empty() {
+            self.running = None;
+            return;
+        }
+        self.running = Some(RunningCommand {
+            command,
+            cwd: self.cwd.clone(),
+            started: Instant::now(),
+            executed_at: Utc::now().timestamp(),
+        });
+    }
+
+    fn finish_command(&mut self, exit_code: Option<i32>) -> Option<ShellCommandEvent> {
+        let running = self.running.take()?;
+        Some(ShellCommandEvent {
+            command: running.command,
+            exit_code,
+            duration_ms: running.started.elapsed().as_millis() as u64,
+            cwd: running.cwd,
+            executed_at: running.executed_at,
+        })
+    }
+}
@@ -1,12 +1,25 @@
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use tauri::{AppHandle, Emitter, Manager};
 
 use crate::commands::ssh::state::{
     get_ssh_session_if_instance, SshConnection, SshResizeRequest, SshWriteRequest,
-    TerminalExitEvent,
+    TerminalCwdEvent, TerminalExitEvent,
 };
+use crate::commands::history::record_shell_command;
+use crate::commands::snippet::internal_get_snippet_code;
+use crate::commands::ssh::runtime::queue_shell_write;
+use crate::commands::ssh::trigger_commands::{load_enabled_triggers, spawn_trigger_action};
+use crate::state::AppState;
 use crate::utils::ssh_log::{self, SshLogRecord};
 
+use crate::commands::ssh::utils::resolve_encoding;
+
+use super::session_log::SessionLogWriter;
+use super::shell_startup::{build_startup_input, ShellStartup};
+use super::shell_integration::{ShellCommandEvent, ShellIntegrationEvent, ShellIntegrationParser};
+use super::terminal_output::{encode_terminal_input, TerminalDecoder};
+use super::terminal_transfer::{run_terminal_transfer, TransferDetector};
 use super::SHELL_WRITE_BATCH_LIMIT;
 
 pub fn spawn_shell_writer_thread<W>(
@@ -32,28 +45,51 @@ pub fn spawn_shell_writer_thread<W>(
         let mut total_bytes_written = 0u64;
 
         while let Some(first_request) = write_rx.recv().await {
-            let mut payload = first_request.data;
-            let mut responders = vec![first_request.result_tx];
+            let mut batched_len = first_request.data.len();
+            let mut requests = vec![first_request];
 
-            while payload.len() < SHELL_WRITE_BATCH_LIMIT {
+            while batched_len < SHELL_WRITE_BATCH_LIMIT {
                 match write_rx.try_recv() {
                     Ok(request) => {
-                        payload.push_str(&request.data);
-                        responders.push(request.result_tx);
+                        batched_len += request.data.len();
+                        requests.push(request);
                     }
                     Err(tokio::sync::mpsc::error::TryRecvError::Empty) => break,
                     Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => break,
                 }
             }
 
+            let mut payload = String::with_capacity(batched_len);
+            // 录像只记录非敏感输入
+            let mut recorded = String::new();
+            let mut responders = Vec::with_capacity(requests.len());
+            for request in requests {
+                if !request.secret {
+                    recorded.push_str(&request.data);
+                }
+                payload.push_str(&request.data);
+                responders.push(request.result_tx);
+            }
+
             let payload_len = payload.len();
             
             use tokio::io::AsyncWriteExt;
             let write_result: Result<(), String> = match get_ssh_session_if_instance(&sessions, &id, instance_id) {
+                // 🟢 ZMODEM/trzsz 传输独占通道，键盘输入会破坏协议数据
+                Some(conn) if conn.file_transfer_active() => Err(
+                    "TERMINAL_TRANSFER_ACTIVE: Terminal input is paused during file transfer"
+                        .to_string(),
+                ),
                 Some(conn) if conn.shell_is_active() => {
-                    match write_half.write_all(payload.as_bytes()).await {
+                    let encoding = resolve_encoding(conn.config.encoding.as_deref());
+                    match write_half.write_all(&encode_terminal_input(encoding, &payload)).await {
                         Ok(_) => match write_half.flush().await {
-                            Ok(_) => Ok(()),
+                            Ok(_) => {
+                                if !recorded.is_empty() {
+                                    conn.record_input(&recorded);
+                                }
+                                Ok(())
+                            }
                             Err(err) => {
                                 let err: std::io::Error = err;
                                 Err(err.to_string())
@@ -103,6 +139,201 @@ pub fn spawn_shell_writer_thread<W>(
     });
 }
 
+// 🟢 原始字节写入回滚缓冲区；前端请求原始模式时额外推送 base64 字节流
+fn publish_shell_output(
+    app: &AppHandle,
+    conn: Option<&SshConnection>,
+    id: &str,
+    decoder: &mut TerminalDecoder,
+    session_log: &mut Option<SessionLogWriter>,
+    shell_integration: &mut ShellIntegrationParser,
+    data: &[u8],
+) {
+    if data.is_empty() {
+        return;
+    }
+    if let Some(conn) = conn {
+        match conn.output_history.lock() {
+            Ok(mut history) => history.push(data),
+            Err(poisoned) => poisoned.into_inner().push(data),
+        }
+        if conn.raw_output_enabled() {
+            let _ = app.emit(&format!("term-bytes-{}", id), BASE64.encode(data));
+        }
+    }
+
+    let text = decoder.decode(data);
+    if !text.is_empty() {
+        if let Some(conn) = conn {
+            match conn.scrollback.lock() {
+                Ok(mut scrollback) => scrollback.push(&text),
+                Err(poisoned) => poisoned.into_inner().push(&text),
+            }
+            conn.record_output(&text);
+        }
+        if let Some(log) = session_log.as_mut() {
+            log.write(&text);
+        }
+        let events = shell_integration.push(&text);
+        let triggers = conn.map(|conn| conn.match_triggers(&text)).unwrap_or_default();
+        let _ = app.emit(&format!("term-data-{}", id), text);
+        if let Some(conn) = conn {
+            conn.set_shell_integration_state(
+                shell_integration.is_active(),
+                shell_integration.command_running(),
+            );
+            for found in triggers {
+                spawn_trigger_action(app, conn, id, found);
+            }
+            for event in events {
+                match event {
+                    ShellIntegrationEvent::Command(command) => {
+                        publish_shell_command(app, conn, id, command)
+                    }
+                    ShellIntegrationEvent::Cwd(cwd) => {
+                        if conn.update_cwd(&cwd) {
+                            let _ = app.emit(
+                                &format!("term-cwd-{}", id),
+                                TerminalCwdEvent { cwd },
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 🟢 shell 集成识别出的命令：推送给前端并写入命令历史 (source = 'shell-integration')
+fn publish_shell_command(
+    app: &AppHandle,
+    conn: &SshConnection,
+    id: &str,
+    command: ShellCommandEvent,
+) {
+    let _ = app.emit(&format!("term-command-{}", id), command.clone());
+
+    let db = app.state::<AppState>().db.clone();
+    let server_id = conn.config.id.clone();
+    let session_id = id.to_string();
+    tokio::spawn(async move {
+        if let Err(err) = record_shell_command(&db, &server_id, &session_id, &command).await {
+            ssh_log::warn(
+                SshLogRecord::new(
+                    "ssh.shell_integration",
+                    "history_record_failed",
+                    "Failed to record shell integration command",
+                )
+                .session_id(session_id)
+                .server_id(server_id)
+                .field("error", err),
+            );
+        }
+    });
+}
+
+// 🟢 服务器开启了纯文本会话日志时打开写入器，失败只记录日志不影响会话
+fn open_session_log(
+    app: &AppHandle,
+    conn: &SshConnection,
+    id: &str,
+    instance_id: u64,
+) -> Option<SessionLogWriter> {
+    let result = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| SessionLogWriter::open(&dir, &conn.config, id));
+    match result {
+        Ok(Some(log)) => {
+            ssh_log::info(
+                SshLogRecord::new("ssh.session_log", "opened", "Opened plain-text session log")
+                    .session_id(id.to_string())
+                    .server_id(conn.config.id.clone())
+                    .instance_id(instance_id)
+                    .field("path", ssh_log::mask_path(&log.path().to_string_lossy())),
+            );
+            Some(log)
+        }
+        Ok(None) => None,
+        Err(err) => {
+            ssh_log::warn(
+                SshLogRecord::new("ssh.session_log", "open_failed", "Failed to open session log")
+                    .session_id(id.to_string())
+                    .server_id(conn.config.id.clone())
+                    .instance_id(instance_id)
+                    .field("error", err),
+            );
+            None
+        }
+    }
+}
+
+// 🟢 加载该服务器启用的输出触发器，失败只记录日志
+async fn load_shell_triggers(app: &AppHandle, conn: &SshConnection, id: &str) {
+    let db = app.state::<AppState>().db.clone();
+    match load_enabled_triggers(&db, &conn.config.id).await {
+        Ok(rules) => conn.set_triggers(rules),
+        Err(err) => {
+            ssh_log::warn(
+                SshLogRecord::new("ssh.trigger", "load_failed", "Failed to load output triggers")
+                    .session_id(id.to_string())
+                    .server_id(conn.config.id.clone())
+                    .field("error", err),
+            );
+        }
+    }
+}
+
+// 🟢 登录 shell 的启动输入；持久会话只在本次新建时输入，重新附加时远端 shell 早已在运行
+async fn load_startup_input(app: &AppHandle, conn: &SshConnection, id: &str) -> Option<String> {
+    let options = conn.config.shell_options.as_ref()?;
+    if conn
+        .persistent_shell()
+        .is_some_and(|persistent| !persistent.created)
+    {
+        return None;
+    }
+    let snippet = match options.startup_snippet_id.as_deref() {
+        Some(snippet_id) if !snippet_id.is_empty() => {
+            let db = app.state::<AppState>().db.clone();
+            match internal_get_snippet_code(&db, snippet_id).await {
+                Ok(code) => Some(code),
+                Err(err) => {
+                    ssh_log::warn(
+                        SshLogRecord::new(
+                            "ssh.shell",
+                            "startup_snippet_failed",
+                            "Failed to load startup snippet",
+                        )
+                        .session_id(id.to_string())
+                        .server_id(conn.config.id.clone())
+                        .field("error", err),
+                    );
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    build_startup_input(options, snippet.as_deref())
+}
+
+fn send_startup_input(conn: SshConnection, id: &str, input: String) {
+    let session_id = id.to_string();
+    tokio::spawn(async move {
+        let server_id = conn.config.id.clone();
+        let result = queue_shell_write(conn.shell_write_tx.clone(), input).await;
+        let record = SshLogRecord::new("ssh.shell", "startup_input_sent", "Sent startup commands")
+            .session_id(session_id)
+            .server_id(server_id);
+        match result {
+            Ok(()) => ssh_log::debug(record),
+            Err(err) => ssh_log::warn(record.field("error", err)),
+        }
+    });
+}
+
 pub fn spawn_shell_reader_thread(
     app: AppHandle,
     mut shell_channel: russh::Channel<russh::client::Msg>,
@@ -124,6 +355,24 @@ pub fn spawn_shell_reader_thread(
 
         let mut total_bytes_read = 0u64;
         let mut last_error: Option<String> = None;
+        let initial_conn = get_ssh_session_if_instance(&sessions, &id, instance_id);
+        let encoding = initial_conn
+            .as_ref()
+            .map(|conn| resolve_encoding(conn.config.encoding.as_deref()))
+            .unwrap_or(encoding_rs::UTF_8);
+        let mut session_log = initial_conn
+            .as_ref()
+            .and_then(|conn| open_session_log(&app, conn, &id, instance_id));
+        let mut startup = ShellStartup::new(None);
+        if let Some(conn) = initial_conn.as_ref() {
+            load_shell_triggers(&app, conn, &id).await;
+            startup = ShellStartup::new(load_startup_input(&app, conn, &id).await);
+        }
+        drop(initial_conn);
+        let mut shell_integration = ShellIntegrationParser::default();
+        let mut transfer_detector = TransferDetector::default();
+        let mut stdout_decoder = TerminalDecoder::new(encoding);
+        let mut stderr_decoder = TerminalDecoder::new(encoding);
 
         let exit_reason = loop {
             tokio::select! {
@@ -131,37 +380,32 @@ pub fn spawn_shell_reader_thread(
                     match channel_msg {
                         Some(russh::ChannelMsg::Data { data }) => {
                             total_bytes_read = total_bytes_read.saturating_add(data.len() as u64);
-                            let data_str = String::from_utf8_lossy(&data).to_string();
-                            
-                            // Save to terminal output history
-                            if let Some(conn) = get_ssh_session_if_instance(&sessions, &id, instance_id) {
-                                if let Ok(mut history) = conn.output_history.lock() {
-                                    history.push_str(&data_str);
-                                    if history.len() > 50000 {
-                                        let overflow = history.len() - 50000;
-                                        *history = history[overflow..].to_string();
-                                    }
+                            startup.on_output();
+                            let conn = get_ssh_session_if_instance(&sessions, &id, instance_id);
+                            // 🟢 rz/sz、trz/tsz 握手：握手前的输出照常显示，随后由传输接管通道直到结束
+                            let detected = conn.as_ref().and_then(|_| transfer_detector.push(&data));
+                            if let (Some(conn), Some((detected, handshake))) = (conn.as_ref(), detected) {
+                                let offset = detected.offset;
+                                publish_shell_output(&app, Some(conn), &id, &mut stdout_decoder, &mut session_log, &mut shell_integration, &data[..offset]);
+                                let outcome = run_terminal_transfer(&app, &mut shell_channel, conn, &id, detected, &handshake).await;
+                                publish_shell_output(&app, Some(conn), &id, &mut stdout_decoder, &mut session_log, &mut shell_integration, &outcome.remaining);
+                                if outcome.channel_closed {
+                                    break "channel_eof";
+                                }
+                            } else {
+                                publish_shell_output(&app, conn.as_ref(), &id, &mut stdout_decoder, &mut session_log, &mut shell_integration, &data);
+                            }
+                            // 🟢 shell 集成上报了提示符 (OSC 133;A) 时立即发送启动输入，不再等待输出停顿
+                            if shell_integration.take_prompt() && startup.is_pending() {
+                                if let (Some(input), Some(conn)) = (startup.take(), conn) {
+                                    send_startup_input(conn, &id, input);
                                 }
                             }
-                            
-                            let _ = app.emit(&format!("term-data-{}", id), data_str);
                         }
                         Some(russh::ChannelMsg::ExtendedData { data, .. }) => {
                             total_bytes_read = total_bytes_read.saturating_add(data.len() as u64);
-                            let data_str = String::from_utf8_lossy(&data).to_string();
-                            
-                            // Save to terminal output history
-                            if let Some(conn) = get_ssh_session_if_instance(&sessions, &id, instance_id) {
-                                if let Ok(mut history) = conn.output_history.lock() {
-                                    history.push_str(&data_str);
-                                    if history.len() > 50000 {
-                                        let overflow = history.len() - 50000;
-                                        *history = history[overflow..].to_string();
-                                    }
-                                }
-                            }
-                            
-                            let _ = app.emit(&format!("term-data-{}", id), data_str);
+                            let conn = get_ssh_session_if_instance(&sessions, &id, instance_id);
+                            publish_shell_output(&app, conn.as_ref(), &id, &mut stderr_decoder, &mut session_log, &mut shell_integration, &data);
                         }
                         Some(russh::ChannelMsg::Eof) | Some(russh::ChannelMsg::Close) | None => {
                             break "channel_eof";
@@ -169,6 +413,11 @@ pub fn spawn_shell_reader_thread(
                         Some(_) => {}
                     }
                 }
+                _ = tokio::time::sleep_until(startup.deadline()), if startup.is_pending() => {
+                    if let (Some(input), Some(conn)) = (startup.take(), get_ssh_session_if_instance(&sessions, &id, instance_id)) {
+                        send_startup_input(conn, &id, input);
+                    }
+                }
                 resize_request = resize_rx.recv() => {
                     let Some(request) = resize_request else {
                         continue;
@@ -177,6 +426,7 @@ pub fn spawn_shell_reader_thread(
                         Some(conn) if conn.shell_is_active() => shell_channel
                             .window_change(request.cols, request.rows, 0, 0)
                             .await
+                            .map(|_| conn.record_resize(request.cols, request.rows))
                             .map_err(|e| format!("PTY resize failed: {}", e)),
                         Some(_) => Err("SSH shell not active".to_string()),
                         None => Err("SSH connection not active".to_string()),
@@ -203,6 +453,10 @@ pub fn spawn_shell_reader_thread(
             }
         };
 
+        if let Some(log) = session_log.take() {
+            log.close(exit_reason);
+        }
+
         let existing = get_ssh_session_if_instance(&sessions, &id, instance_id);
         if let Some(conn) = existing {
             let shell_marked_closed = conn.mark_shell_closed();
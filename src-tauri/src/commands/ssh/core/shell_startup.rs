@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::commands::ssh::utils::shell_quote;
+use crate::models::ShellOptions;
+
+// 输出停顿这么久视为第一个提示符已经出现
+const STARTUP_PROMPT_IDLE: Duration = Duration::from_millis(400);
+// 一直没有停顿 (如持续刷屏的 motd) 时最多等待这么久
+const STARTUP_PROMPT_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_PTY_DIMENSION: u32 = 1000;
+const MAX_TERM_TYPE_LEN: usize = 64;
+
+fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+// 🟢 保存服务器前校验 shell 启动选项
+pub fn validate_shell_options(options: &ShellOptions) -> Result<(), String> {
+    let term = options.term_type.trim();
+    if term.is_empty()
+        || term.len() > MAX_TERM_TYPE_LEN
+        || !term.chars().all(|c| c.is_ascii_graphic())
+    {
+        return Err(format!("Invalid TERM type: {}", options.term_type));
+    }
+    for (label, value) in [("columns", options.cols), ("rows", options.rows)] {
+        if value == 0 || value > MAX_PTY_DIMENSION {
+            return Err(format!(
+                "Terminal {} must be between 1 and {}",
+                label, MAX_PTY_DIMENSION
+            ));
+        }
+    }
+    for var in &options.env {
+        if !is_valid_env_name(&var.name) {
+            return Err(format!("Invalid environment variable name: {}", var.name));
+        }
+        if var.value.contains('\0') {
+            return Err(format!(
+                "Environment variable {} contains a NUL byte",
+                var.name
+            ));
+        }
+    }
+    if let Some(dir) = options.working_dir.as_deref() {
+        if dir.contains(['\0', '\r', '\n']) {
+            return Err("Working directory must be a single line".to_string());
+        }
+    }
+    if let Some(command) = options.command.as_deref() {
+        if command.contains('\0') {
+            return Err("Startup command contains a NUL byte".to_string());
+        }
+    }
+    Ok(())
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+// 🟢 单引号会阻止 ~ 展开：~ 与 ~/ 开头的目录改写为 "$HOME" 加引号路径
+fn quote_working_dir(dir: &str) -> String {
+    if dir == "~" {
+        return "\"$HOME\"".to_string();
+    }
+    match dir.strip_prefix("~/") {
+        Some("") => "\"$HOME\"/".to_string(),
+        Some(rest) => format!("\"$HOME\"/{}", shell_quote(rest)),
+        None => shell_quote(dir),
+    }
+}
+
+// 🟢 固定命令代替登录 shell：设置了工作目录时先 cd 再执行
+pub fn shell_exec_command(options: &ShellOptions) -> Option<String> {
+    let command = non_empty(options.command.as_deref())?;
+    Some(match non_empty(options.working_dir.as_deref()) {
+        Some(dir) => format!("cd -- {} && {}", quote_working_dir(dir), command),
+        None => command.to_string(),
+    })
+}
+
+// 多行文本按终端输入处理：每行以回车结束
+pub fn shell_input_lines(text: &str) -> String {
+    text.trim_end().replace("\r\n", "\n").replace('\n', "\r")
+}
+
+// 是否配置了需要在提示符处输入的内容 (片段在读取任务中才加载，这里只看是否设置)
+pub fn has_startup_input(options: &ShellOptions) -> bool {
+    non_empty(options.command.as_deref()).is_none()
+        && [
+            &options.working_dir,
+            &options.startup_commands,
+            &options.startup_snippet_id,
+        ]
+        .iter()
+        .any(|value| non_empty(value.as_deref()).is_some())
+}
+
+// 🟢 登录 shell 出现提示符后输入的内容：cd 工作目录、启动命令、启动片段
+pub fn build_startup_input(options: &ShellOptions, snippet: Option<&str>) -> Option<String> {
+    if non_empty(options.command.as_deref()).is_some() {
+        return None;
+    }
+    let mut parts = Vec::new();
+    if let Some(dir) = non_empty(options.working_dir.as_deref()) {
+        // 前导空格避免进入远端历史
+        parts.push(format!(" cd -- {}", quote_working_dir(dir)));
+    }
+    if let Some(commands) = non_empty(options.startup_commands.as_deref()) {
+        parts.push(shell_input_lines(commands));
+    }
+    if let Some(snippet) = non_empty(snippet) {
+        parts.push(shell_input_lines(snippet));
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    let mut input = parts.join("\r");
+    input.push('\r');
+    Some(input)
+}
+
+// 🟢 等待第一个提示符：有 shell 集成时以 OSC 133;A 为准 (由读取任务处理)，
+// 否则收到输出后停顿 STARTUP_PROMPT_IDLE 即发送启动输入
+pub struct ShellStartup {
+    input: Option<String>,
+    started: Instant,
+    last_output: Option<Instant>,
+}
+
+impl ShellStartup {
+    pub fn new(input: Option<String>) -> Self {
+        Self {
+            input,
+            started: Instant::now(),
+            last_output: None,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn on_output(&mut self) {
+        if self.input.is_some() {
+            self.last_output = Some(Instant::now());
+        }
+    }
+
+    // 无待发送内容时返回一个很远的时间点，供 select! 分支占位
+    pub fn deadline(&self) -> Instant {
+        let timeout = self.started + STARTUP_PROMPT_TIMEOUT;
+        match (&self.input, self.last_output) {
+            (None, _) => self.started + Duration::from_secs(24 * 60 * 60),
+            (Some(_), Some(last_output)) => (last_output + STARTUP_PROMPT_IDLE).min(timeout),
+            (Some(_), None) => timeout,
+        }
+    }
+
+    pub fn take(&mut self) -> Option<String> {
+        self.input.take()
+    }
+}
@@ -0,0 +1,116 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+// RFC 1928 应答码
+pub const SOCKS5_REPLY_SUCCEEDED: u8 = 0x00;
+pub const SOCKS5_REPLY_GENERAL_FAILURE: u8 = 0x01;
+pub const SOCKS5_REPLY_HOST_UNREACHABLE: u8 = 0x04;
+const SOCKS5_REPLY_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const SOCKS5_REPLY_ADDRESS_TYPE_NOT_SUPPORTED: u8 = 0x08;
+
+// 客户端请求连接的目标
+pub struct Socks5Target {
+    pub host: String,
+    pub port: u16,
+}
+
+pub async fn send_socks5_reply<S>(stream: &mut S, code: u8) -> std::io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    // 绑定地址对 CONNECT 客户端无意义，统一回 0.0.0.0:0
+    stream
+        .write_all(&[0x05, code, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+        .await?;
+    stream.flush().await
+}
+
+// 🟢 SOCKS5 服务端握手 (无认证，仅支持 CONNECT)，返回目标地址
+pub async fn accept_socks5_request<S>(stream: &mut S) -> Result<Socks5Target, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut greeting = [0u8; 2];
+    stream
+        .read_exact(&mut greeting)
+        .await
+        .map_err(|e| format!("SOCKS5 handshake failed: {}", e))?;
+    if greeting[0] != 0x05 {
+        return Err(format!("Unsupported SOCKS version {}", greeting[0]));
+    }
+
+    let mut methods = vec![0u8; greeting[1] as usize];
+    stream
+        .read_exact(&mut methods)
+        .await
+        .map_err(|e| format!("SOCKS5 handshake failed: {}", e))?;
+    if !methods.contains(&0x00) {
+        let _ = stream.write_all(&[0x05, 0xff]).await;
+        return Err("SOCKS5 client offered no acceptable auth method".to_string());
+    }
+    stream
+        .write_all(&[0x05, 0x00])
+        .await
+        .map_err(|e| format!("SOCKS5 handshake failed: {}", e))?;
+
+    let mut request = [0u8; 4];
+    stream
+        .read_exact(&mut request)
+        .await
+        .map_err(|e| format!("SOCKS5 request failed: {}", e))?;
+    if request[0] != 0x05 {
+        return Err("Invalid SOCKS5 request".to_string());
+    }
+    if request[1] != 0x01 {
+        let _ = send_socks5_reply(stream, SOCKS5_REPLY_COMMAND_NOT_SUPPORTED).await;
+        return Err(format!("Unsupported SOCKS5 command {}", request[1]));
+    }
+
+    let host = match request[3] {
+        0x01 => {
+            let mut addr = [0u8; 4];
+            stream
+                .read_exact(&mut addr)
+                .await
+                .map_err(|e| format!("SOCKS5 request failed: {}", e))?;
+            Ipv4Addr::from(addr).to_string()
+        }
+        0x03 => {
+            let mut len = [0u8; 1];
+            stream
+                .read_exact(&mut len)
+                .await
+                .map_err(|e| format!("SOCKS5 request failed: {}", e))?;
+            let mut addr = vec![0u8; len[0] as usize];
+            stream
+                .read_exact(&mut addr)
+                .await
+                .map_err(|e| format!("SOCKS5 request failed: {}", e))?;
+            String::from_utf8(addr).map_err(|_| "SOCKS5 target host is not UTF-8".to_string())?
+        }
+        0x04 => {
+            let mut addr = [0u8; 16];
+            stream
+                .read_exact(&mut addr)
+                .await
+                .map_err(|e| format!("SOCKS5 request failed: {}", e))?;
+            Ipv6Addr::from(addr).to_string()
+        }
+        atyp => {
+            let _ = send_socks5_reply(stream, SOCKS5_REPLY_ADDRESS_TYPE_NOT_SUPPORTED).await;
+            return Err(format!("Unsupported SOCKS5 address type {}", atyp));
+        }
+    };
+
+    let mut port = [0u8; 2];
+    stream
+        .read_exact(&mut port)
+        .await
+        .map_err(|e| format!("SOCKS5 request failed: {}", e))?;
+
+    Ok(Socks5Target {
+        host,
+        port: u16::from_be_bytes(port),
+    })
+}
@@ -0,0 +1,165 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+use encoding_rs::{Decoder, Encoding, EncoderResult, UTF_8};
+
+// 🟢 流式 UTF-8 解码：跨数据包被截断的多字节字符留到下一块再解码，避免产生 U+FFFD
+#[derive(Default)]
+pub struct Utf8ChunkDecoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8ChunkDecoder {
+    pub fn decode(&mut self, chunk: &[u8]) -> String {
+        let mut buffer = std::mem::take(&mut self.pending);
+        buffer.extend_from_slice(chunk);
+
+        let mut output = String::with_capacity(buffer.len());
+        let mut input = buffer.as_slice();
+        loop {
+            match std::str::from_utf8(input) {
+                Ok(valid) => {
+                    output.push_str(valid);
+                    break;
+                }
+                Err(err) => {
+                    let (valid, rest) = input.split_at(err.valid_up_to());
+                    output.push_str(&String::from_utf8_lossy(valid));
+                    match err.error_len() {
+                        // 真正的非法字节：替换后继续
+                        Some(len) => {
+                            output.push(char::REPLACEMENT_CHARACTER);
+                            input = &rest[len..];
+                        }
+                        // 结尾是不完整的序列：等待后续数据
+                        None => {
+                            self.pending = rest.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+// 🟢 按服务器编码解码终端输出；非 UTF-8 编码 (GBK/Big5/Shift_JIS/Latin-1) 交给 encoding_rs 的流式解码器
+pub enum TerminalDecoder {
+    Utf8(Utf8ChunkDecoder),
+    Legacy(Decoder),
+}
+
+impl TerminalDecoder {
+    pub fn new(encoding: &'static Encoding) -> Self {
+        if encoding == UTF_8 {
+            Self::Utf8(Utf8ChunkDecoder::default())
+        } else {
+            Self::Legacy(encoding.new_decoder_without_bom_handling())
+        }
+    }
+
+    pub fn decode(&mut self, chunk: &[u8]) -> String {
+        match self {
+            Self::Utf8(decoder) => decoder.decode(chunk),
+            Self::Legacy(decoder) => {
+                let capacity = decoder
+                    .max_utf8_buffer_length(chunk.len())
+                    .unwrap_or(chunk.len() * 3);
+                let mut output = String::with_capacity(capacity);
+                // last = false：被截断的多字节序列保留在解码器内部状态中
+                let _ = decoder.decode_to_string(chunk, &mut output, false);
+                output
+            }
+        }
+    }
+}
+
+// 🟢 将用户输入编码为服务器字符集，无法表示的字符替换为 '?'
+pub fn encode_terminal_input<'a>(encoding: &'static Encoding, text: &'a str) -> Cow<'a, [u8]> {
+    if encoding == UTF_8 {
+        return Cow::Borrowed(text.as_bytes());
+    }
+
+    let mut encoder = encoding.new_encoder();
+    let mut output = Vec::with_capacity(
+        encoder
+            .max_buffer_length_from_utf8_without_replacement(text.len())
+            .unwrap_or(text.len() * 2),
+    );
+    let mut input = text;
+    loop {
+        let (result, read) =
+            encoder.encode_from_utf8_to_vec_without_replacement(input, &mut output, true);
+        input = &input[read..];
+        match result {
+            EncoderResult::InputEmpty => break,
+            EncoderResult::Unmappable(_) => output.push(b'?'),
+            EncoderResult::OutputFull => output.reserve(input.len() * 2 + 16),
+        }
+    }
+    Cow::Owned(output)
+}
+
+// 🟢 按字节数限制的回滚缓冲区，保存原始字节，超限时丢弃最旧的数据
+pub struct ScrollbackBuffer {
+    data: VecDeque<u8>,
+    limit: usize,
+}
+
+impl ScrollbackBuffer {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            data: VecDeque::with_capacity(limit.min(64 * 1024)),
+            limit,
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        if bytes.len() >= self.limit {
+            self.data.clear();
+            self.data.extend(&bytes[bytes.len() - self.limit..]);
+            return;
+        }
+        let overflow = (self.data.len() + bytes.len()).saturating_sub(self.limit);
+        self.data.drain(..overflow);
+        self.data.extend(bytes);
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (front, back) = self.data.as_slices();
+        let mut bytes = Vec::with_capacity(self.data.len());
+        bytes.extend_from_slice(front);
+        bytes.extend_from_slice(back);
+        bytes
+    }
+
+    // 丢弃最旧数据时可能截断多字节字符，跳过开头的续字节再解码
+    pub fn to_string_lossy(&self) -> String {
+        let bytes = self.to_bytes();
+        let start = bytes
+            .iter()
+            .take(3)
+            .take_while(|byte| (**byte & 0xC0) == 0x80)
+            .count();
+        String::from_utf8_lossy(&bytes[start..]).into_owned()
+    }
+
+    // 🟢 按服务器编码解码整个回滚缓冲区
+    pub fn decode(&self, encoding: &'static Encoding) -> String {
+        if encoding == UTF_8 {
+            return self.to_string_lossy();
+        }
+        let bytes = self.to_bytes();
+        let (text, _) = encoding.decode_without_bom_handling(&bytes);
+        text.into_owned()
+    }
+}
@@ -0,0 +1,588 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+use crate::commands::fs::commands::emit_sftp_transfer_progress;
+use crate::commands::ssh::state::{
+    PendingTerminalTransfer, SshConnection, TerminalTransferEndEvent, TerminalTransferFileEvent,
+    TerminalTransferRegistry, TerminalTransferRequestEvent,
+};
+use crate::utils::ssh_log::{self, SshLogRecord};
+
+use super::{trzsz, zmodem};
+
+// sz 发出的 ZRQINIT 十六进制头：远端发送，本地下载
+const ZMODEM_DOWNLOAD_MARKER: &[u8] = b"**\x18B00";
+// rz 发出的 ZRINIT 十六进制头：远端接收，本地上传
+const ZMODEM_UPLOAD_MARKER: &[u8] = b"**\x18B01";
+// trz/tsz 输出 "::TRZSZ:TRANSFER:<R|S|D>:<version>:<id>"
+const TRZSZ_MARKER: &[u8] = b"::TRZSZ:TRANSFER:";
+// 最长的识别序列是 trzsz 标记加方向字节，跨块时最多有这么多字节落在上一块
+const TRANSFER_MARKER_CARRY: usize = TRZSZ_MARKER.len();
+const TERMINAL_TRANSFER_PROMPT_TIMEOUT_SECS: u64 = 120;
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(200);
+// 读等待切成小段，以便及时响应取消
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TerminalTransferProtocol {
+    Zmodem,
+    Trzsz,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TerminalTransferDirection {
+    // 远端发送 (sz / tsz)，保存到本地目录
+    Download,
+    // 远端接收 (rz / trz)，上传本地文件
+    Upload,
+}
+
+pub struct DetectedTransfer {
+    pub protocol: TerminalTransferProtocol,
+    pub direction: TerminalTransferDirection,
+    // 握手标记在数据块中的起始位置，之前的内容照常输出到终端 (标记从上一块开始时为 0)
+    pub offset: usize,
+    // trz -d 上传目录，需要目录传输支持
+    pub directory: bool,
+}
+
+pub struct UploadFile {
+    pub path: PathBuf,
+    pub name: String,
+    pub size: u64,
+    // Unix 秒
+    pub modified: u64,
+}
+
+// 前端选择的本地路径：下载保存目录或待上传文件
+pub enum TransferSelection {
+    Download(PathBuf),
+    Upload(Vec<UploadFile>),
+}
+
+pub struct TransferOutcome {
+    // 传输结束后剩余的数据，恢复为普通终端输出
+    pub remaining: Vec<u8>,
+    pub channel_closed: bool,
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+// 🟢 在 shell 输出中识别 ZMODEM / trzsz 握手，多个标记同时出现时取最靠前的
+fn detect_terminal_transfer(data: &[u8]) -> Option<DetectedTransfer> {
+    let zmodem = [
+        (ZMODEM_DOWNLOAD_MARKER, TerminalTransferDirection::Download),
+        (ZMODEM_UPLOAD_MARKER, TerminalTransferDirection::Upload),
+    ]
+    .into_iter()
+    .filter_map(|(marker, direction)| {
+        find_bytes(data, marker).map(|offset| DetectedTransfer {
+            protocol: TerminalTransferProtocol::Zmodem,
+            direction,
+            offset,
+            directory: false,
+        })
+    });
+    let trzsz = find_bytes(data, TRZSZ_MARKER).and_then(|offset| {
+        let (direction, directory) = match data.get(offset + TRZSZ_MARKER.len())? {
+            b'S' => (TerminalTransferDirection::Download, false),
+            b'R' => (TerminalTransferDirection::Upload, false),
+            b'D' => (TerminalTransferDirection::Upload, true),
+            _ => return None,
+        };
+        Some(DetectedTransfer {
+            protocol: TerminalTransferProtocol::Trzsz,
+            direction,
+            offset,
+            directory,
+        })
+    });
+    zmodem.chain(trzsz).min_by_key(|detected| detected.offset)
+}
+
+// 🟢 握手标记可能被拆在两个数据块中：保留上一块末尾的字节，与新数据拼接后再扫描
+#[derive(Default)]
+pub struct TransferDetector {
+    tail: Vec<u8>,
+}
+
+impl TransferDetector {
+    // 命中时同时返回从标记开始的握手数据 (含上一块中的部分)，交给传输处理
+    pub fn push(&mut self, data: &[u8]) -> Option<(DetectedTransfer, Vec<u8>)> {
+        let carried = self.tail.len();
+        let mut buffer = std::mem::take(&mut self.tail);
+        buffer.extend_from_slice(data);
+        match detect_terminal_transfer(&buffer) {
+            Some(mut detected) => {
+                let handshake = buffer.split_off(detected.offset);
+                detected.offset = detected.offset.saturating_sub(carried);
+                Some((detected, handshake))
+            }
+            None => {
+                let keep = buffer.len().min(TRANSFER_MARKER_CARRY);
+                self.tail = buffer.split_off(buffer.len() - keep);
+                None
+            }
+        }
+    }
+}
+
+fn unix_secs(time: std::io::Result<std::time::SystemTime>) -> u64 {
+    time.ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// 🟢 校验前端返回的路径：下载需要一个已存在的目录，上传需要一个或多个普通文件
+pub fn prepare_transfer_selection(
+    direction: TerminalTransferDirection,
+    paths: &[String],
+) -> Result<TransferSelection, String> {
+    match direction {
+        TerminalTransferDirection::Download => {
+            let [dir] = paths else {
+                return Err("Choose exactly one directory to save files into".to_string());
+            };
+            let dir = PathBuf::from(dir);
+            if !dir.is_dir() {
+                return Err(format!("Not a directory: {}", dir.display()));
+            }
+            Ok(TransferSelection::Download(dir))
+        }
+        TerminalTransferDirection::Upload => {
+            if paths.is_empty() {
+                return Err("Choose at least one file to upload".to_string());
+            }
+            paths
+                .iter()
+                .map(|path| {
+                    let path = PathBuf::from(path);
+                    let metadata = std::fs::metadata(&path)
+                        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                    if !metadata.is_file() {
+                        return Err(format!("Not a regular file: {}", path.display()));
+                    }
+                    let name = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .ok_or_else(|| format!("Invalid file name: {}", path.display()))?;
+                    Ok(UploadFile {
+                        name,
+                        size: metadata.len(),
+                        modified: unix_secs(metadata.modified()),
+                        path,
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()
+                .map(TransferSelection::Upload)
+        }
+    }
+}
+
+// 远端给出的文件名只取最后一段，已存在同名文件时追加 " (n)"
+pub fn unique_download_path(dir: &Path, remote_name: &str) -> Result<PathBuf, String> {
+    let name: String = remote_name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|ch| !ch.is_control())
+        .collect();
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(format!("Invalid remote file name: {:?}", remote_name));
+    }
+
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return Ok(candidate);
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (name, None),
+    };
+    (1..1000)
+        .map(|index| match extension {
+            Some(extension) => dir.join(format!("{} ({}).{}", stem, index, extension)),
+            None => dir.join(format!("{} ({})", stem, index)),
+        })
+        .find(|candidate| !candidate.exists())
+        .ok_or_else(|| format!("Too many files named {} in {}", name, dir.display()))
+}
+
+// 🟢 传输期间独占 shell 通道：按字节读取远端数据，直接写入通道绕过终端输入队列
+pub struct TransferChannel<'a> {
+    channel: &'a mut russh::Channel<russh::client::Msg>,
+    buffer: VecDeque<u8>,
+    cancel: Arc<AtomicBool>,
+    closed: bool,
+}
+
+impl<'a> TransferChannel<'a> {
+    fn new(
+        channel: &'a mut russh::Channel<russh::client::Msg>,
+        initial: &[u8],
+        cancel: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            channel,
+            buffer: initial.iter().copied().collect(),
+            cancel,
+            closed: false,
+        }
+    }
+
+    // 返回 false 表示等待超时
+    async fn fill(&mut self, timeout: Duration) -> Result<bool, String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.cancel.load(Ordering::Relaxed) {
+                return Err("TRANSFER_CANCELLED: File transfer cancelled by user".to_string());
+            }
+            if self.closed {
+                return Err("Shell channel closed during file transfer".to_string());
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(false);
+            }
+            let wait = (deadline - now).min(CANCEL_POLL_INTERVAL);
+            match tokio::time::timeout(wait, self.channel.wait()).await {
+                Err(_) => continue,
+                Ok(Some(russh::ChannelMsg::Data { data })) if !data.is_empty() => {
+                    self.buffer.extend(data.iter().copied());
+                    return Ok(true);
+                }
+                Ok(Some(russh::ChannelMsg::Eof))
+                | Ok(Some(russh::ChannelMsg::Close))
+                | Ok(None) => self.closed = true,
+                // 传输期间的 stderr 输出与其它通道消息直接丢弃
+                Ok(Some(_)) => {}
+            }
+        }
+    }
+
+    pub async fn try_read_byte(&mut self, timeout: Duration) -> Result<Option<u8>, String> {
+        if self.buffer.is_empty() && !self.fill(timeout).await? {
+            return Ok(None);
+        }
+        Ok(self.buffer.pop_front())
+    }
+
+    pub async fn read_byte(&mut self, timeout: Duration) -> Result<u8, String> {
+        self.try_read_byte(timeout).await?.ok_or_else(|| {
+            format!(
+                "TRANSFER_TIMEOUT: No data from remote for {}s",
+                timeout.as_secs()
+            )
+        })
+    }
+
+    pub fn unread(&mut self, byte: u8) {
+        self.buffer.push_front(byte);
+    }
+
+    // 读取一行 (不含 '\n')
+    pub async fn read_line(&mut self, timeout: Duration, limit: usize) -> Result<Vec<u8>, String> {
+        let mut line = Vec::new();
+        loop {
+            let byte = self.read_byte(timeout).await?;
+            if byte == b'\n' {
+                return Ok(line);
+            }
+            if line.len() >= limit {
+                return Err(format!("Transfer line exceeds {} bytes", limit));
+            }
+            line.push(byte);
+        }
+    }
+
+    pub async fn write(&mut self, data: &[u8]) -> Result<(), String> {
+        if self.closed {
+            return Err("Shell channel closed during file transfer".to_string());
+        }
+        self.channel
+            .data(data)
+            .await
+            .map_err(|e| format!("Failed to write to shell channel: {}", e))
+    }
+
+    fn into_remaining(self) -> (Vec<u8>, bool) {
+        (self.buffer.into_iter().collect(), self.closed)
+    }
+}
+
+// 🟢 按文件推送进度，沿用 SFTP 传输的 sftp_transfer_progress 事件，按间隔节流
+pub struct TransferReporter<'a> {
+    app: &'a AppHandle,
+    session_id: &'a str,
+    transfer_id: &'a str,
+    file_index: usize,
+    total: u64,
+    last_emit: Instant,
+    last_transferred: u64,
+    completed: Vec<String>,
+}
+
+impl<'a> TransferReporter<'a> {
+    fn new(app: &'a AppHandle, session_id: &'a str, transfer_id: &'a str) -> Self {
+        Self {
+            app,
+            session_id,
+            transfer_id,
+            file_index: 0,
+            total: 0,
+            last_emit: Instant::now(),
+            last_transferred: 0,
+            completed: Vec::new(),
+        }
+    }
+
+    pub fn start_file(&mut self, name: &str, local_path: &Path, size: u64) {
+        self.file_index += 1;
+        self.total = size;
+        self.last_emit = Instant::now();
+        self.last_transferred = 0;
+        let _ = self.app.emit(
+            &format!("term-transfer-file-{}", self.session_id),
+            TerminalTransferFileEvent {
+                transfer_id: self.transfer_id.to_string(),
+                index: self.file_index,
+                name: name.to_string(),
+                local_path: local_path.to_string_lossy().into_owned(),
+                size,
+            },
+        );
+        emit_sftp_transfer_progress(self.app, self.transfer_id, 0, size, 0);
+    }
+
+    fn emit_progress(&mut self, transferred: u64) {
+        let elapsed = self.last_emit.elapsed().as_secs_f64();
+        let speed = if elapsed > 0.0 {
+            (transferred.saturating_sub(self.last_transferred) as f64 / elapsed) as u64
+        } else {
+            0
+        };
+        self.last_emit = Instant::now();
+        self.last_transferred = transferred;
+        emit_sftp_transfer_progress(self.app, self.transfer_id, transferred, self.total, speed);
+    }
+
+    pub fn progress(&mut self, transferred: u64) {
+        if self.last_emit.elapsed() >= PROGRESS_EMIT_INTERVAL {
+            self.emit_progress(transferred);
+        }
+    }
+
+    pub fn finish_file(&mut self, local_path: &Path) {
+        self.emit_progress(self.total);
+        self.completed
+            .push(local_path.to_string_lossy().into_owned());
+    }
+}
+
+// 🟢 等待前端选择本地路径 (term-transfer-{id} 事件 + respond_terminal_transfer)
+async fn request_selection(
+    app: &AppHandle,
+    id: &str,
+    transfer_id: &str,
+    detected: &DetectedTransfer,
+) -> Result<TransferSelection, String> {
+    if detected.directory {
+        return Err(
+            "TRANSFER_UNSUPPORTED: trzsz directory transfer (trz -d) is not supported".to_string(),
+        );
+    }
+
+    let registry = app.state::<TerminalTransferRegistry>();
+    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+    {
+        let mut pending = match registry.pending.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        pending.insert(
+            transfer_id.to_string(),
+            PendingTerminalTransfer {
+                direction: detected.direction,
+                response_tx,
+            },
+        );
+    }
+    let _ = app.emit(
+        &format!("term-transfer-{}", id),
+        TerminalTransferRequestEvent {
+            transfer_id: transfer_id.to_string(),
+            protocol: detected.protocol,
+            direction: detected.direction,
+        },
+    );
+
+    let outcome = tokio::time::timeout(
+        Duration::from_secs(TERMINAL_TRANSFER_PROMPT_TIMEOUT_SECS),
+        response_rx,
+    )
+    .await;
+    {
+        let mut pending = match registry.pending.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        pending.remove(transfer_id);
+    }
+
+    match outcome {
+        Ok(Ok(Some(selection))) => Ok(selection),
+        Ok(Ok(None)) => Err("TRANSFER_CANCELLED: File transfer cancelled by user".to_string()),
+        Ok(Err(_)) => Err("TRANSFER_CANCELLED: File transfer prompt was dropped".to_string()),
+        Err(_) => Err(format!(
+            "TRANSFER_TIMEOUT: No local path chosen within {}s",
+            TERMINAL_TRANSFER_PROMPT_TIMEOUT_SECS
+        )),
+    }
+}
+
+async fn execute_transfer(
+    io: &mut TransferChannel<'_>,
+    protocol: TerminalTransferProtocol,
+    selection: TransferSelection,
+    reporter: &mut TransferReporter<'_>,
+) -> Result<(), String> {
+    match (protocol, selection) {
+        (TerminalTransferProtocol::Zmodem, TransferSelection::Download(dir)) => {
+            zmodem::receive_files(io, &dir, reporter).await
+        }
+        (TerminalTransferProtocol::Zmodem, TransferSelection::Upload(files)) => {
+            zmodem::send_files(io, &files, reporter).await
+        }
+        (TerminalTransferProtocol::Trzsz, TransferSelection::Download(dir)) => {
+            trzsz::receive_files(io, &dir, reporter).await
+        }
+        (TerminalTransferProtocol::Trzsz, TransferSelection::Upload(files)) => {
+            trzsz::send_files(io, &files, reporter).await
+        }
+    }
+}
+
+// 🟢 接管 shell 通道完成一次 ZMODEM / trzsz 传输；期间终端输入被拒绝，结束后恢复普通输出
+pub async fn run_terminal_transfer(
+    app: &AppHandle,
+    channel: &mut russh::Channel<russh::client::Msg>,
+    conn: &SshConnection,
+    id: &str,
+    detected: DetectedTransfer,
+    data: &[u8],
+) -> TransferOutcome {
+    let transfer_id = Uuid::new_v4().to_string();
+    let cancel = Arc::new(AtomicBool::new(false));
+    conn.set_file_transfer_active(true);
+    ssh_log::info(
+        SshLogRecord::new(
+            "ssh.transfer",
+            "detected",
+            "Detected in-terminal file transfer handshake",
+        )
+        .session_id(id.to_string())
+        .server_id(conn.config.id.clone())
+        .instance_id(conn.instance_id)
+        .field("transfer_id", transfer_id.clone())
+        .field("protocol", format!("{:?}", detected.protocol))
+        .field("direction", format!("{:?}", detected.direction)),
+    );
+
+    let selection = request_selection(app, id, &transfer_id, &detected).await;
+    let registry = app.state::<TerminalTransferRegistry>();
+    {
+        let mut active = match registry.active.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        active.insert(transfer_id.clone(), cancel.clone());
+    }
+
+    let mut io = TransferChannel::new(channel, data, cancel);
+    let mut reporter = TransferReporter::new(app, id, &transfer_id);
+    let started = selection.is_ok();
+    let result = match selection {
+        Ok(selection) => {
+            execute_transfer(&mut io, detected.protocol, selection, &mut reporter).await
+        }
+        Err(err) => Err(err),
+    };
+    // 失败或取消时通知远端中止，远端程序随后退出回到 shell
+    if let Err(err) = &result {
+        let _ = match detected.protocol {
+            TerminalTransferProtocol::Zmodem => zmodem::abort(&mut io).await,
+            TerminalTransferProtocol::Trzsz => trzsz::abort(&mut io, err, started).await,
+        };
+    }
+    let completed = std::mem::take(&mut reporter.completed);
+    let (remaining, channel_closed) = io.into_remaining();
+
+    {
+        let mut active = match registry.active.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        active.remove(&transfer_id);
+    }
+    conn.set_file_transfer_active(false);
+
+    let status = match &result {
+        Ok(()) => "completed",
+        Err(err) if err.starts_with("TRANSFER_CANCELLED") => "cancelled",
+        Err(_) => "failed",
+    };
+    let mut record = SshLogRecord::new(
+        "ssh.transfer",
+        "finished",
+        "In-terminal file transfer finished",
+    )
+    .session_id(id.to_string())
+    .server_id(conn.config.id.clone())
+    .instance_id(conn.instance_id)
+    .field("transfer_id", transfer_id.clone())
+    .field("status", status)
+    .field("files", completed.len());
+    if let Err(err) = &result {
+        record = record.field("error", err.clone());
+    }
+    if result.is_ok() {
+        ssh_log::info(record);
+    } else {
+        ssh_log::warn(record);
+    }
+    let _ = app.emit(
+        &format!("term-transfer-end-{}", id),
+        TerminalTransferEndEvent {
+            transfer_id,
+            status: status.to_string(),
+            files: completed,
+            error: result.err(),
+        },
+    );
+
+    TransferOutcome {
+        // 中止后缓冲中残留的是协议数据，不再输出到终端
+        remaining: if status == "completed" {
+            remaining
+        } else {
+            Vec::new()
+        },
+        channel_closed,
+    }
+}
@@ -1,55 +1,89 @@
 use std::sync::Arc;
 use std::time::Duration;
 use russh::client;
+use tauri::AppHandle;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
 
-use crate::commands::ssh::state::SshSession;
+use crate::commands::ssh::state::{disconnect_jump_sessions, JumpSessions, SshSession};
 use crate::commands::ssh::utils::auth_method_label;
 use crate::models::SshConfig;
 use crate::utils::ssh_log::{self, SshLogRecord};
 
 use super::{
-    auth::authenticate_session, proxy::establish_tcp_stream, with_connection_context,
-    DEFAULT_CONNECT_TIMEOUT_SECS,
+    auth::authenticate_session,
+    client::PiTermClientHandler,
+    persistent_session::PersistentShell,
+    proxy::establish_tcp_stream,
+    shell_startup::{has_startup_input, shell_exec_command},
+    with_connection_context, DEFAULT_CONNECT_TIMEOUT_SECS,
 };
 
-pub async fn establish_base_session(
+const PERSISTENT_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 🟢 在已有流 (TCP 或跳板机的 direct-tcpip 通道) 上完成 SSH 握手与认证
+async fn handshake_and_authenticate<S>(
+    app: &AppHandle,
     config: &SshConfig,
+    stream: S,
     session_id: Option<&str>,
     role: &'static str,
-) -> Result<SshSession, String> {
+) -> Result<SshSession, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let mut client_config = client::Config {
+        // Interactive shells are allowed to sit idle. Liveness is handled by
+        // optional SSH keepalives and the frontend heartbeat cleanup path.
+        inactivity_timeout: None,
+        ..Default::default()
+    };
+
+    if let Some(interval) = config.keep_alive_interval.filter(|interval| *interval > 0) {
+        client_config.keepalive_interval = Some(Duration::from_secs(interval as u64));
+        ssh_log::debug(with_connection_context(
+            SshLogRecord::new(
+                "ssh.connect",
+                "keepalive_configured",
+                "Configured SSH keepalive interval",
+            )
+            .field("keepalive_interval_secs", interval),
+            session_id,
+            role,
+        ));
+    }
+
+    let client_config = Arc::new(client_config);
+    // 🟢 主机密钥在握手阶段按服务器策略校验，不再无条件信任
+    let handler = PiTermClientHandler::new(app.clone(), config, session_id, role);
+    let link = handler.link();
+
+    let handle = client::connect_stream(client_config, stream, handler)
+        .await
+        .map_err(|e| format!("Handshake Error: {}", e))?;
+    let mut sess = SshSession::new(handle, link);
+
     ssh_log::info(with_connection_context(
         SshLogRecord::new(
             "ssh.connect",
-            "session_establish_started",
-            "Establishing SSH session",
+            "handshake_completed",
+            "SSH handshake completed",
         )
         .field("host", config.host.clone())
-        .field("port", config.port)
-        .field("username", config.username.clone())
-        .field("connection_type", format!("{:?}", config.connection_type))
-        .field("auth_method", auth_method_label(config))
-        .field(
-            "proxy_type",
-            config
-                .proxy
-                .as_ref()
-                .map(|proxy| proxy.proxy_type.clone())
-                .unwrap_or_else(|| "none".to_string()),
-        )
-        .field(
-            "connect_timeout_secs",
-            config
-                .connect_timeout
-                .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS as u32),
-        )
-        .field(
-            "keepalive_interval_secs",
-            config.keep_alive_interval.unwrap_or_default(),
-        ),
+        .field("port", config.port),
         session_id,
         role,
     ));
 
+    authenticate_session(app, &mut sess, config, session_id, role).await?;
+    Ok(sess)
+}
+
+async fn connect_direct(
+    app: &AppHandle,
+    config: &SshConfig,
+    session_id: Option<&str>,
+    role: &'static str,
+) -> Result<SshSession, String> {
     let tcp = establish_tcp_stream(config)?;
     ssh_log::info(with_connection_context(
         SshLogRecord::new(
@@ -68,39 +102,33 @@ pub async fn establish_base_session(
     let async_stream = tokio::net::TcpStream::from_std(tcp)
         .map_err(|e| format!("Failed to convert TCP stream to tokio stream: {}", e))?;
 
-    let mut client_config = client::Config {
-        // Interactive shells are allowed to sit idle. Liveness is handled by
-        // optional SSH keepalives and the frontend heartbeat cleanup path.
-        inactivity_timeout: None,
-        ..Default::default()
-    };
-
-    if let Some(interval) = config.keep_alive_interval.filter(|interval| *interval > 0) {
-        client_config.keepalive_interval = Some(Duration::from_secs(interval as u64));
-        ssh_log::debug(with_connection_context(
-            SshLogRecord::new(
-                "ssh.connect",
-                "keepalive_configured",
-                "Configured SSH keepalive interval",
-            )
-            .field("keepalive_interval_secs", interval),
-            session_id,
-            role,
-        ));
-    }
-
-    let client_config = Arc::new(client_config);
-    let handler = crate::commands::ssh::core::client::PiTermClientHandler;
+    handshake_and_authenticate(app, config, async_stream, session_id, role).await
+}
 
-    let mut sess = client::connect_stream(client_config, async_stream, handler)
+pub async fn open_jump_tunnel(
+    jump: &SshSession,
+    host: &str,
+    port: u16,
+) -> Result<russh::Channel<client::Msg>, String> {
+    jump.channel_open_direct_tcpip(host.to_string(), port as u32, "127.0.0.1", 0)
         .await
-        .map_err(|e| format!("Handshake Error: {}", e))?;
+        .map_err(|e| format!("Jump Tunnel Error ({}:{}): {}", host, port, e))
+}
 
+// 🟢 通过上一跳打开 direct-tcpip 通道连接下一跳 (等同 ssh -J)
+async fn connect_through_jump(
+    app: &AppHandle,
+    jump: &SshSession,
+    config: &SshConfig,
+    session_id: Option<&str>,
+    role: &'static str,
+) -> Result<SshSession, String> {
+    let channel = open_jump_tunnel(jump, &config.host, config.port).await?;
     ssh_log::info(with_connection_context(
         SshLogRecord::new(
             "ssh.connect",
-            "handshake_completed",
-            "SSH handshake completed",
+            "jump_tunnel_opened",
+            "Opened direct-tcpip tunnel through jump host",
         )
         .field("host", config.host.clone())
         .field("port", config.port),
@@ -108,7 +136,62 @@ pub async fn establish_base_session(
         role,
     ));
 
-    authenticate_session(&mut sess, config, session_id, role).await?;
+    handshake_and_authenticate(app, config, channel.into_stream(), session_id, role).await
+}
+
+pub async fn establish_base_session(
+    app: &AppHandle,
+    config: &SshConfig,
+    session_id: Option<&str>,
+    role: &'static str,
+) -> Result<(SshSession, JumpSessions), String> {
+    ssh_log::info(with_connection_context(
+        SshLogRecord::new(
+            "ssh.connect",
+            "session_establish_started",
+            "Establishing SSH session",
+        )
+        .field("host", config.host.clone())
+        .field("port", config.port)
+        .field("username", config.username.clone())
+        .field("connection_type", format!("{:?}", config.connection_type))
+        .field("auth_method", auth_method_label(config))
+        .field(
+            "proxy_type",
+            config
+                .proxy
+                .as_ref()
+                .map(|proxy| proxy.proxy_type.clone())
+                .unwrap_or_else(|| "none".to_string()),
+        )
+        .field(
+            "connect_timeout_secs",
+            config
+                .connect_timeout
+                .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS as u32),
+        )
+        .field(
+            "keepalive_interval_secs",
+            config.keep_alive_interval.unwrap_or_default(),
+        )
+        .field("jump_hosts", config.jump_hosts.len()),
+        session_id,
+        role,
+    ));
+
+    let jump_sessions = establish_jump_chain(app, &config.jump_hosts, session_id, role).await?;
+
+    let sess_result = match jump_sessions.last() {
+        None => connect_direct(app, config, session_id, role).await,
+        Some(last_hop) => connect_through_jump(app, last_hop, config, session_id, role).await,
+    };
+    let sess = match sess_result {
+        Ok(sess) => sess,
+        Err(err) => {
+            disconnect_jump_sessions(jump_sessions, "PiTerm jump chain aborted").await;
+            return Err(err);
+        }
+    };
 
     ssh_log::info(with_connection_context(
         SshLogRecord::new(
@@ -118,20 +201,131 @@ pub async fn establish_base_session(
         )
         .field("host", config.host.clone())
         .field("port", config.port)
-        .field("username", config.username.clone()),
+        .field("username", config.username.clone())
+        .field("jump_hosts", jump_sessions.len()),
         session_id,
         role,
     ));
 
-    Ok(sess)
+    Ok((sess, jump_sessions))
+}
+
+// 🟢 ProxyJump：依次认证每个跳板机，每一跳都使用自己的凭据与主机密钥策略
+pub async fn establish_jump_chain(
+    app: &AppHandle,
+    jump_hosts: &[SshConfig],
+    session_id: Option<&str>,
+    role: &'static str,
+) -> Result<JumpSessions, String> {
+    let mut jump_sessions: JumpSessions = Vec::new();
+    for (index, hop) in jump_hosts.iter().enumerate() {
+        ssh_log::info(with_connection_context(
+            SshLogRecord::new(
+                "ssh.connect",
+                "jump_host_connecting",
+                "Connecting to jump host",
+            )
+            .server_id(hop.id.clone())
+            .field("hop", index + 1)
+            .field("host", hop.host.clone())
+            .field("port", hop.port)
+            .field("username", hop.username.clone()),
+            session_id,
+            role,
+        ));
+
+        let hop_result = match jump_sessions.last() {
+            None => connect_direct(app, hop, session_id, "jump").await,
+            Some(previous) => connect_through_jump(app, previous, hop, session_id, "jump").await,
+        };
+        match hop_result {
+            Ok(hop_session) => jump_sessions.push(Arc::new(hop_session)),
+            Err(err) => {
+                disconnect_jump_sessions(jump_sessions, "PiTerm jump chain aborted").await;
+                return Err(format!(
+                    "Jump host {} ({}:{}) failed: {}",
+                    index + 1,
+                    hop.host,
+                    hop.port,
+                    err
+                ));
+            }
+        }
+    }
+    Ok(jump_sessions)
 }
 
 pub async fn create_shell_channel(
+    app: &AppHandle,
     config: &SshConfig,
     session_id: Option<&str>,
-) -> Result<(SshSession, russh::Channel<russh::client::Msg>), String> {
-    let sess = establish_base_session(config, session_id, "shell").await?;
+    persistent: Option<&mut PersistentShell>,
+    role: &'static str,
+) -> Result<(SshSession, russh::Channel<russh::client::Msg>, JumpSessions), String> {
+    let (sess, jump_sessions) = establish_base_session(app, config, session_id, role).await?;
 
+    match open_shell_channel(&sess, config, session_id, persistent).await {
+        Ok(channel) => Ok((sess, channel, jump_sessions)),
+        Err(err) => {
+            disconnect_jump_sessions(jump_sessions, "PiTerm shell channel failed").await;
+            Err(err)
+        }
+    }
+}
+
+// 🟢 探测远端是否已有同名持久会话；探测失败时按已存在处理，宁可不输入启动内容也不往已有会话里误输入
+async fn persistent_session_exists(
+    sess: &SshSession,
+    persistent: &PersistentShell,
+    session_id: Option<&str>,
+) -> bool {
+    let probe = async {
+        let channel = sess
+            .channel_open_session()
+            .await
+            .map_err(|e| format!("Failed to open channel: {}", e))?;
+        channel
+            .exec(true, persistent.exists_command())
+            .await
+            .map_err(|e| format!("Failed to execute command: {}", e))?;
+        let mut output = String::new();
+        channel
+            .into_stream()
+            .read_to_string(&mut output)
+            .await
+            .map_err(|e| format!("Failed to read command output: {}", e))?;
+        Ok::<_, String>(output)
+    };
+    match tokio::time::timeout(PERSISTENT_PROBE_TIMEOUT, probe)
+        .await
+        .unwrap_or_else(|_| Err("Timed out".to_string()))
+    {
+        Ok(output) => PersistentShell::exists_in_output(&output),
+        Err(err) => {
+            ssh_log::warn(with_connection_context(
+                SshLogRecord::new(
+                    "ssh.shell",
+                    "persistent_session_probe_failed",
+                    "Failed to check for an existing persistent session",
+                )
+                .field("session_name", persistent.name.clone())
+                .field("error", err),
+                session_id,
+                "shell",
+            ));
+            true
+        }
+    }
+}
+
+// 🟢 在已认证的传输上打开交互式 shell 通道 (复制标签页时复用同一传输)
+// persistent 不为空时以 exec 启动/附加远端 tmux/screen 会话，代替默认 shell，并记录会话是否为新建
+pub async fn open_shell_channel(
+    sess: &SshSession,
+    config: &SshConfig,
+    session_id: Option<&str>,
+    persistent: Option<&mut PersistentShell>,
+) -> Result<russh::Channel<russh::client::Msg>, String> {
     let channel = sess
         .channel_open_session()
         .await
@@ -143,8 +337,36 @@ pub async fn create_shell_channel(
         session_id,
         "shell",
     ));
+    // 🟢 Agent 转发需在启动 shell 前请求，远端据此设置 SSH_AUTH_SOCK
+    if config.agent_forwarding {
+        match channel.agent_forward(false).await {
+            Ok(()) => ssh_log::info(with_connection_context(
+                SshLogRecord::new(
+                    "ssh.agent_forward",
+                    "requested",
+                    "Requested agent forwarding on shell channel",
+                )
+                .server_id(config.id.clone())
+                .field("backend", if config.agent_forward_keys.is_empty() { "local" } else { "vault" }),
+                session_id,
+                "shell",
+            )),
+            Err(e) => ssh_log::warn(with_connection_context(
+                SshLogRecord::new(
+                    "ssh.agent_forward",
+                    "request_failed",
+                    "Failed to request agent forwarding",
+                )
+                .server_id(config.id.clone())
+                .field("error", e.to_string()),
+                session_id,
+                "shell",
+            )),
+        }
+    }
+    let options = config.shell_options.clone().unwrap_or_default();
     channel
-        .request_pty(true, "xterm-256color", 80, 24, 0, 0, &[])
+        .request_pty(true, &options.term_type, options.cols, options.rows, 0, 0, &[])
         .await
         .map_err(|e| format!("PTY Error: {}", e))?;
     ssh_log::debug(with_connection_context(
@@ -153,12 +375,69 @@ pub async fn create_shell_channel(
             "pty_requested",
             "Requested PTY for shell channel",
         )
-        .field("pty", "xterm-256color")
-        .field("rows", 24)
-        .field("cols", 80),
+        .field("pty", options.term_type.clone())
+        .field("rows", options.rows)
+        .field("cols", options.cols),
         session_id,
         "shell",
     ));
+    // 🟢 环境变量需远端 sshd 的 AcceptEnv 放行，被拒绝时不影响 shell 启动
+    for var in &options.env {
+        if let Err(e) = channel.set_env(false, var.name.as_str(), var.value.as_str()).await {
+            ssh_log::warn(with_connection_context(
+                SshLogRecord::new(
+                    "ssh.shell",
+                    "set_env_failed",
+                    "Failed to send environment variable",
+                )
+                .server_id(config.id.clone())
+                .field("name", var.name.clone())
+                .field("error", e.to_string()),
+                session_id,
+                "shell",
+            ));
+        }
+    }
+    if let Some(persistent) = persistent {
+        // 重新附加时远端 shell 早已在运行，只有新建会话才需要输入启动内容
+        persistent.created = persistent.owned
+            && has_startup_input(&options)
+            && !persistent_session_exists(sess, persistent, session_id).await;
+        let command = shell_exec_command(&options);
+        channel
+            .exec(true, persistent.launch_command(command.as_deref()))
+            .await
+            .map_err(|e| format!("Shell Start Error: {}", e))?;
+        ssh_log::info(with_connection_context(
+            SshLogRecord::new(
+                "ssh.shell",
+                "persistent_session_started",
+                "Started or attached persistent remote session",
+            )
+            .field("multiplexer", persistent.program())
+            .field("session_name", persistent.name.clone())
+            .field("created", persistent.created),
+            session_id,
+            "shell",
+        ));
+        return Ok(channel);
+    }
+
+    // 🟢 固定命令代替登录 shell (启用持久会话时已在上面作为新会话内的程序运行)
+    if let Some(command) = shell_exec_command(&options) {
+        channel
+            .exec(true, command)
+            .await
+            .map_err(|e| format!("Shell Start Error: {}", e))?;
+        ssh_log::info(with_connection_context(
+            SshLogRecord::new("ssh.shell", "command_started", "Started fixed remote command")
+                .server_id(config.id.clone()),
+            session_id,
+            "shell",
+        ));
+        return Ok(channel);
+    }
+
     channel
         .request_shell(true)
         .await
@@ -169,5 +448,5 @@ pub async fn create_shell_channel(
         "shell",
     ));
 
-    Ok((sess, channel))
+    Ok(channel)
 }
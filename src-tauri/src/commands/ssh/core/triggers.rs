@@ -0,0 +1,237 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::models::{TriggerAction, TriggerRule};
+use crate::utils::ansi::AnsiStripper;
+
+// 匹配窗口只保留最近的输出 (去除控制序列后)，提示符通常就在最后几行
+const TRIGGER_WINDOW_LIMIT_BYTES: usize = 4 * 1024;
+const TRIGGER_PATTERN_SIZE_LIMIT: usize = 256 * 1024;
+// 全局限速：防止规则互相触发 (发送内容被回显又命中) 形成循环
+const TRIGGER_RATE_WINDOW: Duration = Duration::from_secs(10);
+const TRIGGER_RATE_LIMIT: usize = 10;
+const TRIGGER_MIN_COOLDOWN_MS: u32 = 100;
+
+pub fn compile_trigger_pattern(pattern: &str) -> Result<Regex, String> {
+    if pattern.trim().is_empty() {
+        return Err("Trigger pattern is required".to_string());
+    }
+    RegexBuilder::new(pattern)
+        .size_limit(TRIGGER_PATTERN_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("Invalid trigger pattern: {}", e))
+}
+
+fn sends_input(action: TriggerAction) -> bool {
+    action != TriggerAction::Notify
+}
+
+// 提示符结尾常见的 "? " ": "，规则不必把它们写进正则
+fn is_prompt_tail(tail: &str) -> bool {
+    tail.chars()
+        .all(|ch| ch.is_whitespace() || matches!(ch, '?' | ':'))
+}
+
+struct CompiledTrigger {
+    rule: TriggerRule,
+    regex: Regex,
+    last_fired: Option<Instant>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TriggerMatch {
+    pub rule: TriggerRule,
+    pub matched: String,
+    // 命中但因冷却/全局限速被跳过
+    pub rate_limited: bool,
+}
+
+// 🟢 输出触发器引擎：在去除控制序列的滑动窗口上匹配正则，命中后消费窗口避免重复触发；
+// 通知类动作匹配整个窗口，发送类动作只匹配提示符
+#[derive(Default)]
+pub struct TriggerEngine {
+    triggers: Vec<CompiledTrigger>,
+    stripper: AnsiStripper,
+    window: String,
+    fired: VecDeque<Instant>,
+}
+
+impl TriggerEngine {
+    // 无法编译的规则直接跳过 (保存时已校验)
+    pub fn set_rules(&mut self, rules: Vec<TriggerRule>) {
+        self.triggers = rules
+            .into_iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| {
+                let regex = compile_trigger_pattern(&rule.pattern).ok()?;
+                Some(CompiledTrigger {
+                    rule,
+                    regex,
+                    last_fired: None,
+                })
+            })
+            .collect();
+        self.window.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triggers.is_empty()
+    }
+
+    pub fn push(&mut self, text: &str) -> Vec<TriggerMatch> {
+        if self.triggers.is_empty() {
+            return Vec::new();
+        }
+        self.stripper.push(text, &mut self.window);
+        self.trim_window();
+
+        let now = Instant::now();
+        while self
+            .fired
+            .front()
+            .is_some_and(|fired| now.duration_since(*fired) > TRIGGER_RATE_WINDOW)
+        {
+            self.fired.pop_front();
+        }
+
+        // 🟢 发送类动作只匹配尚未换行的最后一行 (提示符)，匹配之后只允许剩下空白、'?'、':'；
+        // 普通输出里出现相同文字 (例如 cat 到 "Password:") 不会把密码打进 shell
+        let prompt_start = self.window.rfind(['\n', '\r']).map_or(0, |i| i + 1);
+        let prompt = &self.window[prompt_start..];
+
+        let mut matches = Vec::new();
+        let mut consumed = 0;
+        for trigger in &mut self.triggers {
+            let found = if sends_input(trigger.rule.action) {
+                trigger
+                    .regex
+                    .find_iter(prompt)
+                    .last()
+                    .filter(|found| is_prompt_tail(&prompt[found.end()..]))
+                    .map(|found| (found.as_str().to_string(), self.window.len()))
+            } else {
+                trigger
+                    .regex
+                    .find(&self.window)
+                    .map(|found| (found.as_str().to_string(), found.end()))
+            };
+            let Some((matched, end)) = found else {
+                continue;
+            };
+            consumed = consumed.max(end);
+            let cooldown =
+                Duration::from_millis(trigger.rule.cooldown_ms.max(TRIGGER_MIN_COOLDOWN_MS) as u64);
+            let cooling = trigger
+                .last_fired
+                .is_some_and(|last| now.duration_since(last) < cooldown);
+            let rate_limited = cooling || self.fired.len() >= TRIGGER_RATE_LIMIT;
+            if !rate_limited {
+                trigger.last_fired = Some(now);
+                self.fired.push_back(now);
+            }
+            matches.push(TriggerMatch {
+                rule: trigger.rule.clone(),
+                matched,
+                rate_limited,
+            });
+        }
+        if consumed > 0 {
+            self.window.drain(..consumed);
+        }
+        matches
+    }
+
+    fn trim_window(&mut self) {
+        if self.window.len() <= TRIGGER_WINDOW_LIMIT_BYTES {
+            return;
+        }
+        let mut start = self.window.len() - TRIGGER_WINDOW_LIMIT_BYTES;
+        while !self.window.is_char_boundary(start) {
+            start += 1;
+        }
+        self.window.drain(..start);
+    }
+}
+
+// 🟢 保存前校验：正则可编译，发送/引用类动作必须有 payload
+pub fn validate_trigger_rule(rule: &TriggerRule) -> Result<(), String> {
+    compile_trigger_pattern(&rule.pattern)?;
+    let payload_required = match rule.action {
+        TriggerAction::SendText => !rule.append_newline,
+        TriggerAction::SendSecret | TriggerAction::RunSnippet => true,
+        TriggerAction::Notify => false,
+    };
+    if payload_required && rule.payload.trim().is_empty() {
+        return Err("Trigger payload is required for this action".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, action: TriggerAction) -> TriggerRule {
+        serde_json::from_value(serde_json::json!({
+            "id": "trg-1",
+            "serverId": "srv-1",
+            "pattern": pattern,
+            "action": action,
+            "payload": "yes",
+        }))
+        .unwrap()
+    }
+
+    fn engine(pattern: &str, action: TriggerAction) -> TriggerEngine {
+        let mut engine = TriggerEngine::default();
+        engine.set_rules(vec![rule(pattern, action)]);
+        engine
+    }
+
+    #[test]
+    fn send_rule_fires_on_ssh_host_key_prompt() {
+        let mut engine = engine(r"\(yes/no(/\[fingerprint\])?\)", TriggerAction::SendText);
+        let matches = engine.push(
+            "The authenticity of host 'db (10.0.0.5)' can't be established.\r\n\
+             Are you sure you want to continue connecting (yes/no/[fingerprint])? ",
+        );
+        assert_eq!(matches.len(), 1);
+    }
+
+    #[test]
+    fn send_rule_fires_on_sudo_prompt() {
+        let mut engine = engine(r"\[sudo\] password for \w+", TriggerAction::SendSecret);
+        assert_eq!(engine.push("[sudo] password for deploy: ").len(), 1);
+    }
+
+    #[test]
+    fn send_rule_accepts_pattern_with_trailing_space() {
+        let mut engine = engine("Password: ", TriggerAction::SendSecret);
+        assert_eq!(engine.push("Password: ").len(), 1);
+    }
+
+    #[test]
+    fn send_rule_ignores_matches_in_earlier_output() {
+        let mut engine = engine("Password", TriggerAction::SendSecret);
+        assert!(engine.push("Password: hunter2\r\n$ ").is_empty());
+    }
+
+    #[test]
+    fn send_rule_ignores_match_followed_by_more_text() {
+        let mut engine = engine("Password", TriggerAction::SendSecret);
+        assert!(engine
+            .push("Password reset required, contact admin")
+            .is_empty());
+    }
+
+    #[test]
+    fn notify_rule_matches_anywhere_in_window() {
+        let mut engine = engine("Segmentation fault", TriggerAction::Notify);
+        assert_eq!(
+            engine.push("Segmentation fault (core dumped)\r\n$ ").len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use md5::{Digest, Md5};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::terminal_transfer::{
+    unique_download_path, TransferChannel, TransferReporter, UploadFile,
+};
+
+// 按 trzsz 协议声明的客户端版本，不支持目录传输与二进制模式
+const TRZSZ_CLIENT_VERSION: &str = "1.1.6";
+const READ_TIMEOUT: Duration = Duration::from_secs(20);
+// 服务端单块数据上限默认 10MB，base64 之后的行长度留出余量
+const LINE_LIMIT: usize = 16 * 1024 * 1024;
+const UPLOAD_CHUNK_LEN: usize = 32 * 1024;
+
+// trzsz 行协议中的字符串与二进制数据均为 base64(zlib(data))
+fn encode_bytes(data: &[u8]) -> Result<String, String> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .map_err(|e| format!("trzsz compress failed: {}", e))?;
+    let compressed = encoder
+        .finish()
+        .map_err(|e| format!("trzsz compress failed: {}", e))?;
+    Ok(BASE64.encode(compressed))
+}
+
+fn decode_bytes(payload: &str) -> Result<Vec<u8>, String> {
+    let compressed = BASE64
+        .decode(payload.trim())
+        .map_err(|e| format!("trzsz invalid base64 payload: {}", e))?;
+    let mut data = Vec::new();
+    ZlibDecoder::new(&compressed[..])
+        .read_to_end(&mut data)
+        .map_err(|e| format!("trzsz decompress failed: {}", e))?;
+    Ok(data)
+}
+
+fn decode_string(payload: &str) -> Result<String, String> {
+    decode_bytes(payload).map(|data| String::from_utf8_lossy(&data).into_owned())
+}
+
+async fn send_line(io: &mut TransferChannel<'_>, kind: &str, payload: &str) -> Result<(), String> {
+    io.write(format!("#{}:{}\n", kind, payload).as_bytes())
+        .await
+}
+
+// 🟢 读取 "#KIND:payload" 行；远端的 "#FAIL:" 转为错误，其它杂项行 (远端回显等) 忽略
+async fn recv_line(io: &mut TransferChannel<'_>, kind: &str) -> Result<String, String> {
+    let prefix = format!("#{}:", kind);
+    loop {
+        let line = io.read_line(READ_TIMEOUT, LINE_LIMIT).await?;
+        let line = String::from_utf8_lossy(&line);
+        // Windows 服务端以 "!\n" 作为换行
+        let line = line.trim_end_matches(['\r', '!']);
+        if let Some(index) = line.rfind(&prefix) {
+            return Ok(line[index + prefix.len()..].to_string());
+        }
+        if let Some(index) = line.rfind("#FAIL:").or_else(|| line.rfind("#fail:")) {
+            let message = decode_string(&line[index + "#FAIL:".len()..])
+                .unwrap_or_else(|_| "unknown error".to_string());
+            return Err(format!("trzsz remote error: {}", message));
+        }
+    }
+}
+
+async fn recv_integer(io: &mut TransferChannel<'_>, kind: &str) -> Result<u64, String> {
+    let payload = recv_line(io, kind).await?;
+    payload
+        .trim()
+        .parse()
+        .map_err(|_| format!("trzsz invalid #{} value: {}", kind, payload))
+}
+
+async fn check_integer(io: &mut TransferChannel<'_>, expected: u64) -> Result<(), String> {
+    let value = recv_integer(io, "SUCC").await?;
+    if value != expected {
+        return Err(format!(
+            "trzsz remote acknowledged {} instead of {}",
+            value, expected
+        ));
+    }
+    Ok(())
+}
+
+// 回复 #ACT 后读取服务端配置；confirm 为 false 时服务端直接退出
+async fn handshake(io: &mut TransferChannel<'_>, confirm: bool) -> Result<(), String> {
+    let action = serde_json::json!({
+        "lang": "rust",
+        "confirm": confirm,
+        "version": TRZSZ_CLIENT_VERSION,
+        "support_dir": false,
+    });
+    send_line(io, "ACT", &encode_bytes(action.to_string().as_bytes())?).await?;
+    if !confirm {
+        return Ok(());
+    }
+
+    let config = decode_bytes(&recv_line(io, "CFG").await?)?;
+    let config: serde_json::Value =
+        serde_json::from_slice(&config).map_err(|e| format!("trzsz invalid config: {}", e))?;
+    if config.get("binary").and_then(|v| v.as_bool()) == Some(true) {
+        return Err("trzsz binary mode is not supported, run trz/tsz without -b".to_string());
+    }
+    if config.get("directory").and_then(|v| v.as_bool()) == Some(true) {
+        return Err("trzsz directory transfer is not supported".to_string());
+    }
+    Ok(())
+}
+
+// 🟢 取消或出错：尚未开始传输时回复 confirm=false，否则发送 #FAIL 让远端 trz/tsz 退出
+pub async fn abort(io: &mut TransferChannel<'_>, error: &str, started: bool) -> Result<(), String> {
+    if !started {
+        return handshake(io, false).await;
+    }
+    let message = encode_bytes(error.as_bytes())?;
+    send_line(io, "FAIL", &message).await
+}
+
+async fn finish(io: &mut TransferChannel<'_>, message: &str) -> Result<(), String> {
+    let message = encode_bytes(message.as_bytes())?;
+    send_line(io, "EXIT", &message).await
+}
+
+async fn send_file(
+    io: &mut TransferChannel<'_>,
+    file: &UploadFile,
+    reporter: &mut TransferReporter<'_>,
+) -> Result<(), String> {
+    let mut source = tokio::fs::File::open(&file.path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", file.path.display(), e))?;
+    send_line(io, "NAME", &encode_bytes(file.name.as_bytes())?).await?;
+    recv_line(io, "SUCC").await?;
+    send_line(io, "SIZE", &file.size.to_string()).await?;
+    check_integer(io, file.size).await?;
+
+    reporter.start_file(&file.name, &file.path, file.size);
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0u8; UPLOAD_CHUNK_LEN];
+    let mut sent = 0u64;
+    while sent < file.size {
+        let read = source
+            .read(&mut buffer)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", file.path.display(), e))?;
+        if read == 0 {
+            return Err(format!("{} changed during upload", file.path.display()));
+        }
+        let chunk = &buffer[..read];
+        send_line(io, "DATA", &encode_bytes(chunk)?).await?;
+        check_integer(io, read as u64).await?;
+        hasher.update(chunk);
+        sent += read as u64;
+        reporter.progress(sent);
+    }
+
+    let digest = hasher.finalize();
+    send_line(io, "MD5", &encode_bytes(&digest)?).await?;
+    if decode_bytes(&recv_line(io, "SUCC").await?)? != digest.as_slice() {
+        return Err(format!("trzsz MD5 mismatch for {}", file.name));
+    }
+    reporter.finish_file(&file.path);
+    Ok(())
+}
+
+// 🟢 远端 trz 接收：逐个发送 NAME / SIZE / DATA / MD5，每一步等待 #SUCC 确认
+pub async fn send_files(
+    io: &mut TransferChannel<'_>,
+    files: &[UploadFile],
+    reporter: &mut TransferReporter<'_>,
+) -> Result<(), String> {
+    handshake(io, true).await?;
+    send_line(io, "NUM", &files.len().to_string()).await?;
+    check_integer(io, files.len() as u64).await?;
+    for file in files {
+        send_file(io, file, reporter).await?;
+    }
+    finish(io, &format!("Uploaded {} file(s)", files.len())).await
+}
+
+async fn receive_file_data(
+    io: &mut TransferChannel<'_>,
+    file: &mut tokio::fs::File,
+    size: u64,
+    reporter: &mut TransferReporter<'_>,
+) -> Result<(), String> {
+    let mut hasher = Md5::new();
+    let mut received = 0u64;
+    while received < size {
+        let data = decode_bytes(&recv_line(io, "DATA").await?)?;
+        if data.is_empty() {
+            return Err("trzsz received an empty data chunk".to_string());
+        }
+        file.write_all(&data)
+            .await
+            .map_err(|e| format!("Failed to write local file: {}", e))?;
+        hasher.update(&data);
+        received += data.len() as u64;
+        send_line(io, "SUCC", &data.len().to_string()).await?;
+        reporter.progress(received);
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write local file: {}", e))?;
+
+    let digest = decode_bytes(&recv_line(io, "MD5").await?)?;
+    if digest != hasher.finalize().as_slice() {
+        return Err("trzsz MD5 mismatch".to_string());
+    }
+    send_line(io, "SUCC", &encode_bytes(&digest)?).await
+}
+
+async fn receive_file(
+    io: &mut TransferChannel<'_>,
+    dir: &Path,
+    reporter: &mut TransferReporter<'_>,
+) -> Result<(), String> {
+    let name = decode_string(&recv_line(io, "NAME").await?)?;
+    let path = unique_download_path(dir, &name)?;
+    let mut file = tokio::fs::File::create(&path)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let local_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    send_line(io, "SUCC", &encode_bytes(local_name.as_bytes())?).await?;
+    let size = recv_integer(io, "SIZE").await?;
+    send_line(io, "SUCC", &size.to_string()).await?;
+
+    reporter.start_file(&name, &path, size);
+    match receive_file_data(io, &mut file, size, reporter).await {
+        Ok(()) => {
+            reporter.finish_file(&path);
+            Ok(())
+        }
+        Err(err) => {
+            // 不保留不完整的文件
+            drop(file);
+            let _ = tokio::fs::remove_file(&path).await;
+            Err(err)
+        }
+    }
+}
+
+// 🟢 远端 tsz 发送：按 NUM 个文件依次接收，保存到 dir
+pub async fn receive_files(
+    io: &mut TransferChannel<'_>,
+    dir: &Path,
+    reporter: &mut TransferReporter<'_>,
+) -> Result<(), String> {
+    handshake(io, true).await?;
+    let count = recv_integer(io, "NUM").await?;
+    send_line(io, "SUCC", &count.to_string()).await?;
+    for _ in 0..count {
+        receive_file(io, dir, reporter).await?;
+    }
+    finish(io, &format!("Saved {} file(s) to {}", count, dir.display())).await
+}
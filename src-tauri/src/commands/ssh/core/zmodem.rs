@@ -0,0 +1,699 @@
+use std::io::SeekFrom;
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use super::terminal_transfer::{
+    unique_download_path, TransferChannel, TransferReporter, UploadFile,
+};
+
+const ZPAD: u8 = b'*';
+const ZDLE: u8 = 0x18;
+const ZBIN: u8 = b'A';
+const ZHEX: u8 = b'B';
+const ZBIN32: u8 = b'C';
+
+// 帧类型
+const ZRQINIT: u8 = 0;
+const ZRINIT: u8 = 1;
+const ZSINIT: u8 = 2;
+const ZACK: u8 = 3;
+const ZFILE: u8 = 4;
+const ZSKIP: u8 = 5;
+const ZNAK: u8 = 6;
+const ZABORT: u8 = 7;
+const ZFIN: u8 = 8;
+const ZRPOS: u8 = 9;
+const ZDATA: u8 = 10;
+const ZEOF: u8 = 11;
+const ZFERR: u8 = 12;
+const ZCRC: u8 = 13;
+const ZCHALLENGE: u8 = 14;
+const ZCAN: u8 = 16;
+const ZFREECNT: u8 = 17;
+const ZCOMMAND: u8 = 18;
+
+// 数据子包结束标记
+const ZCRCE: u8 = b'h';
+const ZCRCG: u8 = b'i';
+const ZCRCQ: u8 = b'j';
+const ZCRCW: u8 = b'k';
+const ZRUB0: u8 = b'l';
+const ZRUB1: u8 = b'm';
+
+// ZRINIT 能力标志 (ZF0)
+const CANFDX: u8 = 0x01;
+const CANOVIO: u8 = 0x02;
+const CANFC32: u8 = 0x20;
+// ZFILE 转换选项 (ZF0)：二进制传输
+const ZCBIN: u8 = 1;
+
+const XON: u8 = 0x11;
+const READ_TIMEOUT: Duration = Duration::from_secs(20);
+const FINISH_TIMEOUT: Duration = Duration::from_millis(500);
+const SUBPACKET_LEN: usize = 1024;
+const MAX_SUBPACKET_LEN: usize = 16 * 1024;
+// 未声明缓冲区大小的接收方，每发送这么多数据以 ZCRCW 等待一次确认
+const SEND_WINDOW: usize = 32 * 1024;
+// 同步到帧头之前允许跳过的字节数
+const MAX_GARBAGE: usize = 8 * 1024 * 1024;
+const MAX_RETRIES: usize = 10;
+
+// 10 个 CAN 加 10 个退格，与 lrzsz 的中止序列一致
+const ABORT_SEQUENCE: &[u8] =
+    b"\x18\x18\x18\x18\x18\x18\x18\x18\x18\x18\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08";
+
+fn remote_aborted() -> String {
+    "ZMODEM transfer aborted by remote".to_string()
+}
+
+struct Header {
+    frame_type: u8,
+    data: [u8; 4],
+    crc32: bool,
+}
+
+impl Header {
+    fn position(&self) -> u64 {
+        u32::from_le_bytes(self.data) as u64
+    }
+}
+
+fn position_bytes(position: u64) -> [u8; 4] {
+    (position as u32).to_le_bytes()
+}
+
+fn crc16_update(mut crc: u16, data: &[u8]) -> u16 {
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+fn crc32_of(parts: &[&[u8]]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize()
+}
+
+// 转义 ZDLE、DLE、XON/XOFF 与 CR 及其高位形式，避免被 PTY 或流控吞掉
+fn escape_into(out: &mut Vec<u8>, data: &[u8]) {
+    for &byte in data {
+        match byte {
+            ZDLE | 0x10 | 0x90 | 0x11 | 0x91 | 0x13 | 0x93 | 0x0d | 0x8d => {
+                out.push(ZDLE);
+                out.push(byte ^ 0x40);
+            }
+            _ => out.push(byte),
+        }
+    }
+}
+
+fn hex_header(frame_type: u8, data: [u8; 4]) -> Vec<u8> {
+    let mut raw = vec![frame_type];
+    raw.extend_from_slice(&data);
+    let crc = crc16_update(0, &raw);
+    raw.extend_from_slice(&crc.to_be_bytes());
+
+    let mut out = vec![ZPAD, ZPAD, ZDLE, ZHEX];
+    for byte in raw {
+        out.extend_from_slice(format!("{:02x}", byte).as_bytes());
+    }
+    out.extend_from_slice(b"\r\x8a");
+    if frame_type != ZFIN && frame_type != ZACK {
+        out.push(XON);
+    }
+    out
+}
+
+fn binary_header(frame_type: u8, data: [u8; 4], crc32: bool) -> Vec<u8> {
+    let mut raw = vec![frame_type];
+    raw.extend_from_slice(&data);
+    let mut out = vec![ZPAD, ZDLE, if crc32 { ZBIN32 } else { ZBIN }];
+    escape_into(&mut out, &raw);
+    if crc32 {
+        escape_into(&mut out, &crc32_of(&[&raw]).to_le_bytes());
+    } else {
+        escape_into(&mut out, &crc16_update(0, &raw).to_be_bytes());
+    }
+    out
+}
+
+fn data_subpacket(data: &[u8], end: u8, crc32: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 8 + 8);
+    escape_into(&mut out, data);
+    out.push(ZDLE);
+    out.push(end);
+    if crc32 {
+        escape_into(&mut out, &crc32_of(&[data, &[end]]).to_le_bytes());
+    } else {
+        let crc = crc16_update(crc16_update(0, data), &[end]);
+        escape_into(&mut out, &crc.to_be_bytes());
+    }
+    if end == ZCRCW {
+        out.push(XON);
+    }
+    out
+}
+
+enum ZdleByte {
+    Data(u8),
+    FrameEnd(u8),
+}
+
+// 读取一个 ZDLE 解码后的字节；连续 5 个 CAN 视为远端取消
+async fn read_zdle(io: &mut TransferChannel<'_>) -> Result<ZdleByte, String> {
+    loop {
+        match io.read_byte(READ_TIMEOUT).await? {
+            0x11 | 0x13 | 0x91 | 0x93 => continue,
+            ZDLE => break,
+            byte => return Ok(ZdleByte::Data(byte)),
+        }
+    }
+    let mut cancels = 1;
+    loop {
+        match io.read_byte(READ_TIMEOUT).await? {
+            0x11 | 0x13 | 0x91 | 0x93 => {}
+            ZDLE => {
+                cancels += 1;
+                if cancels >= 5 {
+                    return Err(remote_aborted());
+                }
+            }
+            byte @ (ZCRCE | ZCRCG | ZCRCQ | ZCRCW) => return Ok(ZdleByte::FrameEnd(byte)),
+            ZRUB0 => return Ok(ZdleByte::Data(0x7f)),
+            ZRUB1 => return Ok(ZdleByte::Data(0xff)),
+            byte if byte & 0x60 == 0x40 => return Ok(ZdleByte::Data(byte ^ 0x40)),
+            byte => return Err(format!("ZMODEM bad escape sequence 0x{:02x}", byte)),
+        }
+    }
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|value| value as u8)
+}
+
+async fn read_hex_header(io: &mut TransferChannel<'_>) -> Result<Option<Header>, String> {
+    let mut raw = [0u8; 7];
+    for slot in raw.iter_mut() {
+        let high = hex_value(io.read_byte(READ_TIMEOUT).await? & 0x7f);
+        let low = hex_value(io.read_byte(READ_TIMEOUT).await? & 0x7f);
+        let (Some(high), Some(low)) = (high, low) else {
+            return Ok(None);
+        };
+        *slot = (high << 4) | low;
+    }
+    // 吃掉结尾的 CR LF
+    let byte = io.read_byte(READ_TIMEOUT).await?;
+    if byte & 0x7f == b'\r' {
+        if let Some(next) = io.try_read_byte(FINISH_TIMEOUT).await? {
+            if next & 0x7f != b'\n' {
+                io.unread(next);
+            }
+        }
+    } else {
+        io.unread(byte);
+    }
+    if crc16_update(0, &raw[..5]).to_be_bytes() != raw[5..] {
+        return Ok(None);
+    }
+    Ok(Some(Header {
+        frame_type: raw[0],
+        data: [raw[1], raw[2], raw[3], raw[4]],
+        crc32: false,
+    }))
+}
+
+async fn read_binary_header(
+    io: &mut TransferChannel<'_>,
+    crc32: bool,
+) -> Result<Option<Header>, String> {
+    let crc_len = if crc32 { 4 } else { 2 };
+    let mut raw = [0u8; 9];
+    for slot in raw.iter_mut().take(5 + crc_len) {
+        match read_zdle(io).await? {
+            ZdleByte::Data(byte) => *slot = byte,
+            ZdleByte::FrameEnd(_) => return Ok(None),
+        }
+    }
+    let valid = if crc32 {
+        crc32_of(&[&raw[..5]]).to_le_bytes() == raw[5..9]
+    } else {
+        crc16_update(0, &raw[..5]).to_be_bytes() == raw[5..7]
+    };
+    Ok(valid.then_some(Header {
+        frame_type: raw[0],
+        data: [raw[1], raw[2], raw[3], raw[4]],
+        crc32,
+    }))
+}
+
+// 🟢 同步到下一个帧头 (ZPAD ZDLE 格式)；校验失败的帧头直接跳过，由对端超时重发
+async fn read_header(io: &mut TransferChannel<'_>) -> Result<Header, String> {
+    let mut skipped = 0usize;
+    let mut cancels = 0;
+    loop {
+        let byte = io.read_byte(READ_TIMEOUT).await?;
+        if byte == ZDLE {
+            cancels += 1;
+            if cancels >= 5 {
+                return Err(remote_aborted());
+            }
+        } else {
+            cancels = 0;
+        }
+        if byte != ZPAD {
+            skipped += 1;
+            if skipped > MAX_GARBAGE {
+                return Err("ZMODEM header not found in remote output".to_string());
+            }
+            continue;
+        }
+        let mut byte = io.read_byte(READ_TIMEOUT).await?;
+        while byte == ZPAD {
+            byte = io.read_byte(READ_TIMEOUT).await?;
+        }
+        if byte != ZDLE {
+            io.unread(byte);
+            continue;
+        }
+        let header = match io.read_byte(READ_TIMEOUT).await? {
+            ZHEX => read_hex_header(io).await?,
+            ZBIN => read_binary_header(io, false).await?,
+            ZBIN32 => read_binary_header(io, true).await?,
+            other => {
+                io.unread(other);
+                None
+            }
+        };
+        if let Some(header) = header {
+            return Ok(header);
+        }
+    }
+}
+
+// 返回 None 表示子包损坏 (CRC 错误或超长)，由调用方请求重发
+async fn read_subpacket(
+    io: &mut TransferChannel<'_>,
+    crc32: bool,
+) -> Result<Option<(Vec<u8>, u8)>, String> {
+    let mut data = Vec::new();
+    loop {
+        match read_zdle(io).await? {
+            ZdleByte::Data(byte) => {
+                if data.len() >= MAX_SUBPACKET_LEN {
+                    return Ok(None);
+                }
+                data.push(byte);
+            }
+            ZdleByte::FrameEnd(end) => {
+                let crc_len = if crc32 { 4 } else { 2 };
+                let mut crc = [0u8; 4];
+                for slot in crc.iter_mut().take(crc_len) {
+                    match read_zdle(io).await? {
+                        ZdleByte::Data(byte) => *slot = byte,
+                        ZdleByte::FrameEnd(_) => return Ok(None),
+                    }
+                }
+                let valid = if crc32 {
+                    crc32_of(&[&data, &[end]]).to_le_bytes() == crc
+                } else {
+                    crc16_update(crc16_update(0, &data), &[end]).to_be_bytes() == crc[..2]
+                };
+                return Ok(valid.then_some((data, end)));
+            }
+        }
+    }
+}
+
+// ZFILE 子包：文件名\0长度 修改时间(八进制) 权限 ...\0
+fn parse_file_info(info: &[u8]) -> (String, Option<u64>) {
+    let mut parts = info.split(|byte| *byte == 0);
+    let name = String::from_utf8_lossy(parts.next().unwrap_or_default()).into_owned();
+    let size = parts.next().map(String::from_utf8_lossy).and_then(|meta| {
+        meta.split_ascii_whitespace()
+            .next()
+            .and_then(|size| size.parse().ok())
+    });
+    (name, size)
+}
+
+// 远端中止后对端可能仍在发送，尽量把结束标记 "OO" 从缓冲中读掉
+async fn consume_over_and_out(io: &mut TransferChannel<'_>) -> Result<(), String> {
+    for _ in 0..2 {
+        match io.try_read_byte(FINISH_TIMEOUT).await? {
+            Some(b'O') => {}
+            Some(other) => {
+                io.unread(other);
+                break;
+            }
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+pub async fn abort(io: &mut TransferChannel<'_>) -> Result<(), String> {
+    io.write(ABORT_SEQUENCE).await
+}
+
+async fn receive_file_data(
+    io: &mut TransferChannel<'_>,
+    file: &mut tokio::fs::File,
+    reporter: &mut TransferReporter<'_>,
+) -> Result<(), String> {
+    let mut received = 0u64;
+    let mut errors = 0;
+    io.write(&hex_header(ZRPOS, position_bytes(0))).await?;
+    loop {
+        let header = read_header(io).await?;
+        match header.frame_type {
+            ZDATA => {
+                // 位置不一致 (之前的数据有丢失)，要求从已接收处重发
+                if header.position() != received {
+                    io.write(&hex_header(ZRPOS, position_bytes(received)))
+                        .await?;
+                    continue;
+                }
+                loop {
+                    let Some((data, end)) = read_subpacket(io, header.crc32).await? else {
+                        errors += 1;
+                        if errors > MAX_RETRIES {
+                            return Err("ZMODEM too many data errors".to_string());
+                        }
+                        io.write(&hex_header(ZRPOS, position_bytes(received)))
+                            .await?;
+                        break;
+                    };
+                    file.write_all(&data)
+                        .await
+                        .map_err(|e| format!("Failed to write local file: {}", e))?;
+                    received += data.len() as u64;
+                    reporter.progress(received);
+                    match end {
+                        ZCRCW => {
+                            io.write(&hex_header(ZACK, position_bytes(received)))
+                                .await?;
+                            break;
+                        }
+                        ZCRCQ => {
+                            io.write(&hex_header(ZACK, position_bytes(received)))
+                                .await?
+                        }
+                        ZCRCG => {}
+                        _ => break,
+                    }
+                }
+            }
+            // 数据尚未收全的 ZEOF 忽略，等待发送方重发
+            ZEOF if header.position() == received => {
+                return file
+                    .flush()
+                    .await
+                    .map_err(|e| format!("Failed to write local file: {}", e));
+            }
+            ZFILE => {
+                read_subpacket(io, header.crc32).await?;
+                io.write(&hex_header(ZRPOS, position_bytes(received)))
+                    .await?;
+            }
+            ZNAK => {
+                io.write(&hex_header(ZRPOS, position_bytes(received)))
+                    .await?
+            }
+            ZFIN | ZABORT | ZFERR | ZCAN => return Err(remote_aborted()),
+            _ => {}
+        }
+    }
+}
+
+async fn receive_file(
+    io: &mut TransferChannel<'_>,
+    dir: &Path,
+    info: &[u8],
+    reporter: &mut TransferReporter<'_>,
+) -> Result<(), String> {
+    let (name, size) = parse_file_info(info);
+    let path = unique_download_path(dir, &name)?;
+    let mut file = tokio::fs::File::create(&path)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    reporter.start_file(&name, &path, size.unwrap_or(0));
+    match receive_file_data(io, &mut file, reporter).await {
+        Ok(()) => {
+            reporter.finish_file(&path);
+            Ok(())
+        }
+        Err(err) => {
+            // 不保留不完整的文件
+            drop(file);
+            let _ = tokio::fs::remove_file(&path).await;
+            Err(err)
+        }
+    }
+}
+
+// 🟢 作为接收方 (本地 rz)：响应远端 sz 的 ZRQINIT，逐个保存文件到 dir
+pub async fn receive_files(
+    io: &mut TransferChannel<'_>,
+    dir: &Path,
+    reporter: &mut TransferReporter<'_>,
+) -> Result<(), String> {
+    let zrinit = hex_header(ZRINIT, [0, 0, 0, CANFDX | CANOVIO | CANFC32]);
+    loop {
+        let header = read_header(io).await?;
+        match header.frame_type {
+            ZRQINIT => io.write(&zrinit).await?,
+            ZSINIT => {
+                read_subpacket(io, header.crc32).await?;
+                io.write(&hex_header(ZACK, [0; 4])).await?;
+            }
+            ZFILE => match read_subpacket(io, header.crc32).await? {
+                Some((info, _)) => {
+                    receive_file(io, dir, &info, reporter).await?;
+                    io.write(&zrinit).await?;
+                }
+                None => io.write(&hex_header(ZNAK, [0; 4])).await?,
+            },
+            ZFIN => {
+                io.write(&hex_header(ZFIN, [0; 4])).await?;
+                return consume_over_and_out(io).await;
+            }
+            // 0 表示可用空间未知
+            ZFREECNT => io.write(&hex_header(ZACK, [0; 4])).await?,
+            ZCOMMAND => return Err("ZMODEM remote command requests are not supported".to_string()),
+            ZABORT | ZFERR | ZCAN => return Err(remote_aborted()),
+            _ => io.write(&zrinit).await?,
+        }
+    }
+}
+
+struct ReceiverCaps {
+    crc32: bool,
+    // 两次 ZCRCW 确认之间最多发送的字节数
+    window: usize,
+}
+
+async fn wait_receiver_init(io: &mut TransferChannel<'_>) -> Result<ReceiverCaps, String> {
+    for _ in 0..MAX_RETRIES {
+        let header = read_header(io).await?;
+        match header.frame_type {
+            ZRINIT => {
+                let buffer = u16::from_le_bytes([header.data[0], header.data[1]]) as usize;
+                return Ok(ReceiverCaps {
+                    crc32: header.data[3] & CANFC32 != 0,
+                    window: if buffer == 0 {
+                        SEND_WINDOW
+                    } else {
+                        buffer.min(SEND_WINDOW)
+                    },
+                });
+            }
+            ZCHALLENGE => io.write(&hex_header(ZACK, header.data)).await?,
+            ZABORT | ZFERR | ZCAN | ZFIN => return Err(remote_aborted()),
+            _ => io.write(&hex_header(ZRQINIT, [0; 4])).await?,
+        }
+    }
+    Err("ZMODEM receiver did not send ZRINIT".to_string())
+}
+
+async fn file_crc32(path: &Path, length: u64) -> Result<u32, String> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut remaining = if length == 0 { u64::MAX } else { length };
+    while remaining > 0 {
+        let limit = (buffer.len() as u64).min(remaining) as usize;
+        let read = file
+            .read(&mut buffer[..limit])
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        remaining -= read as u64;
+    }
+    Ok(hasher.finalize())
+}
+
+// 发送 ZFILE 并等待接收方给出起始位置；None 表示接收方跳过该文件
+async fn offer_file(
+    io: &mut TransferChannel<'_>,
+    file: &UploadFile,
+    info: &[u8],
+    caps: &ReceiverCaps,
+) -> Result<Option<u64>, String> {
+    for _ in 0..MAX_RETRIES {
+        io.write(&binary_header(ZFILE, [0, 0, 0, ZCBIN], caps.crc32))
+            .await?;
+        io.write(&data_subpacket(info, ZCRCW, caps.crc32)).await?;
+        loop {
+            let header = read_header(io).await?;
+            match header.frame_type {
+                ZRPOS => return Ok(Some(header.position())),
+                ZSKIP => return Ok(None),
+                // 接收方已有同名文件，请求校验值以决定是否续传
+                ZCRC => {
+                    let crc = file_crc32(&file.path, header.position()).await?;
+                    io.write(&hex_header(ZCRC, crc.to_le_bytes())).await?;
+                }
+                ZABORT | ZFERR | ZCAN | ZFIN => return Err(remote_aborted()),
+                // ZRINIT / ZNAK 等：接收方没有收到 ZFILE，重新发送
+                _ => break,
+            }
+        }
+    }
+    Err("ZMODEM receiver did not accept the file".to_string())
+}
+
+// 🟢 以 ZDATA 帧流式发送，每个窗口以 ZCRCW 结束并等待 ZACK；收到 ZRPOS 时回退重发
+async fn send_file_data(
+    io: &mut TransferChannel<'_>,
+    file: &UploadFile,
+    start: u64,
+    caps: &ReceiverCaps,
+    reporter: &mut TransferReporter<'_>,
+) -> Result<(), String> {
+    let mut source = tokio::fs::File::open(&file.path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", file.path.display(), e))?;
+    let mut buffer = vec![0u8; SUBPACKET_LEN];
+    let mut position = start;
+    let mut retries = 0;
+    'frame: loop {
+        source
+            .seek(SeekFrom::Start(position))
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", file.path.display(), e))?;
+        io.write(&binary_header(ZDATA, position_bytes(position), caps.crc32))
+            .await?;
+        let mut since_ack = 0usize;
+        loop {
+            let mut filled = 0;
+            while filled < buffer.len() {
+                let read = source
+                    .read(&mut buffer[filled..])
+                    .await
+                    .map_err(|e| format!("Failed to read {}: {}", file.path.display(), e))?;
+                if read == 0 {
+                    break;
+                }
+                filled += read;
+            }
+            let eof = filled < buffer.len();
+            since_ack += filled;
+            let end = if eof {
+                ZCRCE
+            } else if since_ack >= caps.window {
+                ZCRCW
+            } else {
+                ZCRCG
+            };
+            io.write(&data_subpacket(&buffer[..filled], end, caps.crc32))
+                .await?;
+            position += filled as u64;
+            reporter.progress(position);
+            if end == ZCRCG {
+                continue;
+            }
+
+            if end == ZCRCE {
+                io.write(&binary_header(ZEOF, position_bytes(position), caps.crc32))
+                    .await?;
+            }
+            loop {
+                let header = read_header(io).await?;
+                match header.frame_type {
+                    ZACK if end == ZCRCW => continue 'frame,
+                    ZRINIT if end == ZCRCE => return Ok(()),
+                    ZRPOS => {
+                        retries += 1;
+                        if retries > MAX_RETRIES {
+                            return Err("ZMODEM too many retransmission requests".to_string());
+                        }
+                        position = header.position();
+                        continue 'frame;
+                    }
+                    ZSKIP => return Ok(()),
+                    ZABORT | ZFERR | ZCAN | ZFIN => return Err(remote_aborted()),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+// 🟢 作为发送方 (本地 sz)：远端 rz 的 ZRINIT 已在缓冲开头，依次发送文件后以 ZFIN/OO 结束
+pub async fn send_files(
+    io: &mut TransferChannel<'_>,
+    files: &[UploadFile],
+    reporter: &mut TransferReporter<'_>,
+) -> Result<(), String> {
+    let caps = wait_receiver_init(io).await?;
+    let mut remaining_bytes: u64 = files.iter().map(|file| file.size).sum();
+    for (index, file) in files.iter().enumerate() {
+        let mut info = file.name.as_bytes().to_vec();
+        info.push(0);
+        info.extend_from_slice(
+            format!(
+                "{} {:o} 0 0 {} {}",
+                file.size,
+                file.modified,
+                files.len() - index,
+                remaining_bytes
+            )
+            .as_bytes(),
+        );
+        info.push(0);
+        remaining_bytes = remaining_bytes.saturating_sub(file.size);
+
+        reporter.start_file(&file.name, &file.path, file.size);
+        let Some(start) = offer_file(io, file, &info, &caps).await? else {
+            continue;
+        };
+        send_file_data(io, file, start, &caps, reporter).await?;
+        reporter.finish_file(&file.path);
+    }
+
+    for _ in 0..MAX_RETRIES {
+        io.write(&hex_header(ZFIN, [0; 4])).await?;
+        let header = read_header(io).await?;
+        if header.frame_type == ZFIN {
+            return io.write(b"OO").await;
+        }
+    }
+    Err("ZMODEM receiver did not acknowledge ZFIN".to_string())
+}
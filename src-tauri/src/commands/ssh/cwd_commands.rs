@@ -0,0 +1,89 @@
+use serde::Serialize;
+use tauri::State;
+
+use crate::utils::ssh_log::{self, SshLogRecord};
+
+use super::runtime::queue_shell_write;
+use super::state::{SshConnection, SshState};
+use super::utils::shell_quote;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalCwdInfo {
+    // 未启用 shell 集成或尚未收到 OSC 7 时为空
+    pub cwd: Option<String>,
+    pub sync_enabled: bool,
+}
+
+fn get_connection(state: &SshState, id: &str) -> Result<SshConnection, String> {
+    let map = state.sessions.lock().map_err(|e| e.to_string())?;
+    let conn = map
+        .get(id)
+        .cloned()
+        .ok_or_else(|| "SSH connection not active".to_string())?;
+    conn.touch_client_heartbeat();
+    Ok(conn)
+}
+
+#[tauri::command]
+pub fn get_terminal_cwd(state: State<'_, SshState>, id: String) -> Result<TerminalCwdInfo, String> {
+    let conn = get_connection(&state, &id)?;
+    Ok(TerminalCwdInfo {
+        cwd: conn.cwd(),
+        sync_enabled: conn.cwd_sync_enabled(),
+    })
+}
+
+// 🟢 开关终端与 SFTP 面板的目录联动；开启后 list_ssh_files 传空路径时列出 shell 当前目录
+#[tauri::command]
+pub fn set_terminal_cwd_sync(
+    state: State<'_, SshState>,
+    id: String,
+    enabled: bool,
+) -> Result<TerminalCwdInfo, String> {
+    let conn = get_connection(&state, &id)?;
+    conn.set_cwd_sync(enabled);
+    Ok(TerminalCwdInfo {
+        cwd: conn.cwd(),
+        sync_enabled: enabled,
+    })
+}
+
+// 🟢 SFTP 面板 "在此打开终端"：向 shell 输入 cd 命令，前导空格避免进入远端历史
+#[tauri::command]
+pub async fn open_terminal_here(
+    state: State<'_, SshState>,
+    id: String,
+    path: String,
+) -> Result<(), String> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err("Path is empty".to_string());
+    }
+    if path.contains(['\r', '\n']) {
+        return Err("Path must not contain line breaks".to_string());
+    }
+    let conn = get_connection(&state, &id)?;
+    if !conn.shell_is_active() {
+        return Err("SSH shell not active".to_string());
+    }
+    // 前台程序运行时 cd 会被当成它的输入
+    conn.ensure_shell_idle()?;
+
+    queue_shell_write(
+        conn.shell_write_tx.clone(),
+        format!(" cd -- {}\r", shell_quote(path)),
+    )
+    .await?;
+    ssh_log::debug(
+        SshLogRecord::new(
+            "ssh.shell",
+            "cwd_change_requested",
+            "Changed terminal directory from file browser",
+        )
+        .session_id(id)
+        .server_id(conn.config.id.clone())
+        .field("remote_path", ssh_log::mask_path(path)),
+    );
+    Ok(())
+}
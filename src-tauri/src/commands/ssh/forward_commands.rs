@@ -0,0 +1,232 @@
+use chrono::Utc;
+use sqlx::{Pool, Sqlite};
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+use crate::models::{ForwardType, PortForwardRule};
+use crate::state::AppState;
+use crate::utils::ssh_log::{self, SshLogRecord};
+
+use super::forwarding::{self, PortForwardStatus};
+use super::state::{SshConnection, SshState};
+
+const PORT_FORWARD_COLUMNS: &str = "id, server_id, name, forward_type, bind_address, bind_port, \
+     target_host, target_port, auto_start, created_at, updated_at";
+
+async fn load_port_forward(pool: &Pool<Sqlite>, id: &str) -> Result<PortForwardRule, String> {
+    sqlx::query_as::<_, PortForwardRule>(&format!(
+        "SELECT {} FROM port_forwards WHERE id = ?",
+        PORT_FORWARD_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Port forward {} not found", id))
+}
+
+// 连接建立时需要自动启动的规则
+pub async fn load_auto_start_forwards(
+    pool: &Pool<Sqlite>,
+    server_id: &str,
+) -> Result<Vec<PortForwardRule>, String> {
+    sqlx::query_as::<_, PortForwardRule>(&format!(
+        "SELECT {} FROM port_forwards WHERE server_id = ? AND auto_start = 1 ORDER BY created_at ASC",
+        PORT_FORWARD_COLUMNS
+    ))
+    .bind(server_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+fn get_connection(state: &SshState, session_id: &str) -> Result<SshConnection, String> {
+    let map = match state.sessions.lock() {
+        Ok(g) => g,
+        Err(p) => p.into_inner(),
+    };
+    map.get(session_id)
+        .cloned()
+        .ok_or_else(|| "SSH connection not active".to_string())
+}
+
+#[tauri::command]
+pub async fn list_port_forwards(
+    app_state: State<'_, AppState>,
+    server_id: String,
+) -> Result<Vec<PortForwardRule>, String> {
+    sqlx::query_as::<_, PortForwardRule>(&format!(
+        "SELECT {} FROM port_forwards WHERE server_id = ? ORDER BY created_at ASC",
+        PORT_FORWARD_COLUMNS
+    ))
+    .bind(server_id)
+    .fetch_all(&app_state.db)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn save_port_forward(
+    app_state: State<'_, AppState>,
+    mut rule: PortForwardRule,
+) -> Result<PortForwardRule, String> {
+    // 动态转发 (SOCKS5) 的目标由客户端逐个请求决定
+    if rule.forward_type == ForwardType::Dynamic {
+        rule.target_host = String::new();
+        rule.target_port = 0;
+    } else {
+        if rule.target_host.trim().is_empty() {
+            return Err("Target host is required".to_string());
+        }
+        if rule.target_port == 0 {
+            return Err("Target port must be between 1 and 65535".to_string());
+        }
+    }
+    if rule.bind_address.trim().is_empty() {
+        rule.bind_address = "127.0.0.1".to_string();
+    }
+
+    let now = Utc::now().timestamp_millis();
+    if rule.id.is_empty() {
+        rule.id = Uuid::new_v4().to_string();
+    }
+    if rule.created_at == 0 {
+        rule.created_at = now;
+    }
+    rule.updated_at = now;
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO port_forwards (
+            id, server_id, name, forward_type, bind_address, bind_port,
+            target_host, target_port, auto_start, created_at, updated_at
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&rule.id)
+    .bind(&rule.server_id)
+    .bind(&rule.name)
+    .bind(rule.forward_type)
+    .bind(&rule.bind_address)
+    .bind(rule.bind_port)
+    .bind(&rule.target_host)
+    .bind(rule.target_port)
+    .bind(rule.auto_start)
+    .bind(rule.created_at)
+    .bind(rule.updated_at)
+    .execute(&app_state.db)
+    .await
+    .map_err(|e| format!("保存端口转发失败: {}", e))?;
+
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn delete_port_forward(
+    app_state: State<'_, AppState>,
+    ssh_state: State<'_, SshState>,
+    id: String,
+) -> Result<(), String> {
+    // 先停止所有会话上正在运行的该规则
+    let connections: Vec<SshConnection> = match ssh_state.sessions.lock() {
+        Ok(map) => map.values().cloned().collect(),
+        Err(p) => p.into_inner().values().cloned().collect(),
+    };
+    for conn in connections {
+        let removed = match conn.port_forwards.lock() {
+            Ok(mut forwards) => forwards.remove(&id),
+            Err(p) => p.into_inner().remove(&id),
+        };
+        if let Some(forward) = removed {
+            forward.stop();
+        }
+    }
+
+    sqlx::query("DELETE FROM port_forwards WHERE id = ?")
+        .bind(id)
+        .execute(&app_state.db)
+        .await
+        .map_err(|e| format!("删除失败: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn start_port_forward(
+    app: AppHandle,
+    app_state: State<'_, AppState>,
+    ssh_state: State<'_, SshState>,
+    session_id: String,
+    forward_id: String,
+) -> Result<PortForwardStatus, String> {
+    let rule = load_port_forward(&app_state.db, &forward_id).await?;
+    let conn = get_connection(&ssh_state, &session_id)?;
+    if rule.server_id != conn.config.id {
+        return Err("Port forward belongs to a different server".to_string());
+    }
+
+    forwarding::start_port_forward(&app, &conn, &session_id, rule)
+        .await
+        .inspect_err(|err| {
+            ssh_log::warn(
+                SshLogRecord::new("ssh.forward", "start_failed", "Failed to start port forward")
+                    .session_id(session_id.clone())
+                    .field("forward_id", forward_id.clone())
+                    .field("error", err.clone()),
+            );
+        })
+}
+
+#[tauri::command]
+pub async fn stop_port_forward(
+    ssh_state: State<'_, SshState>,
+    session_id: String,
+    forward_id: String,
+) -> Result<(), String> {
+    let conn = get_connection(&ssh_state, &session_id)?;
+    let removed = match conn.port_forwards.lock() {
+        Ok(mut forwards) => forwards.remove(&forward_id),
+        Err(p) => p.into_inner().remove(&forward_id),
+    };
+    let forward = removed.ok_or_else(|| "Port forward is not running".to_string())?;
+    forward.stop();
+
+    ssh_log::info(
+        SshLogRecord::new("ssh.forward", "stopped", "Port forward stopped")
+            .session_id(session_id)
+            .server_id(forward.rule.server_id.clone())
+            .field("forward_id", forward_id),
+    );
+    Ok(())
+}
+
+// 🟢 运行中的转发及其连接数/流量统计；session_id 为空时返回全部会话
+#[tauri::command]
+pub async fn list_active_port_forwards(
+    ssh_state: State<'_, SshState>,
+    session_id: Option<String>,
+) -> Result<Vec<PortForwardStatus>, String> {
+    let connections: Vec<(String, SshConnection)> = match ssh_state.sessions.lock() {
+        Ok(map) => map
+            .iter()
+            .map(|(id, conn)| (id.clone(), conn.clone()))
+            .collect(),
+        Err(p) => p
+            .into_inner()
+            .iter()
+            .map(|(id, conn)| (id.clone(), conn.clone()))
+            .collect(),
+    };
+
+    let mut statuses = Vec::new();
+    for (id, conn) in connections {
+        if session_id.as_ref().is_some_and(|wanted| wanted != &id) {
+            continue;
+        }
+        let forwards = match conn.port_forwards.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        statuses.extend(forwards.values().map(|forward| forward.status(&id)));
+    }
+    statuses.sort_by_key(|status| status.started_at);
+    Ok(statuses)
+}
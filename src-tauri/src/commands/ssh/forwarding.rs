@@ -0,0 +1,718 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+use crate::models::{ForwardType, PortForwardRule};
+use crate::utils::ssh_log::{self, SshLogRecord};
+
+use super::core::{
+    accept_socks5_request, send_socks5_reply, SOCKS5_REPLY_GENERAL_FAILURE,
+    SOCKS5_REPLY_HOST_UNREACHABLE, SOCKS5_REPLY_SUCCEEDED,
+};
+use super::state::{get_ssh_session_if_instance, SharedSshSession, SshConnection, SshTransport};
+
+const FORWARD_BUFFER_SIZE: usize = 32 * 1024;
+const SOCKS_HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Default)]
+pub struct ForwardStats {
+    pub active_connections: AtomicU64,
+    pub total_connections: AtomicU64,
+    // 通道打开失败 / 目标不可达 / SOCKS 握手失败
+    pub failed_connections: AtomicU64,
+    // 本地 -> 远端
+    pub bytes_sent: AtomicU64,
+    // 远端 -> 本地
+    pub bytes_received: AtomicU64,
+}
+
+// 远端监听 (tcpip-forward) 收到连接时转发到的本地目标
+#[derive(Clone)]
+pub struct RemoteForwardTarget {
+    pub forward_id: String,
+    pub server_id: String,
+    pub target_host: String,
+    pub target_port: u16,
+    pub stats: Arc<ForwardStats>,
+    pub shutdown_rx: watch::Receiver<bool>,
+}
+
+// 按 (传输 id, 远端端口) 登记，供 PiTermClientHandler 处理 forwarded-tcpip 通道；
+// 复制的标签页共享同一传输，源标签页关闭后转发仍可被找到
+pub type RemoteForwardMap = Arc<Mutex<HashMap<(u64, u32), RemoteForwardTarget>>>;
+
+#[derive(Default)]
+pub struct RemoteForwardRegistry {
+    pub targets: RemoteForwardMap,
+}
+
+struct RemoteForwardHandle {
+    session: SharedSshSession,
+    targets: RemoteForwardMap,
+    transport_id: u64,
+    address: String,
+    port: u32,
+}
+
+// 运行中的转发；drop 或 stop() 后监听任务与所有连接一起退出
+pub struct ActivePortForward {
+    pub rule: PortForwardRule,
+    pub bind_address: String,
+    pub bind_port: u16,
+    pub stats: Arc<ForwardStats>,
+    pub started_at: i64,
+    shutdown_tx: watch::Sender<bool>,
+    remote: Option<RemoteForwardHandle>,
+}
+
+impl ActivePortForward {
+    pub fn stop(&self) {
+        let _ = self.shutdown_tx.send(true);
+
+        // 远程转发：注销本地目标并请求服务端关闭监听
+        if let Some(remote) = &self.remote {
+            match remote.targets.lock() {
+                Ok(mut targets) => targets.remove(&(remote.transport_id, remote.port)),
+                Err(p) => p.into_inner().remove(&(remote.transport_id, remote.port)),
+            };
+            let session = remote.session.clone();
+            let address = remote.address.clone();
+            let port = remote.port;
+            tauri::async_runtime::spawn(async move {
+                let handle = session.read().await;
+                if !handle.is_closed() {
+                    let _ = handle.cancel_tcpip_forward(address, port).await;
+                }
+            });
+        }
+    }
+
+    pub fn status(&self, session_id: &str) -> PortForwardStatus {
+        PortForwardStatus {
+            session_id: session_id.to_string(),
+            forward_id: self.rule.id.clone(),
+            name: self.rule.name.clone(),
+            forward_type: self.rule.forward_type,
+            bind_address: self.bind_address.clone(),
+            bind_port: self.bind_port,
+            target_host: self.rule.target_host.clone(),
+            target_port: self.rule.target_port,
+            active_connections: self.stats.active_connections.load(Ordering::Relaxed),
+            total_connections: self.stats.total_connections.load(Ordering::Relaxed),
+            failed_connections: self.stats.failed_connections.load(Ordering::Relaxed),
+            bytes_sent: self.stats.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.stats.bytes_received.load(Ordering::Relaxed),
+            started_at: self.started_at,
+        }
+    }
+}
+
+pub type PortForwardMap = Arc<Mutex<HashMap<String, ActivePortForward>>>;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortForwardStatus {
+    pub session_id: String,
+    pub forward_id: String,
+    pub name: Option<String>,
+    pub forward_type: ForwardType,
+    pub bind_address: String,
+    pub bind_port: u16,
+    pub target_host: String,
+    pub target_port: u16,
+    pub active_connections: u64,
+    pub total_connections: u64,
+    pub failed_connections: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub started_at: i64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortForwardErrorEvent {
+    pub session_id: String,
+    pub forward_id: String,
+    pub error: String,
+}
+
+pub fn format_bind_addr(address: &str, port: u16) -> String {
+    if address.contains(':') && !address.starts_with('[') {
+        format!("[{}]:{}", address, port)
+    } else {
+        format!("{}:{}", address, port)
+    }
+}
+
+// 端口冲突等绑定错误转换为带错误码的提示
+pub fn bind_error_message(addr: &str, err: &std::io::Error) -> String {
+    match err.kind() {
+        std::io::ErrorKind::AddrInUse => format!(
+            "PORT_IN_USE: {} is already in use by another process or forward",
+            addr
+        ),
+        std::io::ErrorKind::PermissionDenied => format!(
+            "PORT_PERMISSION_DENIED: Listening on {} requires elevated privileges",
+            addr
+        ),
+        std::io::ErrorKind::AddrNotAvailable => format!(
+            "BIND_ADDRESS_UNAVAILABLE: {} is not an address of this machine",
+            addr
+        ),
+        _ => format!("Failed to listen on {}: {}", addr, err),
+    }
+}
+
+fn forward_log(record: SshLogRecord, session_id: &str, rule: &PortForwardRule) -> SshLogRecord {
+    record
+        .session_id(session_id.to_string())
+        .server_id(rule.server_id.clone())
+        .field("forward_id", rule.id.clone())
+        .field("forward_type", format!("{:?}", rule.forward_type))
+        .field("target", format!("{}:{}", rule.target_host, rule.target_port))
+}
+
+async fn copy_counted<R, W>(mut reader: R, mut writer: W, counter: &AtomicU64)
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = vec![0u8; FORWARD_BUFFER_SIZE];
+    loop {
+        match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                if writer.write_all(&buf[..n]).await.is_err() {
+                    break;
+                }
+                counter.fetch_add(n as u64, Ordering::Relaxed);
+            }
+        }
+    }
+    let _ = writer.shutdown().await;
+}
+
+// 双向转发，直到两端都关闭或转发被停止
+pub async fn pump_streams<L, R>(
+    local: L,
+    remote: R,
+    stats: &ForwardStats,
+    mut shutdown_rx: watch::Receiver<bool>,
+) where
+    L: AsyncRead + AsyncWrite + Unpin,
+    R: AsyncRead + AsyncWrite + Unpin,
+{
+    let (local_read, local_write) = tokio::io::split(local);
+    let (remote_read, remote_write) = tokio::io::split(remote);
+
+    stats.active_connections.fetch_add(1, Ordering::Relaxed);
+    stats.total_connections.fetch_add(1, Ordering::Relaxed);
+    tokio::select! {
+        _ = async {
+            tokio::join!(
+                copy_counted(local_read, remote_write, &stats.bytes_sent),
+                copy_counted(remote_read, local_write, &stats.bytes_received),
+            )
+        } => {}
+        _ = shutdown_rx.changed() => {}
+    }
+    stats.active_connections.fetch_sub(1, Ordering::Relaxed);
+}
+
+async fn handle_local_connection(
+    session: SharedSshSession,
+    socket: TcpStream,
+    peer: SocketAddr,
+    rule: PortForwardRule,
+    stats: Arc<ForwardStats>,
+    shutdown_rx: watch::Receiver<bool>,
+    session_id: String,
+) {
+    let channel = match session
+        .read()
+        .await
+        .channel_open_direct_tcpip(
+            rule.target_host.clone(),
+            rule.target_port as u32,
+            peer.ip().to_string(),
+            peer.port() as u32,
+        )
+        .await
+    {
+        Ok(channel) => channel,
+        Err(err) => {
+            stats.failed_connections.fetch_add(1, Ordering::Relaxed);
+            ssh_log::warn(forward_log(
+                SshLogRecord::new(
+                    "ssh.forward",
+                    "channel_open_failed",
+                    "Failed to open direct-tcpip channel for forwarded connection",
+                )
+                .field("peer", peer.to_string())
+                .field("error", err.to_string()),
+                &session_id,
+                &rule,
+            ));
+            return;
+        }
+    };
+
+    let _ = socket.set_nodelay(true);
+    pump_streams(socket, channel.into_stream(), &stats, shutdown_rx).await;
+}
+
+// 🟢 动态转发：先完成 SOCKS5 握手，再按请求的目标打开 direct-tcpip
+async fn handle_dynamic_connection(
+    session: SharedSshSession,
+    mut socket: TcpStream,
+    peer: SocketAddr,
+    rule: PortForwardRule,
+    stats: Arc<ForwardStats>,
+    shutdown_rx: watch::Receiver<bool>,
+    session_id: String,
+) {
+    let target =
+        match tokio::time::timeout(SOCKS_HANDSHAKE_TIMEOUT, accept_socks5_request(&mut socket))
+            .await
+        {
+            Ok(Ok(target)) => target,
+            Ok(Err(err)) => {
+                stats.failed_connections.fetch_add(1, Ordering::Relaxed);
+                ssh_log::debug(forward_log(
+                    SshLogRecord::new(
+                        "ssh.forward",
+                        "socks_handshake_failed",
+                        "SOCKS5 handshake failed on dynamic forward",
+                    )
+                    .field("peer", peer.to_string())
+                    .field("error", err),
+                    &session_id,
+                    &rule,
+                ));
+                return;
+            }
+            Err(_) => {
+                stats.failed_connections.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        };
+
+    let opened = {
+        let handle = session.read().await;
+        if handle.is_closed() {
+            None
+        } else {
+            Some(
+                handle
+                    .channel_open_direct_tcpip(
+                        target.host.clone(),
+                        target.port as u32,
+                        peer.ip().to_string(),
+                        peer.port() as u32,
+                    )
+                    .await,
+            )
+        }
+    };
+    let channel = match opened {
+        Some(Ok(channel)) => channel,
+        failed => {
+            stats.failed_connections.fetch_add(1, Ordering::Relaxed);
+            let code = if failed.is_none() {
+                SOCKS5_REPLY_GENERAL_FAILURE
+            } else {
+                SOCKS5_REPLY_HOST_UNREACHABLE
+            };
+            let _ = send_socks5_reply(&mut socket, code).await;
+            ssh_log::warn(forward_log(
+                SshLogRecord::new(
+                    "ssh.forward",
+                    "socks_connect_failed",
+                    "Failed to open direct-tcpip channel for SOCKS5 request",
+                )
+                .field("peer", peer.to_string())
+                .field("destination", format!("{}:{}", target.host, target.port))
+                .field(
+                    "error",
+                    match failed {
+                        Some(Err(err)) => err.to_string(),
+                        _ => "SSH session closed".to_string(),
+                    },
+                ),
+                &session_id,
+                &rule,
+            ));
+            return;
+        }
+    };
+
+    if send_socks5_reply(&mut socket, SOCKS5_REPLY_SUCCEEDED)
+        .await
+        .is_err()
+    {
+        let _ = channel.close().await;
+        return;
+    }
+    let _ = socket.set_nodelay(true);
+    pump_streams(socket, channel.into_stream(), &stats, shutdown_rx).await;
+}
+
+async fn run_local_listener(
+    listener: TcpListener,
+    session: SharedSshSession,
+    rule: PortForwardRule,
+    stats: Arc<ForwardStats>,
+    mut shutdown_rx: watch::Receiver<bool>,
+    session_id: String,
+) {
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => break,
+            accepted = listener.accept() => match accepted {
+                Ok((socket, peer)) if rule.forward_type == ForwardType::Dynamic => {
+                    tokio::spawn(handle_dynamic_connection(
+                        session.clone(),
+                        socket,
+                        peer,
+                        rule.clone(),
+                        stats.clone(),
+                        shutdown_rx.clone(),
+                        session_id.clone(),
+                    ));
+                }
+                Ok((socket, peer)) => {
+                    tokio::spawn(handle_local_connection(
+                        session.clone(),
+                        socket,
+                        peer,
+                        rule.clone(),
+                        stats.clone(),
+                        shutdown_rx.clone(),
+                        session_id.clone(),
+                    ));
+                }
+                Err(err) => {
+                    ssh_log::warn(forward_log(
+                        SshLogRecord::new(
+                            "ssh.forward",
+                            "accept_failed",
+                            "Local forward listener failed to accept a connection",
+                        )
+                        .field("error", err.to_string()),
+                        &session_id,
+                        &rule,
+                    ));
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                }
+            }
+        }
+    }
+
+    ssh_log::info(forward_log(
+        SshLogRecord::new("ssh.forward", "listener_stopped", "Port forward listener stopped"),
+        &session_id,
+        &rule,
+    ));
+}
+
+async fn start_local_forward(
+    session: SharedSshSession,
+    session_id: &str,
+    rule: PortForwardRule,
+) -> Result<ActivePortForward, String> {
+    let addr = format_bind_addr(&rule.bind_address, rule.bind_port);
+    let listener = TcpListener::bind(&addr)
+        .await
+        .map_err(|e| bind_error_message(&addr, &e))?;
+    let bound_addr = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read listener address: {}", e))?;
+    if rule.forward_type == ForwardType::Dynamic && !bound_addr.ip().is_loopback() {
+        ssh_log::warn(forward_log(
+            SshLogRecord::new(
+                "ssh.forward",
+                "socks_lan_exposed",
+                "Dynamic forward listens beyond loopback; LAN clients can use it without authentication",
+            )
+            .field("bind", bound_addr.to_string()),
+            session_id,
+            &rule,
+        ));
+    }
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let stats = Arc::new(ForwardStats::default());
+    tokio::spawn(run_local_listener(
+        listener,
+        session,
+        rule.clone(),
+        stats.clone(),
+        shutdown_rx,
+        session_id.to_string(),
+    ));
+
+    Ok(ActivePortForward {
+        rule,
+        bind_address: bound_addr.ip().to_string(),
+        bind_port: bound_addr.port(),
+        stats,
+        started_at: Utc::now().timestamp_millis(),
+        shutdown_tx,
+        remote: None,
+    })
+}
+
+// 远程转发 (ssh -R)：请求服务端监听，连接经 forwarded-tcpip 通道回到本地目标
+async fn start_remote_forward(
+    app: &AppHandle,
+    transport: &SshTransport,
+    rule: PortForwardRule,
+) -> Result<ActivePortForward, String> {
+    let session = transport.session.clone();
+    let address = rule.bind_address.clone();
+    let requested = rule.bind_port as u32;
+    let allocated = session
+        .write()
+        .await
+        .tcpip_forward(address.clone(), requested)
+        .await
+        .map_err(|e| {
+            format!(
+                "REMOTE_FORWARD_REJECTED: Server refused to listen on {}: {}",
+                format_bind_addr(&address, rule.bind_port),
+                e
+            )
+        })?;
+    // 指定端口时服务端不回传端口号
+    let port = if allocated == 0 { requested } else { allocated };
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let stats = Arc::new(ForwardStats::default());
+    let targets = app.state::<RemoteForwardRegistry>().targets.clone();
+    {
+        let mut map = match targets.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        map.insert(
+            (transport.id, port),
+            RemoteForwardTarget {
+                forward_id: rule.id.clone(),
+                server_id: rule.server_id.clone(),
+                target_host: rule.target_host.clone(),
+                target_port: rule.target_port,
+                stats: stats.clone(),
+                shutdown_rx,
+            },
+        );
+    }
+
+    Ok(ActivePortForward {
+        bind_address: address.clone(),
+        bind_port: port as u16,
+        rule,
+        stats,
+        started_at: Utc::now().timestamp_millis(),
+        shutdown_tx,
+        remote: Some(RemoteForwardHandle {
+            session,
+            targets,
+            transport_id: transport.id,
+            address,
+            port,
+        }),
+    })
+}
+
+// 服务端打开 forwarded-tcpip 通道：连接登记的本地目标并双向转发
+pub fn bridge_forwarded_channel(
+    app: &AppHandle,
+    transport_id: u64,
+    session_id: &str,
+    channel: russh::Channel<russh::client::Msg>,
+    connected_port: u32,
+    originator: String,
+) {
+    let target = {
+        let registry = app.state::<RemoteForwardRegistry>();
+        let targets = match registry.targets.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        targets.get(&(transport_id, connected_port)).cloned()
+    };
+
+    let session_id = session_id.to_string();
+    tokio::spawn(async move {
+        let Some(target) = target else {
+            ssh_log::warn(
+                SshLogRecord::new(
+                    "ssh.forward",
+                    "forwarded_channel_unknown",
+                    "Rejected forwarded-tcpip channel for an unregistered remote port",
+                )
+                .session_id(session_id)
+                .field("connected_port", connected_port)
+                .field("originator", originator),
+            );
+            let _ = channel.close().await;
+            return;
+        };
+
+        let addr = format_bind_addr(&target.target_host, target.target_port);
+        let socket = match TcpStream::connect(&addr).await {
+            Ok(socket) => socket,
+            Err(err) => {
+                target.stats.failed_connections.fetch_add(1, Ordering::Relaxed);
+                ssh_log::warn(
+                    SshLogRecord::new(
+                        "ssh.forward",
+                        "local_target_unreachable",
+                        "Failed to connect remote forward to its local target",
+                    )
+                    .session_id(session_id)
+                    .server_id(target.server_id)
+                    .field("forward_id", target.forward_id)
+                    .field("target", addr)
+                    .field("originator", originator)
+                    .field("error", err.to_string()),
+                );
+                let _ = channel.close().await;
+                return;
+            }
+        };
+
+        let _ = socket.set_nodelay(true);
+        pump_streams(socket, channel.into_stream(), &target.stats, target.shutdown_rx).await;
+    });
+}
+
+// 在指定连接上启动转发规则
+pub async fn start_port_forward(
+    app: &AppHandle,
+    conn: &SshConnection,
+    session_id: &str,
+    rule: PortForwardRule,
+) -> Result<PortForwardStatus, String> {
+    {
+        let forwards = match conn.port_forwards.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        if let Some(active) = forwards.get(&rule.id) {
+            return Ok(active.status(session_id));
+        }
+    }
+
+    let active = match rule.forward_type {
+        ForwardType::Local | ForwardType::Dynamic => {
+            start_local_forward(conn.transport.session.clone(), session_id, rule.clone()).await?
+        }
+        ForwardType::Remote => {
+            start_remote_forward(app, &conn.transport, rule.clone()).await?
+        }
+    };
+    let status = active.status(session_id);
+
+    let previous = {
+        let mut forwards = match conn.port_forwards.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        forwards.insert(rule.id.clone(), active)
+    };
+    if let Some(previous) = previous {
+        previous.stop();
+    }
+
+    ssh_log::info(forward_log(
+        SshLogRecord::new("ssh.forward", "started", "Port forward started")
+            .field("bind", format_bind_addr(&status.bind_address, status.bind_port)),
+        session_id,
+        &rule,
+    ));
+    Ok(status)
+}
+
+// 同一服务器的其他标签页已在运行该规则时跳过，避免每开一个标签页都重复绑定同一端口
+fn forward_active_elsewhere(
+    sessions: &Mutex<HashMap<String, SshConnection>>,
+    session_id: &str,
+    rule: &PortForwardRule,
+) -> bool {
+    let map = match sessions.lock() {
+        Ok(g) => g,
+        Err(p) => p.into_inner(),
+    };
+    map.iter()
+        .filter(|(id, conn)| id.as_str() != session_id && conn.config.id == rule.server_id)
+        .any(|(_, conn)| {
+            let forwards = match conn.port_forwards.lock() {
+                Ok(g) => g,
+                Err(p) => p.into_inner(),
+            };
+            forwards.contains_key(&rule.id)
+        })
+}
+
+// 连接建立 (或自动重连) 后在后台启动转发，失败通过事件通知前端
+pub fn spawn_port_forward_restore(
+    app: AppHandle,
+    sessions: Arc<Mutex<HashMap<String, SshConnection>>>,
+    session_id: String,
+    instance_id: u64,
+    rules: Vec<PortForwardRule>,
+) {
+    if rules.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        for rule in rules {
+            let Some(conn) = get_ssh_session_if_instance(&sessions, &session_id, instance_id)
+            else {
+                return;
+            };
+            if forward_active_elsewhere(&sessions, &session_id, &rule) {
+                ssh_log::debug(forward_log(
+                    SshLogRecord::new(
+                        "ssh.forward",
+                        "restore_skipped",
+                        "Port forward is already running on another session of this server",
+                    ),
+                    &session_id,
+                    &rule,
+                ));
+                continue;
+            }
+            if let Err(error) = start_port_forward(&app, &conn, &session_id, rule.clone()).await {
+                ssh_log::warn(forward_log(
+                    SshLogRecord::new(
+                        "ssh.forward",
+                        "restore_failed",
+                        "Failed to start port forward after connect",
+                    )
+                    .field("error", error.clone()),
+                    &session_id,
+                    &rule,
+                ));
+                let _ = app.emit(
+                    "port-forward-error",
+                    PortForwardErrorEvent {
+                        session_id: session_id.clone(),
+                        forward_id: rule.id.clone(),
+                        error,
+                    },
+                );
+            }
+        }
+    });
+}
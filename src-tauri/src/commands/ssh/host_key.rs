@@ -1,15 +1,155 @@
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
 use std::fs::OpenOptions;
 use std::io::{BufRead, BufReader, Write};
 use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 
+type HmacSha1 = Hmac<Sha1>;
+
+const HASHED_HOST_MAGIC: &str = "|1|";
+const HASHED_HOST_SALT_LEN: usize = 20;
+
 // Status of host key check
 #[derive(Debug, PartialEq, Eq, Clone, serde::Serialize)]
 pub enum HostKeyCheckStatus {
     Match,
     Mismatch,
     NotFound,
+    /// 密钥被 @revoked 标记吊销，任何策略下都不能信任
+    Revoked,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum KnownHostMarker {
+    CertAuthority,
+    Revoked,
+}
+
+struct KnownHostEntry<'a> {
+    marker: Option<KnownHostMarker>,
+    hosts: &'a str,
+    key_type: &'a str,
+    key_base64: &'a str,
+}
+
+// 解析 known_hosts 单行: [@marker] hosts keytype base64 [comment]
+fn parse_known_host_line(line: &str) -> Option<KnownHostEntry<'_>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let mut parts = line.split_whitespace();
+    let mut first = parts.next()?;
+    let marker = if first.starts_with('@') {
+        let marker = match first {
+            "@cert-authority" => KnownHostMarker::CertAuthority,
+            "@revoked" => KnownHostMarker::Revoked,
+            // 未知标记整行忽略，与 OpenSSH 行为一致
+            _ => return None,
+        };
+        first = parts.next()?;
+        Some(marker)
+    } else {
+        None
+    };
+
+    Some(KnownHostEntry {
+        marker,
+        hosts: first,
+        key_type: parts.next()?,
+        key_base64: parts.next()?,
+    })
+}
+
+// known_hosts 中的主机名形式：22 端口直接写主机，其它端口写成 [host]:port
+fn known_hosts_target(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+fn hash_host_name(salt: &[u8], target: &str) -> Option<Vec<u8>> {
+    let mut mac = HmacSha1::new_from_slice(salt).ok()?;
+    mac.update(target.as_bytes());
+    Some(mac.finalize().into_bytes().to_vec())
+}
+
+// 匹配 |1|salt|hash 形式的哈希主机 (HMAC-SHA1, 以 salt 为密钥)
+fn hashed_host_matches(pattern: &str, target: &str) -> bool {
+    let Some(rest) = pattern.strip_prefix(HASHED_HOST_MAGIC) else {
+        return false;
+    };
+    let Some((salt_b64, hash_b64)) = rest.split_once('|') else {
+        return false;
+    };
+    let (Ok(salt), Ok(expected)) = (BASE64.decode(salt_b64), BASE64.decode(hash_b64)) else {
+        return false;
+    };
+    hash_host_name(&salt, target).is_some_and(|actual| actual == expected)
+}
+
+// OpenSSH 风格通配符：* 匹配任意长度，? 匹配单个字符，主机名不区分大小写
+fn wildcard_matches(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn host_pattern_matches(pattern: &str, target: &str) -> bool {
+    if pattern.starts_with(HASHED_HOST_MAGIC) {
+        return hashed_host_matches(pattern, target);
+    }
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let target: Vec<char> = target.to_lowercase().chars().collect();
+    wildcard_matches(&pattern, &target)
+}
+
+// 逗号分隔的主机列表：任一 !pattern 命中则整行不匹配
+fn hosts_field_matches(hosts: &str, target: &str) -> bool {
+    let mut matched = false;
+    for pattern in hosts.split(',').filter(|p| !p.is_empty()) {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        if host_pattern_matches(pattern, target) {
+            if negated {
+                return false;
+            }
+            matched = true;
+        }
+    }
+    matched
+}
+
+fn entry_key_matches(entry: &KnownHostEntry<'_>, key_type: &str, host_key: &[u8]) -> bool {
+    entry.key_type == key_type
+        && BASE64
+            .decode(entry.key_base64)
+            .is_ok_and(|decoded| decoded == host_key)
 }
 
 // 辅助函数：获取 known_hosts 路径
@@ -20,6 +160,24 @@ pub fn get_known_hosts_path(app: &AppHandle) -> Option<PathBuf> {
         .map(|p| p.join(".ssh").join("known_hosts"))
 }
 
+// 当前 known_hosts 是否已使用哈希主机名 (HashKnownHosts)，新条目沿用相同格式
+pub fn known_hosts_uses_hashing(app: &AppHandle) -> bool {
+    let Some(path) = get_known_hosts_path(app) else {
+        return false;
+    };
+    let Ok(file) = std::fs::File::open(path) else {
+        return false;
+    };
+
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .any(|line| {
+            parse_known_host_line(&line)
+                .is_some_and(|entry| entry.hosts.starts_with(HASHED_HOST_MAGIC))
+        })
+}
+
 // 检查主机密钥
 pub fn check_local_host_key(
     app: &AppHandle,
@@ -38,68 +196,77 @@ pub fn check_local_host_key(
         .map_err(|e| format!("Failed to open known_hosts: {}", e))?;
     let reader = BufReader::new(file);
 
-    let target_host = if port == 22 {
-        host.to_string()
-    } else {
-        format!("[{}]:{}", host, port)
-    };
+    let target_host = known_hosts_target(host, port);
 
     let mut found_host = false;
+    let mut key_matched = false;
 
     for line_result in reader.lines() {
         let line = match line_result {
             Ok(l) => l,
             Err(_) => continue,
         };
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('#') {
+        let Some(entry) = parse_known_host_line(&line) else {
             continue;
-        }
+        };
 
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 3 {
+        if !hosts_field_matches(entry.hosts, &target_host) {
             continue;
         }
 
-        let hosts_part = parts[0];
-        let key_type_part = parts[1];
-        let b64_part = parts[2];
-
-        // Check if our target_host matches any in the hosts_part (comma-separated)
-        let matches_host = hosts_part.split(',').any(|h| h == target_host);
-        if matches_host {
-            found_host = true;
-            if key_type_part == key_type {
-                if let Ok(decoded_key) = BASE64.decode(b64_part) {
-                    if decoded_key == host_key {
-                        return Ok(HostKeyCheckStatus::Match);
-                    }
+        match entry.marker {
+            // 吊销优先于其它任何匹配结果
+            Some(KnownHostMarker::Revoked) => {
+                if entry_key_matches(&entry, key_type, host_key) {
+                    return Ok(HostKeyCheckStatus::Revoked);
+                }
+            }
+            // CA 条目只用于校验主机证书，握手拿到的是普通主机密钥，不参与比对
+            Some(KnownHostMarker::CertAuthority) => {}
+            None => {
+                found_host = true;
+                if entry_key_matches(&entry, key_type, host_key) {
+                    key_matched = true;
                 }
             }
         }
     }
 
-    if found_host {
+    if key_matched {
+        Ok(HostKeyCheckStatus::Match)
+    } else if found_host {
         Ok(HostKeyCheckStatus::Mismatch)
     } else {
         Ok(HostKeyCheckStatus::NotFound)
     }
 }
 
-// 保存主机密钥到本地磁盘
+// 保存主机密钥到本地磁盘，hash_host 为 true 时写入 |1|salt|hash 形式
 pub fn save_host_key_to_disk(
     app: &AppHandle,
     host: &str,
     port: u16,
     key_type: &str,
     host_key: &[u8],
+    hash_host: bool,
 ) -> Result<(), String> {
     let key_base64 = BASE64.encode(host_key);
-    let line = if port == 22 {
-        format!("{} {} {}\n", host, key_type, key_base64)
+    let target_host = known_hosts_target(host, port);
+    let hosts_field = if hash_host {
+        let mut salt = [0u8; HASHED_HOST_SALT_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let hash = hash_host_name(&salt, &target_host)
+            .ok_or_else(|| "Failed to hash known_hosts entry".to_string())?;
+        format!(
+            "{}{}|{}",
+            HASHED_HOST_MAGIC,
+            BASE64.encode(salt),
+            BASE64.encode(hash)
+        )
     } else {
-        format!("[{}]:{} {} {}\n", host, port, key_type, key_base64)
+        target_host
     };
+    let line = format!("{} {} {}\n", hosts_field, key_type, key_base64);
 
     let known_hosts_path = get_known_hosts_path(app)
         .ok_or_else(|| "Could not determine home directory".to_string())?;
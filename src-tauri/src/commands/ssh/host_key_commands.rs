@@ -8,14 +8,16 @@ use async_trait::async_trait;
 use russh::client;
 use russh_keys::key::PublicKey;
 use russh_keys::PublicKeyBase64;
+use tokio::io::{AsyncRead, AsyncWrite};
 
+use crate::commands::vault::VaultState;
 use crate::state::AppState;
 use crate::models::{ConnectionType, SshConfig};
 use super::resolver;
-use super::core::establish_tcp_stream;
+use super::core::{establish_jump_chain, establish_tcp_stream, open_jump_tunnel};
 
 use super::host_key::{self, HostKeyCheckStatus};
-use super::state::{HostKeyVerificationCache, PendingHostKey};
+use super::state::{disconnect_jump_sessions, HostKeyVerificationCache, PendingHostKey};
 use super::utils;
 
 #[derive(serde::Serialize)]
@@ -61,11 +63,44 @@ impl client::Handler for HostKeyCaptureHandler {
     }
 }
 
+// 只完成密钥交换、取得服务端主机密钥，不进行认证
+async fn capture_host_key<S>(app: &AppHandle, stream: S) -> Result<Option<(PublicKey, String)>, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    utils::emit_ssh_log(app, "Initiating SSH protocol handshake...");
+    let client_config = Arc::new(client::Config::default());
+    let (key_tx, key_rx) = tokio::sync::oneshot::channel();
+    let handler = HostKeyCaptureHandler {
+        key_tx: Arc::new(Mutex::new(Some(key_tx))),
+    };
+
+    let connect_future = client::connect_stream(client_config, stream, handler);
+
+    Ok(tokio::select! {
+        res = key_rx => {
+            match res {
+                Ok(key_data) => Some(key_data),
+                Err(_) => None,
+            }
+        }
+        res = connect_future => {
+            if let Err(e) = res {
+                let err = format!("SSH handshake failed: {}", e);
+                utils::emit_ssh_log(app, &err);
+                return Err(err);
+            }
+            None
+        }
+    })
+}
+
 #[tauri::command]
 pub async fn check_host_key(
     app: AppHandle,
     verification_cache: State<'_, HostKeyVerificationCache>,
     app_state: State<'_, AppState>,
+    vault_state: State<'_, VaultState>,
     id: String,
     host: String,
     port: u16,
@@ -106,48 +141,68 @@ pub async fn check_host_key(
         passphrase: None,
         password_id: None,
         password_source: None,
+        certificate: None,
+        use_agent: false,
         connect_timeout: Some(10),
         keep_alive_interval: None,
         auto_reconnect: None,
         max_reconnects: None,
+        host_key_policy: None,
+        jump_hosts: Vec::new(),
+        agent_forwarding: false,
+        agent_forward_keys: Vec::new(),
+        split_transport: false,
+        encoding: None,
+        record_sessions: false,
+        record_input: false,
+        session_log: None,
+        persistent_session: None,
+        shell_options: None,
     };
 
-    utils::emit_ssh_log(&app, "Connecting to target host (TCP)...");
-    let tcp = establish_tcp_stream(&config).map_err(|e| {
-        let err = format!("Network unreachable: {}", e);
-        utils::emit_ssh_log(&app, &err);
-        err
-    })?;
-
-    utils::emit_ssh_log(&app, "Initiating SSH protocol handshake...");
-    tcp.set_nonblocking(true)
-        .map_err(|e| format!("Failed to set TCP nonblocking: {}", e))?;
-    let async_stream = tokio::net::TcpStream::from_std(tcp)
-        .map_err(|e| format!("Failed to convert TCP stream to tokio stream: {}", e))?;
-
-    let client_config = Arc::new(client::Config::default());
-    let (key_tx, key_rx) = tokio::sync::oneshot::channel();
-    let handler = HostKeyCaptureHandler {
-        key_tx: Arc::new(Mutex::new(Some(key_tx))),
-    };
-
-    let connect_future = client::connect_stream(client_config, async_stream, handler);
-
-    let captured: Option<(PublicKey, String)> = tokio::select! {
-        res = key_rx => {
-            match res {
-                Ok(key_data) => Some(key_data),
-                Err(_) => None,
-            }
-        }
-        res = connect_future => {
-            if let Err(e) = res {
-                let err = format!("SSH handshake failed: {}", e);
-                utils::emit_ssh_log(&app, &err);
-                return Err(err);
-            }
-            None
-        }
+    // 🟢 经跳板机访问的服务器：与 establish_base_session 相同的跳板链上打开隧道再握手
+    let jump_host_ids = resolver::load_jump_host_ids(db_pool, &id).await?;
+    let captured = if jump_host_ids.iter().any(|hop| !hop.trim().is_empty()) {
+        let master_key = {
+            let guard = vault_state.0.lock().unwrap();
+            guard
+                .as_ref()
+                .cloned()
+                .ok_or("VAULT_LOCKED: Please unlock the vault first.")?
+        };
+        let jump_hosts =
+            resolver::resolve_jump_hosts(db_pool, &master_key, Some(&id), &jump_host_ids).await?;
+        utils::emit_ssh_log(
+            &app,
+            &format!("Connecting through {} jump host(s)...", jump_hosts.len()),
+        );
+        let jump_sessions = establish_jump_chain(&app, &jump_hosts, None, "host_key_check")
+            .await
+            .inspect_err(|err| utils::emit_ssh_log(&app, err))?;
+        let result = match jump_sessions.last() {
+            Some(last_hop) => match open_jump_tunnel(last_hop, &host, port).await {
+                Ok(channel) => capture_host_key(&app, channel.into_stream()).await,
+                Err(err) => {
+                    utils::emit_ssh_log(&app, &err);
+                    Err(err)
+                }
+            },
+            None => Err("Jump host chain is empty".to_string()),
+        };
+        disconnect_jump_sessions(jump_sessions, "PiTerm host key check finished").await;
+        result?
+    } else {
+        utils::emit_ssh_log(&app, "Connecting to target host (TCP)...");
+        let tcp = establish_tcp_stream(&config).map_err(|e| {
+            let err = format!("Network unreachable: {}", e);
+            utils::emit_ssh_log(&app, &err);
+            err
+        })?;
+        tcp.set_nonblocking(true)
+            .map_err(|e| format!("Failed to set TCP nonblocking: {}", e))?;
+        let async_stream = tokio::net::TcpStream::from_std(tcp)
+            .map_err(|e| format!("Failed to convert TCP stream to tokio stream: {}", e))?;
+        capture_host_key(&app, async_stream).await?
     };
 
     let (server_public_key, key_type) = captured.ok_or_else(|| {
@@ -177,13 +232,18 @@ pub async fn check_host_key(
             utils::emit_ssh_log(&app, "ℹ️ New host detected, awaiting user trust...");
             "unknown"
         }
+        HostKeyCheckStatus::Revoked => {
+            utils::emit_ssh_log(&app, "⛔ Host key has been REVOKED in known_hosts!");
+            "revoked"
+        }
     };
 
     let cache = verification_cache.entries.clone();
     let mut entries = cache.lock().unwrap();
     prune_pending_host_keys(&mut entries);
 
-    if status == "verified" {
+    // 已吊销的密钥不允许通过 trust_host_key 重新信任
+    if status == "verified" || status == "revoked" {
         entries.remove(&id);
     } else {
         entries.insert(
@@ -222,6 +282,7 @@ pub async fn trust_host_key(
     id: String,
     fingerprint: String,
     _key_type: String,
+    hash_host: Option<bool>,
 ) -> Result<(), String> {
     let db_pool = &app_state.db;
     let cache = verification_cache.entries.clone();
@@ -254,7 +315,16 @@ pub async fn trust_host_key(
         ));
     }
 
-    host_key::save_host_key_to_disk(&app, &host, port, &pending.key_type, &pending.host_key)?;
+    // 未指定时沿用 known_hosts 现有格式 (已有哈希条目则继续写哈希)
+    let hash_host = hash_host.unwrap_or_else(|| host_key::known_hosts_uses_hashing(&app));
+    host_key::save_host_key_to_disk(
+        &app,
+        &host,
+        port,
+        &pending.key_type,
+        &pending.host_key,
+        hash_host,
+    )?;
 
     Ok(())
 }
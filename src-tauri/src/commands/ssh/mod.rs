@@ -1,22 +1,56 @@
+mod auth_commands;
 mod background;
+mod broadcast;
+mod cwd_commands;
+mod forward_commands;
 mod host_key_commands;
+mod persistent_session_commands;
 mod runtime;
+mod scrollback_commands;
+mod shell_integration_commands;
+mod terminal_transfer_commands;
+mod trigger_commands;
 pub(crate) mod session_commands;
 
 pub mod core;
+pub mod forwarding;
 pub mod host_key;
 pub mod resolver;
 pub mod state;
 pub mod utils;
 
+pub use auth_commands::respond_auth_prompt;
+pub use broadcast::{
+    broadcast_write, confirm_broadcast_group, create_broadcast_group, delete_broadcast_group,
+    list_broadcast_groups, set_broadcast_member_paused, set_broadcast_safety_mode,
+    update_broadcast_members, BroadcastRegistry,
+};
+pub use cwd_commands::{
+    get_terminal_cwd, open_terminal_here, set_terminal_cwd_sync, TerminalCwdInfo,
+};
+pub use forward_commands::{
+    delete_port_forward, list_active_port_forwards, list_port_forwards, save_port_forward,
+    start_port_forward, stop_port_forward,
+};
+pub use forwarding::RemoteForwardRegistry;
+pub use persistent_session_commands::{
+    attach_tmux_session, get_persistent_session, list_remote_tmux_sessions,
+};
+pub use scrollback_commands::{
+    get_scrollback_lines, query_scrollback, read_scrollback, search_scrollback, ScrollbackQuery,
+};
+pub use shell_integration_commands::{get_shell_integration_script, inject_shell_integration};
+pub use terminal_transfer_commands::{cancel_terminal_transfer, respond_terminal_transfer};
+pub use trigger_commands::{delete_trigger, list_triggers, save_trigger};
 pub use host_key_commands::{check_host_key, trust_host_key, HostKeyCheckResult, HostKeyData};
 pub use session_commands::{
-    connect_ssh, disconnect_ssh, quick_connect, resize_ssh, test_connection, touch_ssh_session,
-    write_ssh,
+    connect_ssh, disconnect_ssh, duplicate_ssh_session, quick_connect, resize_ssh,
+    set_terminal_raw_output, test_connection, touch_ssh_session, write_ssh,
 };
 pub use state::{
     get_ssh_session_if_instance, remove_ssh_session, remove_ssh_session_if_instance,
-    spawn_ssh_session_cleanup_task, BackgroundSessionEvent, HostKeyVerificationCache,
-    PendingHostKey, SshConnection, SshState, SshWriteRequest, TerminalExitEvent,
-    SshSession,
+    spawn_ssh_session_cleanup_task, AuthPromptRegistry, BackgroundSessionEvent,
+    HostKeyVerificationCache,
+    PendingHostKey, SshConnection, SshState, SshWriteRequest, TerminalCwdEvent, TerminalExitEvent,
+    SshSession, JumpSessions, SharedSshSession, SshTransport, TerminalTransferRegistry,
 };
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use tauri::State;
+
+use crate::commands::monitor::exec_ssh_command;
+use crate::models::PersistentSessionMode;
+use crate::utils::ssh_log::{self, SshLogRecord};
+
+use super::core::{
+    parse_tmux_sessions, tmux_attach_input, tmux_list_sessions_command, validate_session_name,
+    PersistentShell, RemoteTmuxSession,
+};
+use super::runtime::queue_shell_write;
+use super::state::{SshConnection, SshState};
+
+const PERSISTENT_KILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn get_connection(state: &SshState, id: &str) -> Result<SshConnection, String> {
+    let map = state.sessions.lock().map_err(|e| e.to_string())?;
+    let conn = map
+        .get(id)
+        .cloned()
+        .ok_or_else(|| "SSH connection not active".to_string())?;
+    conn.touch_client_heartbeat();
+    Ok(conn)
+}
+
+// 🟢 当前 PiTerm 会话绑定的远端 tmux/screen 会话 (未启用持久会话时为空)
+#[tauri::command]
+pub fn get_persistent_session(
+    state: State<'_, SshState>,
+    id: String,
+) -> Result<Option<PersistentShell>, String> {
+    Ok(get_connection(&state, &id)?.persistent_shell())
+}
+
+// 🟢 列出远端已有的 tmux 会话；拆分传输模式下优先走后台传输，避免占用 shell 传输的通道配额
+#[tauri::command]
+pub async fn list_remote_tmux_sessions(
+    state: State<'_, SshState>,
+    id: String,
+) -> Result<Vec<RemoteTmuxSession>, String> {
+    let conn = get_connection(&state, &id)?;
+    let session = conn
+        .bg_session_arc()
+        .unwrap_or_else(|| conn.transport.session.clone());
+    let output = exec_ssh_command(&session, &tmux_list_sessions_command()).await?;
+    let current = conn
+        .persistent_shell()
+        .filter(|persistent| persistent.mode == PersistentSessionMode::Tmux)
+        .map(|persistent| persistent.name);
+    parse_tmux_sessions(&output, current.as_deref())
+}
+
+// 🟢 在当前终端中附加到指定 tmux 会话，并记为该 PiTerm 会话的持久会话，之后自动重连会回到这里
+#[tauri::command]
+pub async fn attach_tmux_session(
+    state: State<'_, SshState>,
+    id: String,
+    name: String,
+) -> Result<(), String> {
+    let name = name.trim().to_string();
+    validate_session_name(&name)?;
+    let conn = get_connection(&state, &id)?;
+    if !conn.shell_is_active() {
+        return Err("SSH shell not active".to_string());
+    }
+    // 附加命令是键入到 shell 的，前台有程序 (vim、top 等) 时会被当成它的输入
+    conn.ensure_shell_idle()?;
+
+    queue_shell_write(conn.shell_write_tx.clone(), tmux_attach_input(&name)).await?;
+    // PiTerm 自建的会话不在此记录中，关闭标签页时仍按标签页 ID 结束
+    conn.set_persistent_shell(Some(PersistentShell {
+        mode: PersistentSessionMode::Tmux,
+        name: name.clone(),
+        owned: false,
+        created: false,
+    }));
+    ssh_log::info(
+        SshLogRecord::new(
+            "ssh.shell",
+            "tmux_attach_requested",
+            "Attaching terminal to remote tmux session",
+        )
+        .session_id(id)
+        .server_id(conn.config.id.clone())
+        .instance_id(conn.instance_id)
+        .field("session_name", name),
+    );
+    Ok(())
+}
+
+// 🟢 主动关闭标签页时结束 PiTerm 创建的 tmux/screen 会话；传输断开与自动重连不经过这里，会话得以保留。
+// 会话名由标签页 ID 派生，之后手动附加到其他 tmux 会话 (当前记录被替换) 时也能找到并结束它
+pub(super) async fn kill_owned_persistent_shell(conn: &SshConnection, session_id: &str) {
+    let Some(mode) = conn.config.persistent_session else {
+        return;
+    };
+    let persistent = PersistentShell::for_session(mode, session_id);
+    let session = conn
+        .bg_session_arc()
+        .unwrap_or_else(|| conn.transport.session.clone());
+    let result = tokio::time::timeout(
+        PERSISTENT_KILL_TIMEOUT,
+        exec_ssh_command(&session, &persistent.kill_command()),
+    )
+    .await
+    .unwrap_or_else(|_| Err("Timed out".to_string()));
+
+    let record = SshLogRecord::new(
+        "ssh.shell",
+        "persistent_session_killed",
+        "Ended remote persistent session on tab close",
+    )
+    .session_id(session_id.to_string())
+    .server_id(conn.config.id.clone())
+    .instance_id(conn.instance_id)
+    .field("multiplexer", persistent.program())
+    .field("session_name", persistent.name);
+    match result {
+        Ok(_) => ssh_log::info(record),
+        Err(err) => ssh_log::warn(record.field("error", err)),
+    }
+}
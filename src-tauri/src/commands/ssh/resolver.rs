@@ -1,6 +1,9 @@
 use super::utils::clean_private_key;
-use crate::commands::vault::internal_get_secret;
-use crate::models::{ConnectionType, Proxy, SshConfig, TestConnectionPayload};
+use crate::commands::vault::{internal_get_certificate, internal_get_secret};
+use crate::models::{
+    ConnectionType, HostKeyPolicy, PersistentSessionMode, Proxy, SessionLogConfig, ShellOptions,
+    SshConfig, TestConnectionPayload,
+};
 use aes_gcm::{Aes256Gcm, Key};
 use serde_json::Value;
 use sqlx::{Row, SqlitePool};
@@ -34,10 +37,61 @@ pub async fn resolve_config(
     db_pool: &SqlitePool,
     master_key: &Key<Aes256Gcm>,
     server_id: &str,
+) -> Result<SshConfig, String> {
+    let mut config = resolve_server_config(db_pool, master_key, server_id).await?;
+    let jump_host_ids = load_jump_host_ids(db_pool, server_id).await?;
+    config.jump_hosts =
+        resolve_jump_hosts(db_pool, master_key, Some(server_id), &jump_host_ids).await?;
+    Ok(config)
+}
+
+pub async fn load_jump_host_ids(
+    db_pool: &SqlitePool,
+    server_id: &str,
+) -> Result<Vec<String>, String> {
+    Ok(sqlx::query("SELECT jump_host_ids FROM servers WHERE id = ?")
+        .bind(server_id)
+        .fetch_optional(db_pool)
+        .await
+        .map_err(|e| format!("DB Query Error: {}", e))?
+        .and_then(|row| row.try_get::<Option<String>, _>("jump_host_ids").ok().flatten())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+// 🟢 ProxyJump：每一跳都按自身的服务器记录解析 (独立的 Vault 凭据、代理与主机密钥策略)
+pub async fn resolve_jump_hosts(
+    db_pool: &SqlitePool,
+    master_key: &Key<Aes256Gcm>,
+    target_id: Option<&str>,
+    jump_host_ids: &[String],
+) -> Result<Vec<SshConfig>, String> {
+    let mut jump_hosts: Vec<SshConfig> = Vec::with_capacity(jump_host_ids.len());
+    for jump_id in jump_host_ids.iter().filter(|id| !id.trim().is_empty()) {
+        if Some(jump_id.as_str()) == target_id {
+            return Err("Jump host chain must not include the target server itself".to_string());
+        }
+        if jump_hosts.iter().any(|hop| &hop.id == jump_id) {
+            return Err(format!("Jump host {} appears more than once in the chain", jump_id));
+        }
+        let hop = resolve_server_config(db_pool, master_key, jump_id)
+            .await
+            .map_err(|e| format!("Jump host resolution failed ({}): {}", jump_id, e))?;
+        jump_hosts.push(hop);
+    }
+    Ok(jump_hosts)
+}
+
+async fn resolve_server_config(
+    db_pool: &SqlitePool,
+    master_key: &Key<Aes256Gcm>,
+    server_id: &str,
 ) -> Result<SshConfig, String> {
     let row = sqlx::query(
         "SELECT id, name, ip, port, username, connection_type, proxy_id, auth_type, password_id, key_id, passphrase, private_key, password, 
-                connect_timeout, keep_alive_interval, auto_reconnect, max_reconnects 
+                connect_timeout, keep_alive_interval, auto_reconnect, max_reconnects, host_key_policy,
+                agent_forwarding, split_transport, encoding, record_sessions, record_input, session_log,
+                persistent_session, shell_options
          FROM servers WHERE id = ?"
     )
     .bind(server_id)
@@ -60,10 +114,32 @@ pub async fn resolve_config(
     let keep_alive_interval: Option<u32> = row.try_get("keep_alive_interval").ok();
     let auto_reconnect: Option<bool> = row.try_get("auto_reconnect").ok();
     let max_reconnects: Option<u32> = row.try_get("max_reconnects").ok();
+    let host_key_policy: Option<HostKeyPolicy> = row.try_get("host_key_policy").ok();
+    let agent_forwarding: bool = row.try_get("agent_forwarding").unwrap_or(false);
+    let split_transport: bool = row.try_get("split_transport").unwrap_or(false);
+    let encoding: Option<String> = row.try_get("encoding").ok();
+    let record_sessions: bool = row.try_get("record_sessions").unwrap_or(false);
+    let record_input: bool = row.try_get("record_input").unwrap_or(false);
+    let session_log: Option<SessionLogConfig> = row
+        .try_get::<Option<String>, _>("session_log")
+        .ok()
+        .flatten()
+        .and_then(|raw| serde_json::from_str(&raw).ok());
+    let persistent_session: Option<PersistentSessionMode> = row
+        .try_get::<Option<PersistentSessionMode>, _>("persistent_session")
+        .ok()
+        .flatten();
+    let shell_options: Option<ShellOptions> = row
+        .try_get::<Option<String>, _>("shell_options")
+        .ok()
+        .flatten()
+        .and_then(|raw| serde_json::from_str(&raw).ok());
 
     let mut final_password: Option<String> = None;
     let mut final_private_key: Option<String> = None;
     let mut final_passphrase: Option<String> = row.get("passphrase");
+    let mut final_certificate: Option<String> = None;
+    let use_agent = auth_type == "agent";
 
     if auth_type == "password" {
         let pwd_id: Option<String> = row.get("password_id");
@@ -100,6 +176,7 @@ pub async fn resolve_config(
             };
 
             final_private_key = Some(clean_private_key(&raw_key));
+            final_certificate = internal_get_certificate(db_pool, &kid).await?;
         } else {
             let raw_key: Option<String> = row.get("private_key");
             if let Some(k) = raw_key {
@@ -114,7 +191,8 @@ pub async fn resolve_config(
         }
     }
 
-    if final_password.is_none() && final_private_key.is_none() {
+    // 🟢 Agent 认证的凭据由本地 ssh-agent 持有，数据库中可以没有密码/私钥
+    if !use_agent && final_password.is_none() && final_private_key.is_none() {
         return Err(format!(
             "Auth Failed: No password or private key resolved from database. (Type: {})",
             auth_type
@@ -122,6 +200,11 @@ pub async fn resolve_config(
     }
 
     let proxy = load_proxy_for_connection(db_pool, &connection_type, proxy_id.as_deref()).await?;
+    let agent_forward_keys = if agent_forwarding {
+        load_forwardable_keys(db_pool, master_key).await?
+    } else {
+        Vec::new()
+    };
 
     Ok(SshConfig {
         id: server_id.to_string(),
@@ -134,15 +217,62 @@ pub async fn resolve_config(
         password: final_password,
         private_key: final_private_key,
         passphrase: final_passphrase,
+        certificate: final_certificate,
         password_id: None,
         password_source: None,
+        use_agent,
         connect_timeout,
         keep_alive_interval,
         auto_reconnect,
         max_reconnects,
+        host_key_policy,
+        jump_hosts: Vec::new(),
+        agent_forwarding,
+        agent_forward_keys,
+        split_transport,
+        encoding,
+        record_sessions,
+        record_input,
+        session_log,
+        persistent_session,
+        shell_options,
     })
 }
 
+// 🟢 Agent 转发：读取用户标记为可转发的 Vault 私钥 (私钥, 口令)
+async fn load_forwardable_keys(
+    db_pool: &SqlitePool,
+    master_key: &Key<Aes256Gcm>,
+) -> Result<Vec<(String, Option<String>)>, String> {
+    let ids: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM vault_keys WHERE forwardable = 1 AND key_type = 'private_key' ORDER BY created_at ASC",
+    )
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| format!("DB Query Error: {}", e))?;
+
+    let mut keys = Vec::with_capacity(ids.len());
+    for id in ids {
+        let decrypted = internal_get_secret(db_pool, master_key, &id).await?;
+        let (raw_key, passphrase) = match serde_json::from_str::<Value>(&decrypted) {
+            Ok(parsed) => match parsed.get("val").and_then(|v| v.as_str()) {
+                Some(val) => (
+                    val.to_string(),
+                    parsed
+                        .get("pass")
+                        .and_then(|v| v.as_str())
+                        .filter(|pass| !pass.trim().is_empty())
+                        .map(str::to_string),
+                ),
+                None => (decrypted, None),
+            },
+            Err(_) => (decrypted, None),
+        };
+        keys.push((clean_private_key(&raw_key), passphrase));
+    }
+    Ok(keys)
+}
+
 pub async fn resolve_test_config(
     db_pool: &SqlitePool,
     master_key: Option<&Key<Aes256Gcm>>,
@@ -151,6 +281,7 @@ pub async fn resolve_test_config(
     let mut final_password: Option<String> = None;
     let mut final_private_key: Option<String> = None;
     let mut final_passphrase: Option<String> = payload.passphrase.clone();
+    let mut final_certificate: Option<String> = None;
 
     // --- 处理密码 ---
     if payload.auth_type == "password" {
@@ -193,6 +324,7 @@ pub async fn resolve_test_config(
                     };
                     final_private_key = Some(clean_private_key(&raw_key));
                 }
+                final_certificate = internal_get_certificate(db_pool, &kid).await?;
             }
         } else {
             if let Some(pk) = payload.private_key {
@@ -208,6 +340,15 @@ pub async fn resolve_test_config(
     )
     .await?;
 
+    let jump_hosts = if payload.jump_host_ids.is_empty() {
+        Vec::new()
+    } else {
+        let mk = master_key.ok_or_else(|| {
+            "VAULT_LOCKED: Please unlock the vault to use jump host credentials.".to_string()
+        })?;
+        resolve_jump_hosts(db_pool, mk, None, &payload.jump_host_ids).await?
+    };
+
     Ok(SshConfig {
         id: "test_session".to_string(),
         name: None,
@@ -219,11 +360,25 @@ pub async fn resolve_test_config(
         password: final_password,
         private_key: final_private_key,
         passphrase: final_passphrase,
+        certificate: final_certificate,
         password_id: None,
         password_source: None,
+        use_agent: payload.auth_type == "agent",
         connect_timeout: payload.connect_timeout,
         keep_alive_interval: None,
         auto_reconnect: None,
         max_reconnects: None,
+        // 未指定时与已保存服务器一致：未知主机密钥需先确认
+        host_key_policy: Some(payload.host_key_policy.unwrap_or_default()),
+        jump_hosts,
+        agent_forwarding: false,
+        agent_forward_keys: Vec::new(),
+        split_transport: false,
+        encoding: None,
+        record_sessions: false,
+        record_input: false,
+        session_log: None,
+        persistent_session: None,
+        shell_options: None,
     })
 }
@@ -2,6 +2,8 @@ use std::time::Duration;
 
 use crate::utils::ssh_log::{self, SshLogRecord};
 
+use super::state::SshWriteRequest;
+
 pub const SSH_WRITE_QUEUE_CAPACITY: usize = 1024;
 
 pub(super) const SSH_BLOCKING_OPERATION_TIMEOUT: Duration = Duration::from_secs(10);
@@ -47,3 +49,48 @@ where
         err
     })?
 }
+
+// 🟢 把输入放入 shell 写队列并等待写入结果 (write_ssh 与广播共用)
+pub(super) async fn queue_shell_write(
+    write_tx: tokio::sync::mpsc::Sender<SshWriteRequest>,
+    data: String,
+) -> Result<(), String> {
+    queue_shell_request(write_tx, data, false).await
+}
+
+// 触发器发送的密码走这里，不写入录像
+pub(super) async fn queue_shell_secret_write(
+    write_tx: tokio::sync::mpsc::Sender<SshWriteRequest>,
+    data: String,
+) -> Result<(), String> {
+    queue_shell_request(write_tx, data, true).await
+}
+
+async fn queue_shell_request(
+    write_tx: tokio::sync::mpsc::Sender<SshWriteRequest>,
+    data: String,
+    secret: bool,
+) -> Result<(), String> {
+    tokio::time::timeout(SSH_BLOCKING_OPERATION_TIMEOUT, async move {
+        let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+        write_tx
+            .send(SshWriteRequest {
+                data,
+                secret,
+                result_tx,
+            })
+            .await
+            .map_err(|_| "SSH shell not active".to_string())?;
+
+        result_rx
+            .await
+            .map_err(|_| "SSH write worker stopped".to_string())?
+    })
+    .await
+    .map_err(|_| {
+        format!(
+            "SSH write timed out after {}s",
+            SSH_BLOCKING_OPERATION_TIMEOUT.as_secs()
+        )
+    })?
+}
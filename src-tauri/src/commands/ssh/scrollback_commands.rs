@@ -0,0 +1,109 @@
+use regex::RegexBuilder;
+use serde::Deserialize;
+use tauri::State;
+
+use super::core::{ScrollbackPage, ScrollbackSearchResult};
+use super::state::SshState;
+
+const DEFAULT_SCROLLBACK_PAGE_LINES: usize = 200;
+const MAX_SCROLLBACK_PAGE_LINES: usize = 2000;
+const DEFAULT_SCROLLBACK_SEARCH_LIMIT: usize = 50;
+const MAX_SCROLLBACK_SEARCH_LIMIT: usize = 500;
+const MAX_SCROLLBACK_CONTEXT_LINES: usize = 50;
+const MAX_SCROLLBACK_PATTERN_LEN: usize = 1024;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackQuery {
+    pub pattern: String,
+    #[serde(default)]
+    pub case_sensitive: Option<bool>,
+    #[serde(default)]
+    pub context_lines: Option<usize>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    // 只搜索该行号之前的行 (分页游标，取上一页的 nextBeforeLine)
+    #[serde(default)]
+    pub before_line: Option<u64>,
+}
+
+pub fn read_scrollback(
+    state: &SshState,
+    id: &str,
+    start_line: Option<u64>,
+    limit: Option<usize>,
+) -> Result<ScrollbackPage, String> {
+    let scrollback = {
+        let map = state.sessions.lock().map_err(|e| e.to_string())?;
+        map.get(id)
+            .map(|conn| conn.scrollback.clone())
+            .ok_or_else(|| "SSH connection not active".to_string())?
+    };
+    let limit = limit
+        .unwrap_or(DEFAULT_SCROLLBACK_PAGE_LINES)
+        .clamp(1, MAX_SCROLLBACK_PAGE_LINES);
+    let page = match scrollback.lock() {
+        Ok(lines) => lines.page(start_line, limit),
+        Err(poisoned) => poisoned.into_inner().page(start_line, limit),
+    };
+    Ok(page)
+}
+
+pub fn query_scrollback(
+    state: &SshState,
+    id: &str,
+    query: &ScrollbackQuery,
+) -> Result<ScrollbackSearchResult, String> {
+    if query.pattern.is_empty() {
+        return Err("Search pattern is empty".to_string());
+    }
+    if query.pattern.len() > MAX_SCROLLBACK_PATTERN_LEN {
+        return Err("Search pattern is too long".to_string());
+    }
+    let pattern = RegexBuilder::new(&query.pattern)
+        .case_insensitive(!query.case_sensitive.unwrap_or(false))
+        .build()
+        .map_err(|e| format!("INVALID_PATTERN: {}", e))?;
+
+    let scrollback = {
+        let map = state.sessions.lock().map_err(|e| e.to_string())?;
+        map.get(id)
+            .map(|conn| conn.scrollback.clone())
+            .ok_or_else(|| "SSH connection not active".to_string())?
+    };
+    let context = query
+        .context_lines
+        .unwrap_or(0)
+        .min(MAX_SCROLLBACK_CONTEXT_LINES);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SCROLLBACK_SEARCH_LIMIT)
+        .clamp(1, MAX_SCROLLBACK_SEARCH_LIMIT);
+    let result = match scrollback.lock() {
+        Ok(lines) => lines.search(&pattern, context, limit, query.before_line),
+        Err(poisoned) => poisoned
+            .into_inner()
+            .search(&pattern, context, limit, query.before_line),
+    };
+    Ok(result)
+}
+
+// 🟢 分页读取去除控制序列后的回滚文本；start_line 为空时返回最新的 limit 行
+#[tauri::command]
+pub fn get_scrollback_lines(
+    state: State<'_, SshState>,
+    id: String,
+    start_line: Option<u64>,
+    limit: Option<usize>,
+) -> Result<ScrollbackPage, String> {
+    read_scrollback(&state, &id, start_line, limit)
+}
+
+#[tauri::command]
+pub fn search_scrollback(
+    state: State<'_, SshState>,
+    id: String,
+    query: ScrollbackQuery,
+) -> Result<ScrollbackSearchResult, String> {
+    query_scrollback(&state, &id, &query)
+}
@@ -3,19 +3,28 @@ use std::sync::Arc;
 use tauri::{AppHandle, Emitter, State};
 
 use crate::commands::vault::VaultState;
-use crate::models::{SshConfig, TestConnectionPayload};
+use crate::models::{HostKeyPolicy, SshConfig, TestConnectionPayload};
 use crate::state::AppState;
 use crate::utils::ssh_log::{self, SshLogRecord};
 
-use super::background::spawn_background_session_connector;
-use super::core::{create_shell_channel, spawn_shell_reader_thread, spawn_shell_writer_thread};
+use crate::commands::recording::start_auto_recording;
+
+use super::background::start_background_session;
+use super::forward_commands::load_auto_start_forwards;
+use super::forwarding::spawn_port_forward_restore;
+use super::persistent_session_commands::kill_owned_persistent_shell;
+use super::core::{
+    create_shell_channel, open_shell_channel, spawn_shell_reader_thread, spawn_shell_writer_thread,
+    PersistentShell,
+};
 use super::resolver;
 use super::runtime::{
-    run_blocking_ssh_task, SSH_BLOCKING_OPERATION_TIMEOUT, SSH_WRITE_QUEUE_CAPACITY,
+    queue_shell_write, run_blocking_ssh_task, SSH_BLOCKING_OPERATION_TIMEOUT,
+    SSH_WRITE_QUEUE_CAPACITY,
 };
 use super::state::{
-    remove_ssh_session, SshConnection, SshResizeRequest, SshState, SshWriteRequest,
-    TerminalExitEvent,
+    disconnect_jump_sessions, remove_ssh_session, SshConnection, SshResizeRequest, SshState,
+    SshTransport, TerminalExitEvent,
 };
 use super::utils;
 
@@ -87,7 +96,10 @@ pub async fn connect_ssh(
         let _ = conn.shutdown("PiTerm replaced session");
     }
 
-    let (shell_sess, shell_channel) = create_shell_channel(&config, Some(&session_id)).await
+    let mut persistent = config
+        .persistent_session
+        .map(|mode| PersistentShell::for_session(mode, &session_id));
+    let (shell_sess, shell_channel, shell_jump_sessions) = create_shell_channel(&app, &config, Some(&session_id), persistent.as_mut(), "shell").await
         .map_err(|e| {
             let err = format!("Shell Connection Failed: {}", e);
             ssh_log::error(
@@ -103,17 +115,17 @@ pub async fn connect_ssh(
             err
         })?;
 
-    let shell_sess = Arc::new(shell_sess);
     let shell_channel_id = shell_channel.id();
     let (shell_write_tx, shell_write_rx) = tokio::sync::mpsc::channel(SSH_WRITE_QUEUE_CAPACITY);
     let (shell_resize_tx, shell_resize_rx) = tokio::sync::mpsc::channel(SSH_WRITE_QUEUE_CAPACITY);
     let connection = SshConnection::new(
         config.clone(),
-        shell_sess.clone(),
+        SshTransport::new(shell_sess, shell_jump_sessions),
         shell_channel_id,
         shell_write_tx,
         shell_resize_tx,
     );
+    connection.set_persistent_shell(persistent);
     let connection_instance_id = connection.instance_id;
 
     let active_sessions = {
@@ -150,14 +162,36 @@ pub async fn connect_ssh(
         connection_instance_id,
         shell_resize_rx,
     );
-    spawn_background_session_connector(
-        app,
+    start_background_session(
+        app.clone(),
         sessions.clone(),
         config.clone(),
         session_id.clone(),
         server_id.clone(),
         connection_instance_id,
     );
+    start_auto_recording(&app, &state, &session_id);
+
+    // 🟢 自动启动该服务器保存的端口转发
+    match load_auto_start_forwards(db_pool, &server_id).await {
+        Ok(rules) => spawn_port_forward_restore(
+            app,
+            sessions.clone(),
+            session_id.clone(),
+            connection_instance_id,
+            rules,
+        ),
+        Err(err) => ssh_log::warn(
+            SshLogRecord::new(
+                "ssh.forward",
+                "auto_start_load_failed",
+                "Failed to load auto-start port forwards",
+            )
+            .session_id(session_id.clone())
+            .server_id(server_id.clone())
+            .field("error", err),
+        ),
+    }
 
     ssh_log::info(
         SshLogRecord::new(
@@ -169,13 +203,141 @@ pub async fn connect_ssh(
         .server_id(server_id)
         .instance_id(connection_instance_id)
         .field("active_session_count", active_sessions)
-        .field("background_status", "connecting"),
+        .field(
+            "background_status",
+            if config.split_transport { "connecting" } else { "shared" },
+        ),
+    );
+    Ok(())
+}
+
+// 🟢 复制标签页：在源会话的已认证传输上再开一个 shell 通道，不重新握手
+#[tauri::command]
+pub async fn duplicate_ssh_session(
+    app: AppHandle,
+    state: State<'_, SshState>,
+    source_id: String,
+    session_id: String,
+) -> Result<(), String> {
+    let sessions = state.sessions.clone();
+    let source = {
+        let map = match sessions.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        map.get(&source_id)
+            .cloned()
+            .ok_or_else(|| "SSH connection not active".to_string())?
+    };
+    if source.shutdown_complete.load(std::sync::atomic::Ordering::SeqCst) {
+        return Err("SSH connection not active".to_string());
+    }
+    ssh_log::info(
+        SshLogRecord::new(
+            "ssh.command",
+            "duplicate_requested",
+            "Received request to duplicate SSH session",
+        )
+        .session_id(session_id.clone())
+        .server_id(source.config.id.clone())
+        .field("source_session_id", source_id.clone()),
+    );
+
+    // 新标签页使用自己的远端会话，而不是与源标签页共用同一个 tmux 窗口
+    let mut persistent = source
+        .config
+        .persistent_session
+        .map(|mode| PersistentShell::for_session(mode, &session_id));
+    let shell_channel = {
+        let transport = source.transport.session.read().await;
+        if transport.is_closed() {
+            return Err("SSH transport of the source session is closed".to_string());
+        }
+        open_shell_channel(&transport, &source.config, Some(&session_id), persistent.as_mut()).await
+    }
+    .map_err(|e| {
+        let err = format!("Duplicate Session Failed: {}", e);
+        ssh_log::error(
+            SshLogRecord::new(
+                "ssh.command",
+                "duplicate_failed",
+                "Failed to open a shell channel on the shared SSH transport",
+            )
+            .session_id(session_id.clone())
+            .server_id(source.config.id.clone())
+            .field("source_session_id", source_id.clone())
+            .field("error", err.clone()),
+        );
+        err
+    })?;
+
+    if let Some(existing) = remove_ssh_session(&sessions, &session_id) {
+        let _ = existing.shutdown("PiTerm replaced session");
+    }
+
+    let shell_channel_id = shell_channel.id();
+    let (shell_write_tx, shell_write_rx) = tokio::sync::mpsc::channel(SSH_WRITE_QUEUE_CAPACITY);
+    let (shell_resize_tx, shell_resize_rx) = tokio::sync::mpsc::channel(SSH_WRITE_QUEUE_CAPACITY);
+    let connection = source.duplicate(shell_channel_id, shell_write_tx, shell_resize_tx);
+    connection.set_persistent_shell(persistent);
+    let connection_instance_id = connection.instance_id;
+    let transport_users = connection.transport.user_count();
+
+    {
+        let mut map = match sessions.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        map.insert(session_id.clone(), connection);
+    }
+
+    let write_half = shell_channel.make_writer();
+    spawn_shell_writer_thread(
+        write_half,
+        sessions.clone(),
+        session_id.clone(),
+        connection_instance_id,
+        shell_write_rx,
+    );
+    spawn_shell_reader_thread(
+        app.clone(),
+        shell_channel,
+        sessions.clone(),
+        session_id.clone(),
+        connection_instance_id,
+        shell_resize_rx,
+    );
+    start_background_session(
+        app.clone(),
+        sessions,
+        source.config.clone(),
+        session_id.clone(),
+        source.config.id.clone(),
+        connection_instance_id,
+    );
+    start_auto_recording(&app, &state, &session_id);
+
+    ssh_log::info(
+        SshLogRecord::new(
+            "ssh.command",
+            "duplicate_completed",
+            "Duplicated SSH session on the shared transport",
+        )
+        .session_id(session_id)
+        .server_id(source.config.id.clone())
+        .instance_id(connection_instance_id)
+        .field("source_session_id", source_id)
+        .field("transport_users", transport_users),
     );
     Ok(())
 }
 
 #[tauri::command]
-pub async fn disconnect_ssh(state: State<'_, SshState>, id: String) -> Result<(), String> {
+pub async fn disconnect_ssh(
+    state: State<'_, SshState>,
+    id: String,
+    keep_persistent: Option<bool>,
+) -> Result<(), String> {
     ssh_log::info(
         SshLogRecord::new(
             "ssh.command",
@@ -187,6 +349,10 @@ pub async fn disconnect_ssh(state: State<'_, SshState>, id: String) -> Result<()
     let conn = remove_ssh_session(&state.sessions, &id);
 
     if let Some(conn) = conn {
+        // 默认随标签页结束远端 tmux/screen 会话，前端可选择保留以便之后重新附加
+        if !keep_persistent.unwrap_or(false) {
+            kill_owned_persistent_shell(&conn, &id).await;
+        }
         run_blocking_ssh_task("disconnect", move || {
             let _ = conn.shutdown("PiTerm disconnect");
             Ok(())
@@ -254,26 +420,7 @@ pub async fn write_ssh(state: State<'_, SshState>, id: String, data: String) ->
             })?
     };
 
-    let write_result = tokio::time::timeout(SSH_BLOCKING_OPERATION_TIMEOUT, async move {
-        let (result_tx, result_rx) = tokio::sync::oneshot::channel();
-        write_tx
-            .send(SshWriteRequest { data, result_tx })
-            .await
-            .map_err(|_| "SSH shell not active".to_string())?;
-
-        result_rx
-            .await
-            .map_err(|_| "SSH write worker stopped".to_string())?
-    })
-    .await
-    .map_err(|_| {
-        format!(
-            "SSH write timed out after {}s",
-            SSH_BLOCKING_OPERATION_TIMEOUT.as_secs()
-        )
-    })?;
-
-    write_result.map_err(|err| {
+    queue_shell_write(write_tx, data).await.map_err(|err| {
         ssh_log::warn(
             SshLogRecord::new(
                 "ssh.command",
@@ -397,8 +544,33 @@ pub fn touch_ssh_session(state: State<'_, SshState>, id: String) -> Result<(), S
     Ok(())
 }
 
+// 🟢 切换原始字节输出：开启后额外推送 term-bytes-<id> (base64)，term-data-<id> 保持不变
+#[tauri::command]
+pub fn set_terminal_raw_output(
+    state: State<'_, SshState>,
+    id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let map = state.sessions.lock().map_err(|e| e.to_string())?;
+    let conn = map
+        .get(&id)
+        .ok_or_else(|| "SSH connection not active".to_string())?;
+    conn.set_raw_output(enabled);
+    ssh_log::debug(
+        SshLogRecord::new(
+            "ssh.command",
+            "raw_output_toggled",
+            "Toggled raw byte output for SSH session",
+        )
+        .session_id(id)
+        .field("enabled", enabled),
+    );
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn test_connection(
+    app: AppHandle,
     app_state: State<'_, AppState>,
     vault_state: State<'_, VaultState>,
     payload: TestConnectionPayload,
@@ -428,29 +600,40 @@ pub async fn test_connection(
     use crate::commands::ssh::core::establish_base_session;
     use tokio::io::AsyncReadExt;
 
-    let sess = establish_base_session(&config, None, "test")
+    // 跳板会话需要在测试期间保持存活
+    let (sess, jump_sessions) = establish_base_session(&app, &config, None, "test")
         .await
         .map_err(|e| format!("连接建立失败: {}", e))?;
 
-    let channel = sess
-        .channel_open_session()
-        .await
-        .map_err(|e| format!("通道创建失败: {}", e))?;
+    let result = async {
+        let channel = sess
+            .channel_open_session()
+            .await
+            .map_err(|e| format!("通道创建失败: {}", e))?;
 
-    channel
-        .exec(true, "whoami")
-        .await
-        .map_err(|e| format!("命令验证失败: {}", e))?;
+        channel
+            .exec(true, "whoami")
+            .await
+            .map_err(|e| format!("命令验证失败: {}", e))?;
 
-    let mut s = String::new();
-    let stream = channel.into_stream();
-    let (mut read_half, _) = tokio::io::split(stream);
-    read_half
-        .read_to_string(&mut s)
-        .await
-        .map_err(|e| format!("结果读取失败: {}", e))?;
+        let mut s = String::new();
+        let stream = channel.into_stream();
+        let (mut read_half, _) = tokio::io::split(stream);
+        read_half
+            .read_to_string(&mut s)
+            .await
+            .map_err(|e| format!("结果读取失败: {}", e))?;
+        Ok(s)
+    }
+    .await;
+
+    // 🟢 测试结束后与正式连接一样先断开目标会话，再拆除跳板链
+    let _ = sess
+        .disconnect(russh::Disconnect::ByApplication, "PiTerm connection test", "en")
+        .await;
+    disconnect_jump_sessions(jump_sessions, "PiTerm connection test").await;
 
-    Ok(format!("连接成功! 用户: {}", s.trim()))
+    result.map(|s: String| format!("连接成功! 用户: {}", s.trim()))
 }
 
 #[tauri::command]
@@ -464,6 +647,7 @@ pub async fn quick_connect(
     password: Option<String>,
     private_key: Option<String>,
     passphrase: Option<String>,
+    host_key_policy: Option<HostKeyPolicy>,
 ) -> Result<(), String> {
     let sessions = state.sessions.clone();
     let session_id = id;
@@ -494,10 +678,24 @@ pub async fn quick_connect(
 
         password_id: None,
         password_source: None,
+        certificate: None,
+        use_agent: false,
         connect_timeout: Some(10),
         keep_alive_interval: Some(15),
         auto_reconnect: Some(false),
         max_reconnects: Some(0),
+        // 未指定时与已保存服务器一致：未知主机密钥走 check_host_key / trust_host_key 确认
+        host_key_policy: Some(host_key_policy.unwrap_or_default()),
+        jump_hosts: Vec::new(),
+        agent_forwarding: false,
+        agent_forward_keys: Vec::new(),
+        split_transport: false,
+        encoding: None,
+        record_sessions: false,
+        record_input: false,
+        session_log: None,
+        persistent_session: None,
+        shell_options: None,
     };
     ssh_log::info(
         SshLogRecord::new(
@@ -529,7 +727,7 @@ pub async fn quick_connect(
         let _ = conn.shutdown("PiTerm replaced session");
     }
 
-    let (shell_sess, shell_channel) = create_shell_channel(&config, Some(&session_id)).await
+    let (shell_sess, shell_channel, shell_jump_sessions) = create_shell_channel(&app, &config, Some(&session_id), None, "shell").await
         .map_err(|e| {
             let err = format!("Shell Connection Failed: {}", e);
             ssh_log::error(
@@ -545,13 +743,12 @@ pub async fn quick_connect(
             err
         })?;
 
-    let shell_sess = Arc::new(shell_sess);
     let shell_channel_id = shell_channel.id();
     let (shell_write_tx, shell_write_rx) = tokio::sync::mpsc::channel(SSH_WRITE_QUEUE_CAPACITY);
     let (shell_resize_tx, shell_resize_rx) = tokio::sync::mpsc::channel(SSH_WRITE_QUEUE_CAPACITY);
     let connection = SshConnection::new(
         config.clone(),
-        shell_sess.clone(),
+        SshTransport::new(shell_sess, shell_jump_sessions),
         shell_channel_id,
         shell_write_tx,
         shell_resize_tx,
@@ -592,7 +789,7 @@ pub async fn quick_connect(
         connection_instance_id,
         shell_resize_rx,
     );
-    spawn_background_session_connector(
+    start_background_session(
         app,
         sessions.clone(),
         config.clone(),
@@ -611,7 +808,10 @@ pub async fn quick_connect(
         .server_id("quick_connect")
         .instance_id(connection_instance_id)
         .field("active_session_count", active_sessions)
-        .field("background_status", "connecting"),
+        .field(
+            "background_status",
+            if config.split_transport { "connecting" } else { "shared" },
+        ),
     );
 
     Ok(())
@@ -640,6 +840,10 @@ pub fn handle_auto_reconnect(
 
         let config = conn.config.clone();
         let max_attempts = config.max_reconnects.unwrap_or(3);
+        // 释放旧连接占用的本地端口，重连成功后按原规则恢复
+        let forward_rules = conn.stop_port_forwards();
+        // 🟢 持久会话模式：重连后附加回同一个 tmux/screen 会话，保留正在运行的任务
+        let mut persistent = conn.persistent_shell();
 
         let _ = app.emit(
             &format!("term-data-{}", session_id),
@@ -665,25 +869,32 @@ pub fn handle_auto_reconnect(
                 format!("\x1b[33m[PiTerm] Reconnecting (attempt {}/{})...\x1b[0m\r\n", attempt, max_attempts),
             );
 
-            match create_shell_channel(&config, Some(&session_id)).await {
-                Ok((shell_sess, shell_channel)) => {
+            // 自动重连无人值守，不弹出 keyboard-interactive 提示
+            match create_shell_channel(&app, &config, Some(&session_id), persistent.as_mut(), "reconnect").await {
+                Ok((shell_sess, shell_channel, shell_jump_sessions)) => {
                     let old_conn = remove_ssh_session(&sessions, &session_id);
+                    // 🟢 录像随会话延续，重连前后写入同一个文件
+                    let recording = old_conn.as_ref().and_then(|c| c.take_recording());
                     if let Some(c) = old_conn {
                         let _ = c.shutdown("PiTerm auto-reconnect replaced session");
                     }
 
-                    let shell_sess = Arc::new(shell_sess);
                     let shell_channel_id = shell_channel.id();
                     let (shell_write_tx, shell_write_rx) = tokio::sync::mpsc::channel(SSH_WRITE_QUEUE_CAPACITY);
                     let (shell_resize_tx, shell_resize_rx) = tokio::sync::mpsc::channel(SSH_WRITE_QUEUE_CAPACITY);
                     let new_conn = SshConnection::new(
                         config.clone(),
-                        shell_sess.clone(),
+                        SshTransport::new(shell_sess, shell_jump_sessions),
                         shell_channel_id,
                         shell_write_tx,
                         shell_resize_tx,
                     );
                     let new_instance_id = new_conn.instance_id;
+                    new_conn.set_persistent_shell(persistent.clone());
+                    new_conn.set_cwd_sync(conn.cwd_sync_enabled());
+                    if let Some(recorder) = recording {
+                        let _ = new_conn.attach_recording(recorder);
+                    }
 
                     {
                         let mut map = match sessions.lock() {
@@ -712,7 +923,7 @@ pub fn handle_auto_reconnect(
                         shell_resize_rx,
                     );
 
-                    spawn_background_session_connector(
+                    start_background_session(
                         app.clone(),
                         sessions.clone(),
                         config.clone(),
@@ -721,10 +932,23 @@ pub fn handle_auto_reconnect(
                         new_instance_id,
                     );
 
-                    let _ = app.emit(
-                        &format!("term-data-{}", session_id),
-                        "\x1b[32m[PiTerm] Connection re-established successfully!\x1b[0m\r\n\r\n".to_string(),
+                    spawn_port_forward_restore(
+                        app.clone(),
+                        sessions.clone(),
+                        session_id.clone(),
+                        new_instance_id,
+                        forward_rules,
                     );
+
+                    let message = match persistent.as_ref() {
+                        Some(persistent) => format!(
+                            "\x1b[32m[PiTerm] Connection re-established, reattached {} session '{}'.\x1b[0m\r\n\r\n",
+                            persistent.program(),
+                            persistent.name
+                        ),
+                        None => "\x1b[32m[PiTerm] Connection re-established successfully!\x1b[0m\r\n\r\n".to_string(),
+                    };
+                    let _ = app.emit(&format!("term-data-{}", session_id), message);
                     return;
                 }
                 Err(err) => {
@@ -0,0 +1,51 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use tauri::State;
+
+use crate::utils::ssh_log::{self, SshLogRecord};
+
+use super::core::SHELL_INTEGRATION_SCRIPT;
+use super::runtime::queue_shell_write;
+use super::state::SshState;
+
+// 🟢 bash/zsh 集成脚本原文，供用户加入远端 ~/.bashrc 或 ~/.zshrc 长期启用
+#[tauri::command]
+pub fn get_shell_integration_script() -> String {
+    SHELL_INTEGRATION_SCRIPT.to_string()
+}
+
+// 🟢 向当前 shell 注入集成脚本：以 base64 单行 eval 输入，前导空格避免进入远端历史 (HISTCONTROL=ignorespace)
+#[tauri::command]
+pub async fn inject_shell_integration(
+    state: State<'_, SshState>,
+    id: String,
+) -> Result<(), String> {
+    let conn = {
+        let map = state.sessions.lock().map_err(|e| e.to_string())?;
+        map.get(&id)
+            .cloned()
+            .ok_or_else(|| "SSH connection not active".to_string())?
+    };
+    if !conn.shell_is_active() {
+        return Err("SSH shell not active".to_string());
+    }
+    conn.touch_client_heartbeat();
+
+    // macOS 旧版 base64 只支持 -D
+    let input = format!(
+        " __piterm_si='{}'; eval \"$(printf '%s' \"$__piterm_si\" | base64 -d 2>/dev/null || printf '%s' \"$__piterm_si\" | base64 -D)\"; unset __piterm_si\r",
+        BASE64.encode(SHELL_INTEGRATION_SCRIPT)
+    );
+    queue_shell_write(conn.shell_write_tx.clone(), input).await?;
+
+    ssh_log::info(
+        SshLogRecord::new(
+            "ssh.shell_integration",
+            "injected",
+            "Injected shell integration script into remote shell",
+        )
+        .session_id(id)
+        .server_id(conn.config.id.clone())
+        .instance_id(conn.instance_id),
+    );
+    Ok(())
+}
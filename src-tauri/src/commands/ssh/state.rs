@@ -1,9 +1,18 @@
 use crate::utils::ssh_log::{self, SshLogRecord};
-use crate::commands::ssh::core::PiTermClientHandler;
-use crate::models::SshConfig;
+use crate::commands::recording::SessionRecorder;
+use crate::commands::ssh::core::{
+    PersistentShell, PiTermClientHandler, TransportLink, ScrollbackBuffer, ScrollbackLines, SHELL_SCROLLBACK_LIMIT_BYTES,
+    SHELL_SCROLLBACK_MAX_LINES, SHELL_SCROLLBACK_TEXT_LIMIT_BYTES, TerminalTransferDirection,
+    TerminalTransferProtocol, TransferSelection, TriggerEngine, TriggerMatch,
+};
+use crate::commands::ssh::forwarding::PortForwardMap;
+use crate::models::{
+    PortForwardRule, SshConfig, TriggerAction, TriggerRule, DEFAULT_PTY_COLS, DEFAULT_PTY_ROWS,
+};
 use russh::client;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
@@ -18,6 +27,8 @@ static NEXT_SSH_CONNECTION_INSTANCE_ID: AtomicU64 = AtomicU64::new(1);
 
 pub struct SshWriteRequest {
     pub data: String,
+    // 🟢 密码等敏感输入：照常写入 shell，但不进入录像
+    pub secret: bool,
     pub result_tx: oneshot::Sender<Result<(), String>>,
 }
 
@@ -27,14 +38,116 @@ pub struct SshResizeRequest {
     pub result_tx: oneshot::Sender<Result<(), String>>,
 }
 
-pub type SshSession = client::Handle<PiTermClientHandler>;
+// 🟢 已认证的 russh Handle，附带 handler 的传输身份
+pub struct SshSession {
+    handle: client::Handle<PiTermClientHandler>,
+    link: Arc<TransportLink>,
+}
+
+impl SshSession {
+    pub fn new(handle: client::Handle<PiTermClientHandler>, link: Arc<TransportLink>) -> Self {
+        Self { handle, link }
+    }
+
+    pub fn transport_id(&self) -> u64 {
+        self.link.id
+    }
+}
+
+impl Deref for SshSession {
+    type Target = client::Handle<PiTermClientHandler>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.handle
+    }
+}
+
+impl DerefMut for SshSession {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.handle
+    }
+}
+
+// 🟢 主会话/后台会话放入共享锁，并回填 handler 的传输链接
+fn share_session(session: SshSession) -> SharedSshSession {
+    let link = session.link.clone();
+    let shared = Arc::new(tokio::sync::RwLock::new(session));
+    link.bind(&shared);
+    shared
+}
+
+// 🟢 打开通道只需 &Handle，走读锁可并发；tcpip-forward 等全局请求需要 &mut Handle，才取写锁
+pub type SharedSshSession = Arc<tokio::sync::RwLock<SshSession>>;
+
+// 🟢 ProxyJump 跳板会话，按连接顺序保存 (第一跳在前)
+pub type JumpSessions = Vec<Arc<SshSession>>;
+
+// 由近到远逆序断开：先断目标侧的跳板，再断第一跳
+pub async fn disconnect_jump_sessions(jump_sessions: JumpSessions, reason: &str) {
+    for jump_session in jump_sessions.into_iter().rev() {
+        let _ = jump_session
+            .disconnect(russh::Disconnect::ByApplication, reason, "en")
+            .await;
+    }
+}
+
+// 🟢 已认证的 SSH 传输，可被多个标签页 (复制标签页) 共享，最后一个使用者释放时才断开
+pub struct SshTransport {
+    // 远程转发按传输登记，handler 收到 forwarded-tcpip 通道时据此查找
+    pub id: u64,
+    pub session: SharedSshSession,
+    jump_sessions: Mutex<JumpSessions>,
+    users: AtomicUsize,
+}
+
+impl SshTransport {
+    pub fn new(session: SshSession, jump_sessions: JumpSessions) -> Arc<Self> {
+        Arc::new(Self {
+            id: session.transport_id(),
+            session: share_session(session),
+            jump_sessions: Mutex::new(jump_sessions),
+            users: AtomicUsize::new(1),
+        })
+    }
+
+    fn acquire(self: &Arc<Self>) -> Arc<Self> {
+        self.users.fetch_add(1, Ordering::SeqCst);
+        self.clone()
+    }
+
+    fn release(&self, reason: &str) {
+        if self.users.fetch_sub(1, Ordering::SeqCst) != 1 {
+            return;
+        }
+
+        let session = self.session.clone();
+        let jump_sessions = match self.jump_sessions.lock() {
+            Ok(mut slot) => std::mem::take(&mut *slot),
+            Err(poisoned) => std::mem::take(&mut *poisoned.into_inner()),
+        };
+        let reason = reason.to_string();
+        tokio::spawn(async move {
+            let _ = session.read().await.disconnect(russh::Disconnect::ByApplication, &reason, "en").await;
+            // 🟢 目标会话断开后再拆除跳板链
+            disconnect_jump_sessions(jump_sessions, &reason).await;
+        });
+    }
+
+    pub fn user_count(&self) -> usize {
+        self.users.load(Ordering::Relaxed)
+    }
+}
 
 #[derive(Clone)]
 pub struct SshConnection {
     pub instance_id: u64,
     pub config: SshConfig,
-    pub shell_session: Arc<SshSession>,
-    pub bg_session: Arc<Mutex<Option<Arc<SshSession>>>>,
+    pub transport: Arc<SshTransport>,
+    transport_released: Arc<AtomicBool>,
+    // 🟢 单传输模式：后台 (监控 exec / SFTP) 与 shell 复用同一传输；拆分模式另建后台传输
+    pub multiplexed: bool,
+    pub bg_session: Arc<Mutex<Option<SharedSshSession>>>,
+    pub bg_jump_sessions: Arc<Mutex<JumpSessions>>,
     pub sftp_session: Arc<Mutex<Option<Arc<russh_sftp::client::SftpSession>>>>,
     pub shell_channel_id: russh::ChannelId,
     pub shell_write_tx: mpsc::Sender<SshWriteRequest>,
@@ -43,31 +156,285 @@ pub struct SshConnection {
     pub bg_connecting: Arc<AtomicBool>,
     pub shutdown_complete: Arc<AtomicBool>,
     pub last_client_heartbeat: Arc<Mutex<Instant>>,
-    pub output_history: Arc<Mutex<String>>,
+    pub output_history: Arc<Mutex<ScrollbackBuffer>>,
+    // 🟢 去除控制序列、按行索引的回滚，供 UI 与 Agent 分页/搜索
+    pub scrollback: Arc<Mutex<ScrollbackLines>>,
+    // 🟢 前端请求原始字节 (term-bytes-*) 时开启
+    pub raw_output: Arc<AtomicBool>,
+    // 🟢 当前 PTY 尺寸 (cols, rows)，录像头部使用
+    pub pty_size: Arc<Mutex<(u32, u32)>>,
+    pub recording: Arc<Mutex<Option<SessionRecorder>>>,
+    // 🟢 该 PiTerm 会话绑定的远端 tmux/screen 会话名，重连时据此重新附加
+    pub persistent_shell: Arc<Mutex<Option<PersistentShell>>>,
+    // 🟢 shell 集成 (OSC 7) 上报的远端工作目录；开启 cwd_sync 时 SFTP 面板跟随该目录
+    pub cwd: Arc<Mutex<Option<String>>>,
+    pub cwd_sync: Arc<AtomicBool>,
+    // 🟢 收到过 OSC 133 标记即视为启用了 shell 集成；133;C 到 133;D 之间有前台命令在运行
+    pub shell_integration_active: Arc<AtomicBool>,
+    pub foreground_command_running: Arc<AtomicBool>,
+    // 🟢 终端内 ZMODEM/trzsz 传输接管 shell 通道期间为 true，此时拒绝终端输入
+    pub file_transfer_active: Arc<AtomicBool>,
+    // 🟢 该服务器的输出触发器 (自动应答)，由 shell 读取任务在输出时匹配
+    pub triggers: Arc<Mutex<TriggerEngine>>,
+    pub port_forwards: PortForwardMap,
 }
 
 impl SshConnection {
     pub fn new(
         config: SshConfig,
-        shell_session: Arc<SshSession>,
+        transport: Arc<SshTransport>,
         shell_channel_id: russh::ChannelId,
         shell_write_tx: mpsc::Sender<SshWriteRequest>,
         shell_resize_tx: mpsc::Sender<SshResizeRequest>,
     ) -> Self {
+        let multiplexed = !config.split_transport;
+        let pty_size = config
+            .shell_options
+            .as_ref()
+            .map(|options| (options.cols, options.rows))
+            .unwrap_or((DEFAULT_PTY_COLS, DEFAULT_PTY_ROWS));
+        let bg_session = multiplexed.then(|| transport.session.clone());
         Self {
             instance_id: NEXT_SSH_CONNECTION_INSTANCE_ID.fetch_add(1, Ordering::Relaxed),
             config,
-            shell_session,
-            bg_session: Arc::new(Mutex::new(None)),
+            transport,
+            transport_released: Arc::new(AtomicBool::new(false)),
+            multiplexed,
+            bg_connecting: Arc::new(AtomicBool::new(!multiplexed)),
+            bg_session: Arc::new(Mutex::new(bg_session)),
+            bg_jump_sessions: Arc::new(Mutex::new(Vec::new())),
             sftp_session: Arc::new(Mutex::new(None)),
             shell_channel_id,
             shell_write_tx,
             shell_resize_tx,
             shell_active: Arc::new(AtomicBool::new(true)),
-            bg_connecting: Arc::new(AtomicBool::new(true)),
             shutdown_complete: Arc::new(AtomicBool::new(false)),
             last_client_heartbeat: Arc::new(Mutex::new(Instant::now())),
-            output_history: Arc::new(Mutex::new(String::new())),
+            output_history: Arc::new(Mutex::new(ScrollbackBuffer::new(SHELL_SCROLLBACK_LIMIT_BYTES))),
+            scrollback: Arc::new(Mutex::new(ScrollbackLines::new(
+                SHELL_SCROLLBACK_MAX_LINES,
+                SHELL_SCROLLBACK_TEXT_LIMIT_BYTES,
+            ))),
+            raw_output: Arc::new(AtomicBool::new(false)),
+            pty_size: Arc::new(Mutex::new(pty_size)),
+            recording: Arc::new(Mutex::new(None)),
+            persistent_shell: Arc::new(Mutex::new(None)),
+            cwd: Arc::new(Mutex::new(None)),
+            cwd_sync: Arc::new(AtomicBool::new(false)),
+            shell_integration_active: Arc::new(AtomicBool::new(false)),
+            foreground_command_running: Arc::new(AtomicBool::new(false)),
+            file_transfer_active: Arc::new(AtomicBool::new(false)),
+            triggers: Arc::new(Mutex::new(TriggerEngine::default())),
+            port_forwards: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // 🟢 复制标签页：在同一传输上挂载新的 shell 通道，无需重新握手/认证
+    pub fn duplicate(
+        &self,
+        shell_channel_id: russh::ChannelId,
+        shell_write_tx: mpsc::Sender<SshWriteRequest>,
+        shell_resize_tx: mpsc::Sender<SshResizeRequest>,
+    ) -> Self {
+        Self::new(
+            self.config.clone(),
+            self.transport.acquire(),
+            shell_channel_id,
+            shell_write_tx,
+            shell_resize_tx,
+        )
+    }
+
+    fn release_transport(&self, reason: &str) {
+        if !self.transport_released.swap(true, Ordering::SeqCst) {
+            self.transport.release(reason);
+        }
+    }
+
+    fn take_bg_jump_sessions(&self) -> JumpSessions {
+        match self.bg_jump_sessions.lock() {
+            Ok(mut slot) => std::mem::take(&mut *slot),
+            Err(poisoned) => std::mem::take(&mut *poisoned.into_inner()),
+        }
+    }
+
+    // 停止全部端口转发，返回其规则以便重连后恢复
+    pub fn stop_port_forwards(&self) -> Vec<PortForwardRule> {
+        let drained: Vec<_> = match self.port_forwards.lock() {
+            Ok(mut forwards) => forwards.drain().map(|(_, forward)| forward).collect(),
+            Err(poisoned) => poisoned
+                .into_inner()
+                .drain()
+                .map(|(_, forward)| forward)
+                .collect(),
+        };
+        drained
+            .into_iter()
+            .map(|forward| {
+                forward.stop();
+                forward.rule
+            })
+            .collect()
+    }
+
+    pub fn raw_output_enabled(&self) -> bool {
+        self.raw_output.load(Ordering::Relaxed)
+    }
+
+    pub fn set_raw_output(&self, enabled: bool) {
+        self.raw_output.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn pty_size(&self) -> (u32, u32) {
+        match self.pty_size.lock() {
+            Ok(size) => *size,
+            Err(poisoned) => *poisoned.into_inner(),
+        }
+    }
+
+    fn with_recorder(&self, f: impl FnOnce(&mut SessionRecorder)) {
+        let mut slot = match self.recording.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        if let Some(recorder) = slot.as_mut() {
+            f(recorder);
+        }
+    }
+
+    pub fn record_output(&self, text: &str) {
+        self.with_recorder(|recorder| recorder.record_output(text));
+    }
+
+    pub fn record_input(&self, text: &str) {
+        self.with_recorder(|recorder| recorder.record_input(text));
+    }
+
+    pub fn record_resize(&self, cols: u32, rows: u32) {
+        match self.pty_size.lock() {
+            Ok(mut size) => *size = (cols, rows),
+            Err(poisoned) => *poisoned.into_inner() = (cols, rows),
+        }
+        self.with_recorder(|recorder| recorder.record_resize(cols, rows));
+    }
+
+    pub fn recording_id(&self) -> Option<String> {
+        match self.recording.lock() {
+            Ok(slot) => slot.as_ref().map(|r| r.id().to_string()),
+            Err(poisoned) => poisoned.into_inner().as_ref().map(|r| r.id().to_string()),
+        }
+    }
+
+    // 已在录制时原样退回新建的录像，由调用方丢弃
+    pub fn attach_recording(&self, recorder: SessionRecorder) -> Result<(), SessionRecorder> {
+        let mut slot = match self.recording.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        if slot.is_some() {
+            return Err(recorder);
+        }
+        *slot = Some(recorder);
+        Ok(())
+    }
+
+    // 🟢 取出录像而不结束，自动重连时移交给新连接以保持同一个录像文件
+    pub fn take_recording(&self) -> Option<SessionRecorder> {
+        match self.recording.lock() {
+            Ok(mut slot) => slot.take(),
+            Err(poisoned) => poisoned.into_inner().take(),
+        }
+    }
+
+    pub fn stop_recording(&self) -> Option<String> {
+        let recorder = self.take_recording()?;
+        let id = recorder.id().to_string();
+        recorder.finish();
+        Some(id)
+    }
+
+    pub fn persistent_shell(&self) -> Option<PersistentShell> {
+        match self.persistent_shell.lock() {
+            Ok(slot) => slot.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    pub fn set_persistent_shell(&self, persistent: Option<PersistentShell>) {
+        match self.persistent_shell.lock() {
+            Ok(mut slot) => *slot = persistent,
+            Err(poisoned) => *poisoned.into_inner() = persistent,
+        }
+    }
+
+    pub fn cwd(&self) -> Option<String> {
+        match self.cwd.lock() {
+            Ok(slot) => slot.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    // 返回 true 表示目录发生变化
+    pub fn update_cwd(&self, cwd: &str) -> bool {
+        let mut slot = match self.cwd.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        if slot.as_deref() == Some(cwd) {
+            return false;
+        }
+        *slot = Some(cwd.to_string());
+        true
+    }
+
+    pub fn cwd_sync_enabled(&self) -> bool {
+        self.cwd_sync.load(Ordering::Relaxed)
+    }
+
+    pub fn set_cwd_sync(&self, enabled: bool) {
+        self.cwd_sync.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn shell_integration_active(&self) -> bool {
+        self.shell_integration_active.load(Ordering::Relaxed)
+    }
+
+    pub fn set_shell_integration_state(&self, active: bool, command_running: bool) {
+        self.shell_integration_active.store(active, Ordering::Relaxed);
+        self.foreground_command_running
+            .store(command_running, Ordering::Relaxed);
+    }
+
+    // 🟢 向 shell 键入命令前检查：未启用 shell 集成时无法判断，按空闲处理
+    pub fn ensure_shell_idle(&self) -> Result<(), String> {
+        if self.foreground_command_running.load(Ordering::Relaxed) {
+            return Err(
+                "COMMAND_RUNNING: A command is running in this terminal; wait for the shell prompt"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    pub fn file_transfer_active(&self) -> bool {
+        self.file_transfer_active.load(Ordering::Relaxed)
+    }
+
+    pub fn set_file_transfer_active(&self, active: bool) {
+        self.file_transfer_active.store(active, Ordering::SeqCst);
+    }
+
+    pub fn set_triggers(&self, rules: Vec<TriggerRule>) {
+        match self.triggers.lock() {
+            Ok(mut engine) => engine.set_rules(rules),
+            Err(poisoned) => poisoned.into_inner().set_rules(rules),
+        }
+    }
+
+    pub fn match_triggers(&self, text: &str) -> Vec<TriggerMatch> {
+        match self.triggers.lock() {
+            Ok(mut engine) => engine.push(text),
+            Err(poisoned) => poisoned.into_inner().push(text),
         }
     }
 
@@ -92,7 +459,7 @@ impl SshConnection {
         }
     }
 
-    pub fn bg_session_arc(&self) -> Option<Arc<SshSession>> {
+    pub fn bg_session_arc(&self) -> Option<SharedSshSession> {
         match self.bg_session.lock() {
             Ok(slot) => slot.clone(),
             Err(poisoned) => poisoned.into_inner().clone(),
@@ -118,17 +485,25 @@ impl SshConnection {
         self.bg_connecting.store(false, Ordering::SeqCst);
     }
 
-    pub fn install_bg_session(&self, session: Arc<SshSession>) {
+    pub fn install_bg_session(&self, session: SshSession, jump_sessions: JumpSessions) {
+        let session = share_session(session);
         let previous = match self.bg_session.lock() {
             Ok(mut slot) => slot.replace(session),
             Err(poisoned) => poisoned.into_inner().replace(session),
         };
+        let previous_jumps = match self.bg_jump_sessions.lock() {
+            Ok(mut slot) => std::mem::replace(&mut *slot, jump_sessions),
+            Err(poisoned) => std::mem::replace(&mut *poisoned.into_inner(), jump_sessions),
+        };
 
         self.bg_connecting.store(false, Ordering::SeqCst);
 
-        if let Some(previous_session) = previous {
+        if previous.is_some() || !previous_jumps.is_empty() {
             tokio::spawn(async move {
-                let _ = previous_session.disconnect(russh::Disconnect::ByApplication, "PiTerm background session replaced", "en").await;
+                if let Some(previous_session) = previous {
+                    let _ = previous_session.read().await.disconnect(russh::Disconnect::ByApplication, "PiTerm background session replaced", "en").await;
+                }
+                disconnect_jump_sessions(previous_jumps, "PiTerm background session replaced").await;
             });
         }
     }
@@ -154,7 +529,7 @@ impl SshConnection {
         }
     }
 
-    fn take_bg_session(&self) -> Option<Arc<SshSession>> {
+    fn take_bg_session(&self) -> Option<SharedSshSession> {
         match self.bg_session.lock() {
             Ok(mut slot) => slot.take(),
             Err(poisoned) => poisoned.into_inner().take(),
@@ -171,11 +546,10 @@ impl SshConnection {
             return false;
         }
 
-        let shell_session = self.shell_session.clone();
-
-        tokio::spawn(async move {
-            let _ = shell_session.disconnect(russh::Disconnect::ByApplication, "PiTerm shell closed", "en").await;
-        });
+        // 单传输模式下传输仍承载后台通道，待会话关闭时再释放
+        if !self.multiplexed {
+            self.release_transport("PiTerm shell closed");
+        }
 
         true
     }
@@ -190,15 +564,22 @@ impl SshConnection {
         }
 
         self.clear_sftp_session();
-        let shell_session = self.shell_session.clone();
+        self.stop_port_forwards();
+        self.stop_recording();
+        self.release_transport(disconnect_reason);
+        if self.multiplexed {
+            return true;
+        }
+
         let bg_session = self.take_bg_session();
+        let bg_jumps = self.take_bg_jump_sessions();
         let reason_str = disconnect_reason.to_string();
 
         tokio::spawn(async move {
-            let _ = shell_session.disconnect(russh::Disconnect::ByApplication, &reason_str, "en").await;
             if let Some(bg_sess) = bg_session {
-                let _ = bg_sess.disconnect(russh::Disconnect::ByApplication, &reason_str, "en").await;
+                let _ = bg_sess.read().await.disconnect(russh::Disconnect::ByApplication, &reason_str, "en").await;
             }
+            disconnect_jump_sessions(bg_jumps, &reason_str).await;
         });
 
         true
@@ -386,6 +767,88 @@ pub struct HostKeyVerificationCache {
     pub entries: Arc<Mutex<HashMap<String, PendingHostKey>>>,
 }
 
+// 🟢 keyboard-interactive 认证：等待前端回答的提示
+pub struct PendingAuthPrompt {
+    pub prompt_count: usize,
+    pub response_tx: oneshot::Sender<Option<Vec<String>>>,
+}
+
+#[derive(Default)]
+pub struct AuthPromptRegistry {
+    pub pending: Arc<Mutex<HashMap<String, PendingAuthPrompt>>>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthPromptItem {
+    pub prompt: String,
+    pub echo: bool,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthPromptEvent {
+    pub request_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub server_id: String,
+    pub host: String,
+    pub username: String,
+    pub name: String,
+    pub instructions: String,
+    pub prompts: Vec<AuthPromptItem>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthPromptClosedEvent {
+    pub request_id: String,
+    pub reason: String,
+}
+
+// 🟢 终端内 ZMODEM/trzsz 传输：等待前端选择本地路径；None 表示用户取消
+pub struct PendingTerminalTransfer {
+    pub direction: TerminalTransferDirection,
+    pub response_tx: oneshot::Sender<Option<TransferSelection>>,
+}
+
+#[derive(Default)]
+pub struct TerminalTransferRegistry {
+    pub pending: Arc<Mutex<HashMap<String, PendingTerminalTransfer>>>,
+    // 进行中的传输及其取消标记
+    pub active: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalTransferRequestEvent {
+    pub transfer_id: String,
+    pub protocol: TerminalTransferProtocol,
+    pub direction: TerminalTransferDirection,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalTransferFileEvent {
+    pub transfer_id: String,
+    // 从 1 开始
+    pub index: usize,
+    pub name: String,
+    pub local_path: String,
+    pub size: u64,
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalTransferEndEvent {
+    pub transfer_id: String,
+    // completed / cancelled / failed
+    pub status: String,
+    pub files: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 #[derive(Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TerminalExitEvent {
@@ -393,6 +856,27 @@ pub struct TerminalExitEvent {
     pub reason: String,
 }
 
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalCwdEvent {
+    pub cwd: String,
+}
+
+// 🟢 term-trigger-{id}：输出触发器命中 (发送类动作不包含发送内容)
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerFiredEvent {
+    pub rule_id: String,
+    pub name: Option<String>,
+    pub action: TriggerAction,
+    pub matched: String,
+    pub rate_limited: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 #[derive(Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BackgroundSessionEvent {
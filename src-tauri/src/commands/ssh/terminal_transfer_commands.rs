@@ -0,0 +1,85 @@
+use std::sync::atomic::Ordering;
+
+use tauri::State;
+
+use crate::utils::ssh_log::{self, SshLogRecord};
+
+use super::core::prepare_transfer_selection;
+use super::state::TerminalTransferRegistry;
+
+// 🟢 前端回答 term-transfer-{id} 请求：下载传入一个保存目录，上传传入文件列表；paths 为 None 表示取消
+#[tauri::command]
+pub async fn respond_terminal_transfer(
+    registry: State<'_, TerminalTransferRegistry>,
+    transfer_id: String,
+    paths: Option<Vec<String>>,
+) -> Result<(), String> {
+    let pending = {
+        let mut pending = match registry.pending.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        let entry = pending
+            .get(&transfer_id)
+            .ok_or_else(|| "File transfer request expired or already answered.".to_string())?;
+        let selection = paths
+            .as_deref()
+            .map(|paths| prepare_transfer_selection(entry.direction, paths))
+            .transpose()?;
+        pending.remove(&transfer_id).map(|entry| (entry, selection))
+    }
+    .ok_or_else(|| "File transfer request expired or already answered.".to_string())?;
+
+    ssh_log::debug(
+        SshLogRecord::new(
+            "ssh.transfer",
+            "request_answered",
+            "Received local paths for in-terminal file transfer",
+        )
+        .field("transfer_id", transfer_id)
+        .field("cancelled", paths.is_none()),
+    );
+
+    let (entry, selection) = pending;
+    entry
+        .response_tx
+        .send(selection)
+        .map_err(|_| "File transfer is no longer waiting for local paths.".to_string())
+}
+
+// 🟢 取消终端内传输：仍在等待选择路径时等同于取消选择，传输中则通知远端中止
+#[tauri::command]
+pub fn cancel_terminal_transfer(
+    registry: State<'_, TerminalTransferRegistry>,
+    transfer_id: String,
+) -> Result<(), String> {
+    let pending = {
+        let mut pending = match registry.pending.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        pending.remove(&transfer_id)
+    };
+    if let Some(entry) = pending {
+        let _ = entry.response_tx.send(None);
+        return Ok(());
+    }
+
+    let active = match registry.active.lock() {
+        Ok(g) => g,
+        Err(p) => p.into_inner(),
+    };
+    let cancel = active
+        .get(&transfer_id)
+        .ok_or_else(|| "File transfer not found or already finished.".to_string())?;
+    cancel.store(true, Ordering::SeqCst);
+    ssh_log::info(
+        SshLogRecord::new(
+            "ssh.transfer",
+            "cancel_requested",
+            "Cancelling in-terminal file transfer",
+        )
+        .field("transfer_id", transfer_id),
+    );
+    Ok(())
+}
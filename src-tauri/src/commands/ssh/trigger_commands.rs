@@ -0,0 +1,252 @@
+use chrono::Utc;
+use serde_json::Value;
+use sqlx::{Pool, Sqlite};
+use tauri::{AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+use crate::commands::snippet::internal_get_snippet_code;
+use crate::commands::vault::{internal_get_secret, VaultState};
+use crate::models::{TriggerAction, TriggerRule};
+use crate::state::AppState;
+use crate::utils::ssh_log::{self, SshLogRecord};
+
+use super::core::{shell_input_lines, validate_trigger_rule, TriggerMatch};
+use super::runtime::{queue_shell_secret_write, queue_shell_write};
+use super::state::{SshConnection, SshState, TriggerFiredEvent};
+
+const TRIGGER_COLUMNS: &str = "id, server_id, name, pattern, action, payload, append_newline, \
+     cooldown_ms, enabled, created_at, updated_at";
+
+// shell 读取任务启动时加载，规则变更后重新下发到该服务器的所有会话
+pub async fn load_enabled_triggers(
+    pool: &Pool<Sqlite>,
+    server_id: &str,
+) -> Result<Vec<TriggerRule>, String> {
+    sqlx::query_as::<_, TriggerRule>(&format!(
+        "SELECT {} FROM triggers WHERE server_id = ? AND enabled = 1 ORDER BY created_at ASC",
+        TRIGGER_COLUMNS
+    ))
+    .bind(server_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+async fn reload_server_triggers(
+    pool: &Pool<Sqlite>,
+    ssh_state: &SshState,
+    server_id: &str,
+) -> Result<(), String> {
+    let connections: Vec<SshConnection> = match ssh_state.sessions.lock() {
+        Ok(map) => map
+            .values()
+            .filter(|c| c.config.id == server_id)
+            .cloned()
+            .collect(),
+        Err(p) => p
+            .into_inner()
+            .values()
+            .filter(|c| c.config.id == server_id)
+            .cloned()
+            .collect(),
+    };
+    if connections.is_empty() {
+        return Ok(());
+    }
+    let rules = load_enabled_triggers(pool, server_id).await?;
+    for conn in connections {
+        conn.set_triggers(rules.clone());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_triggers(
+    app_state: State<'_, AppState>,
+    server_id: String,
+) -> Result<Vec<TriggerRule>, String> {
+    sqlx::query_as::<_, TriggerRule>(&format!(
+        "SELECT {} FROM triggers WHERE server_id = ? ORDER BY created_at ASC",
+        TRIGGER_COLUMNS
+    ))
+    .bind(server_id)
+    .fetch_all(&app_state.db)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn save_trigger(
+    app_state: State<'_, AppState>,
+    ssh_state: State<'_, SshState>,
+    mut rule: TriggerRule,
+) -> Result<TriggerRule, String> {
+    validate_trigger_rule(&rule)?;
+
+    let now = Utc::now().timestamp_millis();
+    if rule.id.is_empty() {
+        rule.id = Uuid::new_v4().to_string();
+    }
+    if rule.created_at == 0 {
+        rule.created_at = now;
+    }
+    rule.updated_at = now;
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO triggers (
+            id, server_id, name, pattern, action, payload, append_newline,
+            cooldown_ms, enabled, created_at, updated_at
+         ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&rule.id)
+    .bind(&rule.server_id)
+    .bind(&rule.name)
+    .bind(&rule.pattern)
+    .bind(rule.action)
+    .bind(&rule.payload)
+    .bind(rule.append_newline)
+    .bind(rule.cooldown_ms)
+    .bind(rule.enabled)
+    .bind(rule.created_at)
+    .bind(rule.updated_at)
+    .execute(&app_state.db)
+    .await
+    .map_err(|e| format!("保存触发器失败: {}", e))?;
+
+    reload_server_triggers(&app_state.db, &ssh_state, &rule.server_id).await?;
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn delete_trigger(
+    app_state: State<'_, AppState>,
+    ssh_state: State<'_, SshState>,
+    id: String,
+) -> Result<(), String> {
+    let server_id: Option<String> =
+        sqlx::query_scalar("SELECT server_id FROM triggers WHERE id = ?")
+            .bind(&id)
+            .fetch_optional(&app_state.db)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    sqlx::query("DELETE FROM triggers WHERE id = ?")
+        .bind(id)
+        .execute(&app_state.db)
+        .await
+        .map_err(|e| format!("删除失败: {}", e))?;
+
+    if let Some(server_id) = server_id {
+        reload_server_triggers(&app_state.db, &ssh_state, &server_id).await?;
+    }
+    Ok(())
+}
+
+async fn resolve_trigger_secret(app: &AppHandle, secret_id: &str) -> Result<String, String> {
+    let vault_state = app.state::<VaultState>();
+    let master_key = {
+        let guard = match vault_state.0.lock() {
+            Ok(g) => g,
+            Err(p) => p.into_inner(),
+        };
+        guard
+            .as_ref()
+            .cloned()
+            .ok_or("VAULT_LOCKED: Please unlock the vault first.")?
+    };
+    let db = app.state::<AppState>().db.clone();
+    let decrypted = internal_get_secret(&db, &master_key, secret_id).await?;
+    Ok(match serde_json::from_str::<Value>(&decrypted) {
+        Ok(parsed) => match parsed.get("val").and_then(|v| v.as_str()) {
+            Some(val) => val.to_string(),
+            None => decrypted,
+        },
+        Err(_) => decrypted,
+    })
+}
+
+async fn resolve_trigger_snippet(app: &AppHandle, snippet_id: &str) -> Result<String, String> {
+    let db = app.state::<AppState>().db.clone();
+    let code = internal_get_snippet_code(&db, snippet_id).await?;
+    Ok(shell_input_lines(&code))
+}
+
+// 🟢 执行命中的触发器动作；密码只写入 shell，不进入事件、日志与录像
+pub(crate) fn spawn_trigger_action(
+    app: &AppHandle,
+    conn: &SshConnection,
+    session_id: &str,
+    found: TriggerMatch,
+) {
+    let app = app.clone();
+    let write_tx = conn.shell_write_tx.clone();
+    let server_id = conn.config.id.clone();
+    let session_id = session_id.to_string();
+    tokio::spawn(async move {
+        let rule = found.rule;
+        let result = if found.rate_limited {
+            Ok(None)
+        } else {
+            let input = match rule.action {
+                TriggerAction::SendText => Ok(Some(rule.payload.clone())),
+                TriggerAction::SendSecret => {
+                    resolve_trigger_secret(&app, &rule.payload).await.map(Some)
+                }
+                TriggerAction::RunSnippet => {
+                    resolve_trigger_snippet(&app, &rule.payload).await.map(Some)
+                }
+                TriggerAction::Notify => Ok(None),
+            };
+            match input {
+                Ok(Some(mut input)) => {
+                    if rule.append_newline {
+                        input.push('\r');
+                    }
+                    let written = if rule.action == TriggerAction::SendSecret {
+                        queue_shell_secret_write(write_tx, input).await
+                    } else {
+                        queue_shell_write(write_tx, input).await
+                    };
+                    written.map(|_| None)
+                }
+                Ok(None) => {
+                    Ok((rule.action == TriggerAction::Notify).then(|| rule.payload.clone()))
+                }
+                Err(err) => Err(err),
+            }
+        };
+
+        let record = SshLogRecord::new("ssh.trigger", "fired", "Output trigger matched")
+            .session_id(session_id.clone())
+            .server_id(server_id)
+            .field("trigger_id", rule.id.clone())
+            .field("action", format!("{:?}", rule.action))
+            .field("rate_limited", found.rate_limited);
+        let (message, error) = match result {
+            Ok(message) => {
+                if found.rate_limited {
+                    ssh_log::warn(record);
+                } else {
+                    ssh_log::debug(record);
+                }
+                (message.filter(|m| !m.is_empty()), None)
+            }
+            Err(err) => {
+                ssh_log::warn(record.field("error", err.clone()));
+                (None, Some(err))
+            }
+        };
+        let _ = app.emit(
+            &format!("term-trigger-{}", session_id),
+            TriggerFiredEvent {
+                rule_id: rule.id,
+                name: rule.name,
+                action: rule.action,
+                matched: found.matched,
+                rate_limited: found.rate_limited,
+                message,
+                error,
+            },
+        );
+    });
+}
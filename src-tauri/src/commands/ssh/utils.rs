@@ -1,5 +1,6 @@
 use crate::models::SshConfig;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use encoding_rs::{Encoding, UTF_8};
 use tauri::{AppHandle, Emitter};
 
 pub fn emit_ssh_log(app: &AppHandle, msg: &str) {
@@ -61,7 +62,9 @@ pub fn clean_private_key(raw_key: &str) -> String {
 }
 
 pub fn auth_method_label(config: &SshConfig) -> &'static str {
-    if config
+    if config.use_agent {
+        "agent"
+    } else if config
         .private_key
         .as_deref()
         .map(|value| !value.trim().is_empty())
@@ -79,3 +82,32 @@ pub fn auth_method_label(config: &SshConfig) -> &'static str {
         "unknown"
     }
 }
+
+// 🟢 服务器编码设置 → encoding_rs 编码，未设置或无法识别时按 UTF-8 处理
+pub fn resolve_encoding(label: Option<&str>) -> &'static Encoding {
+    label
+        .map(str::trim)
+        .filter(|label| !label.is_empty())
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .filter(|encoding| encoding.output_encoding() == *encoding)
+        .unwrap_or(UTF_8)
+}
+
+// 保存前校验编码标签，统一为 WHATWG 规范名 (latin1 → windows-1252)
+pub fn normalize_encoding_label(label: Option<&str>) -> Result<Option<String>, String> {
+    let Some(label) = label.map(str::trim).filter(|label| !label.is_empty()) else {
+        return Ok(None);
+    };
+    match Encoding::for_label(label.as_bytes()) {
+        // UTF-16 等无法双向转换的编码不支持
+        Some(encoding) if encoding.output_encoding() == encoding => {
+            Ok(Some(encoding.name().to_ascii_lowercase()))
+        }
+        _ => Err(format!("Unsupported encoding: {}", label)),
+    }
+}
+
+// 单引号包裹路径/参数，内部单引号转义为 '\''
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
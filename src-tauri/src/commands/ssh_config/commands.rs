@@ -0,0 +1,638 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{Pool, Row, Sqlite};
+use tauri::{command, AppHandle, Manager, State};
+use uuid::Uuid;
+
+use crate::commands::vault::{
+    internal_add_secret, internal_update_secret, parse_certificate, store_certificate, VaultState,
+};
+use crate::models::{AuthType, HostKeyPolicy};
+use crate::state::AppState;
+
+use super::parser::{proxy_command_jump_host, ParsedSshConfig, SshConfigHost};
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SshConfigFieldChange {
+    pub field: String,
+    pub current: Option<String>,
+    pub imported: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SshConfigPreviewEntry {
+    pub alias: String,
+    // "new" | "changed" | "unchanged"
+    pub status: String,
+    pub server_id: Option<String>,
+    pub host: SshConfigHost,
+    pub identity_file: Option<String>,
+    pub jump_hosts: Vec<String>,
+    pub changes: Vec<SshConfigFieldChange>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SshConfigImportPreview {
+    pub source_path: String,
+    pub entries: Vec<SshConfigPreviewEntry>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SshConfigImportResult {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub imported_keys: usize,
+    pub warnings: Vec<String>,
+}
+
+// 数据库中已存在的服务器 (仅导入涉及的字段)
+struct ExistingServer {
+    id: String,
+    name: String,
+    ip: String,
+    port: u16,
+    username: String,
+    key_name: Option<String>,
+    connect_timeout: Option<u32>,
+    keep_alive_interval: Option<u32>,
+    jump_host_ids: Vec<String>,
+}
+
+struct PlannedServer {
+    server_id: String,
+    existing: Option<usize>,
+    host: SshConfigHost,
+    username: String,
+    identity_file: Option<PathBuf>,
+    jump_host_ids: Vec<String>,
+    jump_host_names: Vec<String>,
+    changes: Vec<SshConfigFieldChange>,
+    warnings: Vec<String>,
+}
+
+impl PlannedServer {
+    fn status(&self) -> &'static str {
+        match (self.existing, self.changes.is_empty()) {
+            (None, _) => "new",
+            (Some(_), false) => "changed",
+            (Some(_), true) => "unchanged",
+        }
+    }
+}
+
+struct ImportPlan {
+    source_path: PathBuf,
+    servers: Vec<PlannedServer>,
+    existing: Vec<ExistingServer>,
+    warnings: Vec<String>,
+}
+
+fn local_username() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "root".to_string())
+}
+
+fn identity_key_name(path: &Path) -> String {
+    format!("SSH Config Key: {}", path.display())
+}
+
+fn change(
+    field: &str,
+    current: Option<String>,
+    imported: Option<String>,
+) -> Option<SshConfigFieldChange> {
+    (current != imported).then(|| SshConfigFieldChange {
+        field: field.to_string(),
+        current,
+        imported,
+    })
+}
+
+async fn load_existing_servers(pool: &Pool<Sqlite>) -> Result<Vec<ExistingServer>, String> {
+    let rows = sqlx::query(
+        "SELECT s.id, s.name, s.ip, s.port, s.username, s.connect_timeout, s.keep_alive_interval, \
+         s.jump_host_ids, vk.name AS key_name \
+         FROM servers s LEFT JOIN vault_keys vk ON vk.id = s.key_id",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("数据库查询失败: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ExistingServer {
+            id: row.try_get("id").unwrap_or_default(),
+            name: row.try_get("name").unwrap_or_default(),
+            ip: row.try_get("ip").unwrap_or_default(),
+            port: row.try_get::<i64, _>("port").unwrap_or(22) as u16,
+            username: row.try_get("username").unwrap_or_default(),
+            key_name: row.try_get("key_name").unwrap_or(None),
+            connect_timeout: row
+                .try_get::<Option<i64>, _>("connect_timeout")
+                .unwrap_or(None)
+                .map(|v| v as u32),
+            keep_alive_interval: row
+                .try_get::<Option<i64>, _>("keep_alive_interval")
+                .unwrap_or(None)
+                .map(|v| v as u32),
+            jump_host_ids: row
+                .try_get::<Option<String>, _>("jump_host_ids")
+                .unwrap_or(None)
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+        })
+        .collect())
+}
+
+// ProxyJump 的 "user@host:port" 拆分
+fn split_jump_spec(spec: &str) -> (Option<&str>, &str, Option<u16>) {
+    let (user, rest) = match spec.rsplit_once('@') {
+        Some((user, rest)) => (Some(user), rest),
+        None => (None, spec),
+    };
+    if let Some(inner) = rest.strip_prefix('[') {
+        if let Some((host, tail)) = inner.split_once(']') {
+            let port = tail.strip_prefix(':').and_then(|p| p.parse().ok());
+            return (user, host, port);
+        }
+    }
+    match rest.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => (user, host, port.parse().ok()),
+        _ => (user, rest, None),
+    }
+}
+
+async fn build_plan(
+    app: &AppHandle,
+    pool: &Pool<Sqlite>,
+    path: Option<String>,
+    aliases: Option<Vec<String>>,
+) -> Result<ImportPlan, String> {
+    let home = app
+        .path()
+        .home_dir()
+        .map_err(|e| format!("Failed to resolve home directory: {}", e))?;
+    let source_path = path
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| match p.strip_prefix("~/") {
+            Some(rest) => home.join(rest),
+            None => PathBuf::from(p),
+        })
+        .unwrap_or_else(|| home.join(".ssh").join("config"));
+
+    let parsed = ParsedSshConfig::parse_file(&source_path, &home)?;
+    let existing = load_existing_servers(pool).await?;
+    let local_user = local_username();
+
+    let all_aliases = parsed.host_aliases();
+    let selected: Vec<String> = match aliases {
+        Some(wanted) => all_aliases
+            .iter()
+            .filter(|alias| wanted.contains(alias))
+            .cloned()
+            .collect(),
+        None => all_aliases.clone(),
+    };
+
+    // 第一轮：确定每个别名对应的服务器 ID (已有记录沿用，否则分配新 ID)
+    let mut servers: Vec<PlannedServer> = Vec::new();
+    for alias in &selected {
+        let host = parsed.resolve_host(alias, &local_user);
+        let username = host.user.clone().unwrap_or_else(|| local_user.clone());
+        let existing_index = existing
+            .iter()
+            .position(|server| server.name == *alias)
+            .or_else(|| {
+                existing.iter().position(|server| {
+                    server.ip == host.host_name
+                        && server.port == host.port
+                        && server.username == username
+                })
+            });
+
+        let mut warnings = Vec::new();
+        let identity_file = host
+            .identity_files
+            .iter()
+            .map(PathBuf::from)
+            .find(|candidate| candidate.is_file());
+        if identity_file.is_none() && !host.identity_files.is_empty() {
+            warnings.push(format!(
+                "IdentityFile not found: {}",
+                host.identity_files.join(", ")
+            ));
+        } else if host.identity_files.len() > 1 {
+            warnings.push(format!(
+                "Multiple IdentityFile entries; only {} is imported",
+                identity_file
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default()
+            ));
+        }
+
+        servers.push(PlannedServer {
+            server_id: existing_index
+                .map(|index| existing[index].id.clone())
+                .unwrap_or_else(|| Uuid::new_v4().to_string()),
+            existing: existing_index,
+            host,
+            username,
+            identity_file,
+            jump_host_ids: Vec::new(),
+            jump_host_names: Vec::new(),
+            changes: Vec::new(),
+            warnings,
+        });
+    }
+
+    // 第二轮：ProxyJump / ProxyCommand 映射到本次导入或已有的服务器
+    let planned_ids: HashMap<String, String> = servers
+        .iter()
+        .map(|server| (server.host.alias.clone(), server.server_id.clone()))
+        .collect();
+    for server in servers.iter_mut() {
+        let mut hops = server.host.proxy_jump.clone();
+        if let Some(command) = server.host.proxy_command.clone() {
+            match proxy_command_jump_host(&command) {
+                Some(hop) if hops.is_empty() => hops.push(hop),
+                Some(_) => {}
+                None => server.warnings.push(format!(
+                    "ProxyCommand is not supported and was skipped: {}",
+                    command
+                )),
+            }
+        }
+
+        for hop in hops {
+            let (user, hop_host, hop_port) = split_jump_spec(&hop);
+            let jump_id = planned_ids
+                .get(hop_host)
+                .cloned()
+                .or_else(|| {
+                    existing
+                        .iter()
+                        .find(|candidate| candidate.name == hop_host)
+                        .map(|candidate| candidate.id.clone())
+                })
+                .or_else(|| {
+                    existing
+                        .iter()
+                        .find(|candidate| {
+                            candidate.ip == hop_host
+                                && candidate.port == hop_port.unwrap_or(22)
+                                && user.is_none_or(|u| candidate.username == u)
+                        })
+                        .map(|candidate| candidate.id.clone())
+                });
+            match jump_id {
+                Some(id) if id == server.server_id => server.warnings.push(format!(
+                    "Jump host '{}' refers to the host itself; skipped",
+                    hop
+                )),
+                Some(id) => {
+                    server.jump_host_ids.push(id);
+                    server.jump_host_names.push(hop);
+                }
+                None => server.warnings.push(format!(
+                    "Jump host '{}' is neither imported nor saved; skipped",
+                    hop
+                )),
+            }
+        }
+    }
+
+    // 第三轮：与已有记录逐字段比较
+    for server in servers.iter_mut() {
+        let Some(index) = server.existing else {
+            continue;
+        };
+        let current = &existing[index];
+        let imported_key_name = server.identity_file.as_deref().map(identity_key_name);
+        let changes = [
+            change(
+                "name",
+                Some(current.name.clone()),
+                Some(server.host.alias.clone()),
+            ),
+            change(
+                "ip",
+                Some(current.ip.clone()),
+                Some(server.host.host_name.clone()),
+            ),
+            change(
+                "port",
+                Some(current.port.to_string()),
+                Some(server.host.port.to_string()),
+            ),
+            change(
+                "username",
+                Some(current.username.clone()),
+                Some(server.username.clone()),
+            ),
+            server.host.connect_timeout.and_then(|v| {
+                change(
+                    "connectTimeout",
+                    current.connect_timeout.map(|c| c.to_string()),
+                    Some(v.to_string()),
+                )
+            }),
+            server.host.server_alive_interval.and_then(|v| {
+                change(
+                    "keepAliveInterval",
+                    current.keep_alive_interval.map(|c| c.to_string()),
+                    Some(v.to_string()),
+                )
+            }),
+            imported_key_name
+                .and_then(|name| change("identity", current.key_name.clone(), Some(name))),
+            change(
+                "jumpHosts",
+                Some(current.jump_host_ids.join(",")),
+                Some(server.jump_host_ids.join(",")),
+            )
+            .map(|mut c| {
+                c.imported = Some(server.jump_host_names.join(","));
+                c
+            }),
+        ];
+        server.changes = changes.into_iter().flatten().collect();
+    }
+
+    Ok(ImportPlan {
+        source_path,
+        servers,
+        existing,
+        warnings: parsed.warnings,
+    })
+}
+
+// 🟢 预览 ~/.ssh/config 导入结果 (dry-run，不写数据库)
+#[command]
+pub async fn preview_ssh_config_import(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: Option<String>,
+) -> Result<SshConfigImportPreview, String> {
+    let plan = build_plan(&app, &state.db, path, None).await?;
+
+    Ok(SshConfigImportPreview {
+        source_path: plan.source_path.display().to_string(),
+        entries: plan
+            .servers
+            .into_iter()
+            .map(|server| SshConfigPreviewEntry {
+                alias: server.host.alias.clone(),
+                status: server.status().to_string(),
+                server_id: server.existing.map(|_| server.server_id.clone()),
+                identity_file: server
+                    .identity_file
+                    .as_ref()
+                    .map(|p| p.display().to_string()),
+                jump_hosts: server.jump_host_names,
+                changes: server.changes,
+                warnings: server.warnings,
+                host: server.host,
+            })
+            .collect(),
+        warnings: plan.warnings,
+    })
+}
+
+// 将私钥文件写入 Vault (同名记录原地更新)，并附带同目录下的 -cert.pub 证书
+async fn import_identity_file(
+    pool: &Pool<Sqlite>,
+    master_key: &aes_gcm::Key<aes_gcm::Aes256Gcm>,
+    path: &Path,
+    username: &str,
+    warnings: &mut Vec<String>,
+) -> Result<String, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let name = identity_key_name(path);
+
+    let existing_id: Option<String> =
+        sqlx::query_scalar("SELECT id FROM vault_keys WHERE name = ? AND key_type = 'private_key'")
+            .bind(&name)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+    let key_id = match existing_id {
+        Some(id) => {
+            internal_update_secret(
+                pool,
+                master_key,
+                &id,
+                &name,
+                &content,
+                Some(username.to_string()),
+            )
+            .await?;
+            id
+        }
+        None => {
+            internal_add_secret(
+                pool,
+                master_key,
+                &name,
+                "private_key",
+                &content,
+                Some(username.to_string()),
+                None,
+            )
+            .await?
+        }
+    };
+
+    let mut cert_path = path.as_os_str().to_owned();
+    cert_path.push("-cert.pub");
+    let cert_path = PathBuf::from(cert_path);
+    if let Ok(certificate) = std::fs::read_to_string(&cert_path) {
+        match parse_certificate(&certificate) {
+            Ok(_) => store_certificate(pool, &key_id, Some(certificate.trim())).await?,
+            Err(e) => warnings.push(format!(
+                "Skipped certificate {}: {}",
+                cert_path.display(),
+                e
+            )),
+        }
+    }
+
+    Ok(key_id)
+}
+
+// 🟢 导入 ~/.ssh/config：新建或更新服务器，IdentityFile 写入 Vault
+#[command]
+pub async fn import_ssh_config(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    vault_state: State<'_, VaultState>,
+    path: Option<String>,
+    aliases: Option<Vec<String>>,
+) -> Result<SshConfigImportResult, String> {
+    let pool = &state.db;
+    let plan = build_plan(&app, pool, path, aliases).await?;
+    let mut result = SshConfigImportResult {
+        warnings: plan.warnings.clone(),
+        ..Default::default()
+    };
+
+    let pending: Vec<&PlannedServer> = plan
+        .servers
+        .iter()
+        .filter(|server| server.status() != "unchanged")
+        .collect();
+    result.unchanged = plan.servers.len() - pending.len();
+
+    // 私钥导入需要已解锁的 Vault
+    let mut key_ids: HashMap<PathBuf, String> = HashMap::new();
+    if pending.iter().any(|server| server.identity_file.is_some()) {
+        let master_key = {
+            let guard = match vault_state.0.lock() {
+                Ok(guard) => guard,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            guard.as_ref().cloned().ok_or("VAULT_LOCKED")?
+        };
+        for server in &pending {
+            let Some(identity) = &server.identity_file else {
+                continue;
+            };
+            if key_ids.contains_key(identity) {
+                continue;
+            }
+            let key_id = import_identity_file(
+                pool,
+                &master_key,
+                identity,
+                &server.username,
+                &mut result.warnings,
+            )
+            .await?;
+            key_ids.insert(identity.clone(), key_id);
+            result.imported_keys += 1;
+        }
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let now = Utc::now().timestamp_millis();
+    let mut next_sort: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(sort), 0) FROM servers")
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap_or(0);
+
+    for server in pending {
+        let key_id = server
+            .identity_file
+            .as_ref()
+            .and_then(|identity| key_ids.get(identity).cloned());
+        let jump_host_ids_json =
+            serde_json::to_string(&server.jump_host_ids).unwrap_or("[]".to_string());
+        for warning in &server.warnings {
+            result
+                .warnings
+                .push(format!("{}: {}", server.host.alias, warning));
+        }
+
+        match server.existing.map(|index| &plan.existing[index]) {
+            Some(current) => {
+                sqlx::query(
+                    "UPDATE servers SET name = ?, ip = ?, port = ?, username = ?, \
+                     connect_timeout = ?, keep_alive_interval = ?, jump_host_ids = ?, updated_at = ? \
+                     WHERE id = ?",
+                )
+                .bind(&server.host.alias)
+                .bind(&server.host.host_name)
+                .bind(server.host.port)
+                .bind(&server.username)
+                .bind(server.host.connect_timeout.or(current.connect_timeout))
+                .bind(server.host.server_alive_interval.or(current.keep_alive_interval))
+                .bind(&jump_host_ids_json)
+                .bind(now)
+                .bind(&current.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("更新服务器失败: {}", e))?;
+
+                // 未配置 IdentityFile 时保留原有认证方式
+                if let Some(key_id) = key_id {
+                    sqlx::query(
+                        "UPDATE servers SET auth_type = ?, key_id = ?, key_source = 'store' WHERE id = ?",
+                    )
+                    .bind(AuthType::PrivateKey)
+                    .bind(key_id)
+                    .bind(&current.id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| format!("更新服务器失败: {}", e))?;
+                }
+                result.updated += 1;
+            }
+            None => {
+                next_sort += 1;
+                let auth_type = if key_id.is_some() {
+                    AuthType::PrivateKey
+                } else {
+                    AuthType::Agent
+                };
+                sqlx::query(
+                    r#"
+                    INSERT INTO servers (
+                        id, name, icon, provider, sort, ip, port, tags,
+                        connection_type, auth_type, username, key_id, key_source,
+                        os, is_pinned, enable_expiration, created_at, updated_at,
+                        connect_timeout, keep_alive_interval, auto_reconnect, max_reconnects,
+                        host_key_policy, jump_host_ids
+                    ) VALUES (
+                        ?, ?, 'server', 'Custom', ?, ?, ?, '[]',
+                        'direct', ?, ?, ?, ?,
+                        'linux', 0, 0, ?, ?,
+                        ?, ?, 0, 3,
+                        ?, ?
+                    )
+                    "#,
+                )
+                .bind(&server.server_id)
+                .bind(&server.host.alias)
+                .bind(next_sort)
+                .bind(&server.host.host_name)
+                .bind(server.host.port)
+                .bind(auth_type)
+                .bind(&server.username)
+                .bind(key_id.as_deref())
+                .bind(key_id.as_ref().map(|_| "store"))
+                .bind(now)
+                .bind(now)
+                .bind(server.host.connect_timeout.unwrap_or(10))
+                .bind(server.host.server_alive_interval.unwrap_or(60))
+                .bind(HostKeyPolicy::default())
+                .bind(&jump_host_ids_json)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("保存服务器失败: {}", e))?;
+                result.created += 1;
+            }
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(result)
+}
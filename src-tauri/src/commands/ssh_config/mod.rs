@@ -0,0 +1,5 @@
+pub mod commands;
+pub mod parser;
+
+// 方便外部统一导入命令
+pub use commands::*;
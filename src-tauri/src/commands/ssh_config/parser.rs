@@ -0,0 +1,487 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// Include 嵌套层数上限 (与 OpenSSH 的 READCONF_MAX_DEPTH 一致)
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+// 取第一次出现的值 (OpenSSH "first obtained value wins")
+const SINGLE_VALUE_KEYWORDS: &[&str] = &[
+    "hostname",
+    "user",
+    "port",
+    "proxyjump",
+    "proxycommand",
+    "serveraliveinterval",
+    "connecttimeout",
+];
+
+#[derive(Debug, Clone)]
+enum MatchCriterion {
+    All,
+    Host(Vec<String>),
+    OriginalHost(Vec<String>),
+    User(Vec<String>),
+    LocalUser(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+enum BlockCondition {
+    Host(Vec<String>),
+    Match(Vec<MatchCriterion>),
+    // exec / canonical / localnetwork 等无法在导入时求值的 Match，整块忽略
+    Unsupported,
+}
+
+#[derive(Debug, Clone)]
+struct ConfigBlock {
+    condition: BlockCondition,
+    directives: Vec<(String, Vec<String>)>,
+}
+
+#[derive(Debug)]
+pub struct ParsedSshConfig {
+    blocks: Vec<ConfigBlock>,
+    home: PathBuf,
+    pub warnings: Vec<String>,
+}
+
+// 单个 Host 别名解析后的有效配置
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SshConfigHost {
+    pub alias: String,
+    pub host_name: String,
+    pub user: Option<String>,
+    pub port: u16,
+    pub identity_files: Vec<String>,
+    pub proxy_jump: Vec<String>,
+    pub proxy_command: Option<String>,
+    pub server_alive_interval: Option<u32>,
+    pub connect_timeout: Option<u32>,
+}
+
+// OpenSSH 风格通配符：* 任意长度，? 单个字符，不区分大小写
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// 模式列表 (空格或逗号分隔)：任一 !pattern 命中则不匹配
+fn pattern_list_matches(patterns: &[String], text: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns
+        .iter()
+        .flat_map(|p| p.split(','))
+        .filter(|p| !p.is_empty())
+    {
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated, text) => return false,
+            Some(_) => {}
+            None if wildcard_match(pattern, text) => matched = true,
+            None => {}
+        }
+    }
+    matched
+}
+
+// 支持 "Keyword value"、"Keyword=value" 以及双引号参数
+fn parse_line(raw: &str) -> Option<(String, Vec<String>)> {
+    let line = raw.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let split_at = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let keyword = line[..split_at].to_lowercase();
+    let rest = line[split_at..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+    for c in rest.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            '#' if !in_quotes && !has_token => break,
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        args.push(current);
+    }
+
+    Some((keyword, args))
+}
+
+fn parse_match(args: &[String]) -> Result<Vec<MatchCriterion>, String> {
+    let mut criteria = Vec::new();
+    let mut iter = args.iter();
+    while let Some(criterion) = iter.next() {
+        let keyword = criterion.to_lowercase();
+        if keyword == "all" {
+            criteria.push(MatchCriterion::All);
+            continue;
+        }
+        let mut value = || {
+            iter.next()
+                .map(|v| vec![v.clone()])
+                .ok_or_else(|| format!("'{}' without argument", keyword))
+        };
+        match keyword.as_str() {
+            "host" => criteria.push(MatchCriterion::Host(value()?)),
+            "originalhost" => criteria.push(MatchCriterion::OriginalHost(value()?)),
+            "user" => criteria.push(MatchCriterion::User(value()?)),
+            "localuser" => criteria.push(MatchCriterion::LocalUser(value()?)),
+            other => return Err(format!("'{}'", other)),
+        }
+    }
+    Ok(criteria)
+}
+
+fn expand_home(path: &str, home: &Path) -> PathBuf {
+    if path == "~" {
+        home.to_path_buf()
+    } else if let Some(rest) = path.strip_prefix("~/") {
+        home.join(rest)
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+// Include 支持 ~ 与文件名中的通配符，相对路径以 ~/.ssh 为基准
+fn expand_include(pattern: &str, home: &Path) -> Vec<PathBuf> {
+    let path = expand_home(pattern, home);
+    let path = if path.is_relative() {
+        home.join(".ssh").join(path)
+    } else {
+        path
+    };
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !file_name.contains(['*', '?']) {
+        return if path.is_file() {
+            vec![path]
+        } else {
+            Vec::new()
+        };
+    }
+
+    let Some(parent) = path.parent() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(parent) else {
+        return Vec::new();
+    };
+    let mut matches: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|candidate| {
+            candidate.is_file()
+                && candidate
+                    .file_name()
+                    .map(|name| wildcard_match(&file_name, &name.to_string_lossy()))
+                    .unwrap_or(false)
+        })
+        .collect();
+    matches.sort();
+    matches
+}
+
+impl ParsedSshConfig {
+    pub fn parse_file(path: &Path, home: &Path) -> Result<Self, String> {
+        let mut parsed = Self {
+            // 第一个 Host/Match 之前的指令对所有主机生效
+            blocks: vec![ConfigBlock {
+                condition: BlockCondition::Host(vec!["*".to_string()]),
+                directives: Vec::new(),
+            }],
+            home: home.to_path_buf(),
+            warnings: Vec::new(),
+        };
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        parsed.parse_content(&content, path, 0);
+        Ok(parsed)
+    }
+
+    fn parse_content(&mut self, content: &str, path: &Path, depth: usize) {
+        for (index, raw) in content.lines().enumerate() {
+            let Some((keyword, args)) = parse_line(raw) else {
+                continue;
+            };
+            let location = format!("{}:{}", path.display(), index + 1);
+
+            match keyword.as_str() {
+                "host" => self.blocks.push(ConfigBlock {
+                    condition: BlockCondition::Host(args),
+                    directives: Vec::new(),
+                }),
+                "match" => {
+                    let condition = match parse_match(&args) {
+                        Ok(criteria) => BlockCondition::Match(criteria),
+                        Err(reason) => {
+                            self.warnings.push(format!(
+                                "{}: Match criterion {} is not supported; block ignored",
+                                location, reason
+                            ));
+                            BlockCondition::Unsupported
+                        }
+                    };
+                    self.blocks.push(ConfigBlock {
+                        condition,
+                        directives: Vec::new(),
+                    });
+                }
+                "include" => {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        self.warnings
+                            .push(format!("{}: Include nested too deeply; skipped", location));
+                        continue;
+                    }
+                    for pattern in &args {
+                        let home = self.home.clone();
+                        let files = expand_include(pattern, &home);
+                        if files.is_empty() {
+                            self.warnings.push(format!(
+                                "{}: Include '{}' matched no files",
+                                location, pattern
+                            ));
+                        }
+                        for file in files {
+                            match std::fs::read_to_string(&file) {
+                                Ok(included) => self.parse_content(&included, &file, depth + 1),
+                                Err(e) => self.warnings.push(format!(
+                                    "{}: Failed to read included {}: {}",
+                                    location,
+                                    file.display(),
+                                    e
+                                )),
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    if let Some(block) = self.blocks.last_mut() {
+                        block.directives.push((keyword, args));
+                    }
+                }
+            }
+        }
+    }
+
+    // Host 行里可直接连接的别名 (不含通配符与否定模式)
+    pub fn host_aliases(&self) -> Vec<String> {
+        let mut aliases: Vec<String> = Vec::new();
+        for block in &self.blocks {
+            if let BlockCondition::Host(patterns) = &block.condition {
+                for pattern in patterns.iter().flat_map(|p| p.split(',')) {
+                    if pattern.is_empty()
+                        || pattern.starts_with('!')
+                        || pattern.contains(['*', '?'])
+                        || aliases.iter().any(|alias| alias == pattern)
+                    {
+                        continue;
+                    }
+                    aliases.push(pattern.to_string());
+                }
+            }
+        }
+        aliases
+    }
+
+    pub fn resolve_host(&self, alias: &str, local_user: &str) -> SshConfigHost {
+        let mut values: HashMap<&str, Vec<String>> = HashMap::new();
+        let mut identity_files: Vec<String> = Vec::new();
+
+        for block in &self.blocks {
+            let matches = match &block.condition {
+                BlockCondition::Host(patterns) => pattern_list_matches(patterns, alias),
+                BlockCondition::Match(criteria) => {
+                    criteria.iter().all(|criterion| match criterion {
+                        MatchCriterion::All => true,
+                        MatchCriterion::Host(patterns) => {
+                            let host = values
+                                .get("hostname")
+                                .and_then(|v| v.first())
+                                .map(|h| h.replace("%h", alias))
+                                .unwrap_or_else(|| alias.to_string());
+                            pattern_list_matches(patterns, &host)
+                        }
+                        MatchCriterion::OriginalHost(patterns) => {
+                            pattern_list_matches(patterns, alias)
+                        }
+                        MatchCriterion::User(patterns) => {
+                            let user = values
+                                .get("user")
+                                .and_then(|v| v.first())
+                                .map(String::as_str)
+                                .unwrap_or(local_user);
+                            pattern_list_matches(patterns, user)
+                        }
+                        MatchCriterion::LocalUser(patterns) => {
+                            pattern_list_matches(patterns, local_user)
+                        }
+                    })
+                }
+                BlockCondition::Unsupported => false,
+            };
+            if !matches {
+                continue;
+            }
+
+            for (keyword, args) in &block.directives {
+                if args.is_empty() {
+                    continue;
+                }
+                if keyword == "identityfile" {
+                    identity_files.push(args.join(" "));
+                } else if let Some(known) = SINGLE_VALUE_KEYWORDS
+                    .iter()
+                    .find(|known| **known == keyword.as_str())
+                {
+                    values.entry(known).or_insert_with(|| args.clone());
+                }
+            }
+        }
+
+        let first = |key: &str| values.get(key).and_then(|v| v.first()).cloned();
+        let host_name = first("hostname")
+            .map(|h| h.replace("%h", alias))
+            .unwrap_or_else(|| alias.to_string());
+        let user = first("user");
+        let port = first("port")
+            .and_then(|p| p.parse::<u16>().ok())
+            .unwrap_or(22);
+        let remote_user = user.clone().unwrap_or_else(|| local_user.to_string());
+
+        let identity_files = identity_files
+            .into_iter()
+            .filter(|file| !file.eq_ignore_ascii_case("none"))
+            .map(|file| {
+                let expanded = file
+                    .replace("%d", &self.home.to_string_lossy())
+                    .replace("%u", local_user)
+                    .replace("%r", &remote_user)
+                    .replace("%h", &host_name)
+                    .replace("%n", alias)
+                    .replace("%p", &port.to_string())
+                    .replace("%%", "%");
+                expand_home(&expanded, &self.home)
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+
+        let proxy_jump = first("proxyjump")
+            .filter(|jump| !jump.eq_ignore_ascii_case("none"))
+            .map(|jump| {
+                jump.split(',')
+                    .map(str::trim)
+                    .filter(|hop| !hop.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        SshConfigHost {
+            alias: alias.to_string(),
+            host_name,
+            user,
+            port,
+            identity_files,
+            proxy_jump,
+            proxy_command: values
+                .get("proxycommand")
+                .map(|args| args.join(" "))
+                .filter(|cmd| !cmd.eq_ignore_ascii_case("none")),
+            server_alive_interval: first("serveraliveinterval").and_then(|v| v.parse().ok()),
+            connect_timeout: first("connecttimeout").and_then(|v| v.parse().ok()),
+        }
+    }
+}
+
+// ProxyCommand 中常见的 "ssh -W %h:%p jump" 写法等价于 ProxyJump
+pub fn proxy_command_jump_host(command: &str) -> Option<String> {
+    let tokens: Vec<&str> = command.split_whitespace().collect();
+    let program = tokens.first()?;
+    if !program.ends_with("ssh") && !program.ends_with("ssh.exe") {
+        return None;
+    }
+    let forward_index = tokens.iter().position(|token| *token == "-W")?;
+    if tokens.get(forward_index + 1) != Some(&"%h:%p") {
+        return None;
+    }
+
+    // 跳过带参数的选项，取第一个非选项参数作为跳板主机
+    const OPTIONS_WITH_ARG: &[&str] = &["-W", "-p", "-l", "-i", "-o", "-F", "-J"];
+    let mut index = 1;
+    let mut jump_host = None;
+    let mut jump_port = None;
+    let mut jump_user = None;
+    while index < tokens.len() {
+        let token = tokens[index];
+        if OPTIONS_WITH_ARG.contains(&token) {
+            match token {
+                "-p" => jump_port = tokens.get(index + 1).copied(),
+                "-l" => jump_user = tokens.get(index + 1).copied(),
+                _ => {}
+            }
+            index += 2;
+            continue;
+        }
+        if !token.starts_with('-') {
+            jump_host = Some(token);
+            break;
+        }
+        index += 1;
+    }
+
+    let host = jump_host?;
+    let mut spec = match jump_user {
+        Some(user) if !host.contains('@') => format!("{}@{}", user, host),
+        _ => host.to_string(),
+    };
+    if let Some(port) = jump_port {
+        spec = format!("{}:{}", spec, port);
+    }
+    Some(spec)
+}
@@ -70,6 +70,27 @@ pub struct KeyEntry {
     // 🟢 新增字段
     #[serde(rename = "lastUsed")]
     pub last_used: Option<LastUsedInfo>,
+
+    // 🟢 附加的 OpenSSH 用户证书摘要 (主体、有效期)
+    #[serde(default)]
+    pub certificate: Option<CertificateInfo>,
+
+    // 🟢 是否允许通过 Agent 转发提供给远端服务器
+    #[serde(default)]
+    pub forwardable: bool,
+}
+
+// 🟢 OpenSSH 用户证书摘要，时间均为 Unix 秒
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateInfo {
+    pub cert_type: String,
+    pub key_id: String,
+    pub serial: u64,
+    pub principals: Vec<String>,
+    pub valid_after: u64,
+    pub valid_before: u64,
+    pub expired: bool,
 }
 
 // 🟢 [新增] 临时结构体：用于接收 SQL 联表查询的扁平结果
@@ -84,6 +105,9 @@ struct KeyEntryRow {
     algorithm: Option<String>,
     created_at: i64,
     updated_at: i64,
+    certificate: Option<String>,
+    #[sqlx(default)]
+    forwardable: bool,
     // 下面是联表查询出来的字段 (可能为空)
     last_used_at: Option<i64>,
     server_name: Option<String>,
@@ -118,6 +142,33 @@ fn get_key_fingerprint(key: &Key<Aes256Gcm>) -> String {
     format!("{:x}", result)[..8].to_string()
 }
 
+// =========================================================
+// OpenSSH 证书
+// =========================================================
+
+pub(crate) fn parse_certificate(certificate: &str) -> Result<ssh_key::Certificate, String> {
+    ssh_key::Certificate::from_openssh(certificate.trim())
+        .map_err(|e| format!("Invalid OpenSSH certificate: {}", e))
+}
+
+pub(crate) fn certificate_expired(cert: &ssh_key::Certificate) -> bool {
+    let now = Utc::now().timestamp().max(0) as u64;
+    now >= cert.valid_before() || now < cert.valid_after()
+}
+
+fn describe_certificate(certificate: &str) -> Option<CertificateInfo> {
+    let cert = parse_certificate(certificate).ok()?;
+    Some(CertificateInfo {
+        cert_type: cert.algorithm().to_certificate_type(),
+        key_id: cert.key_id().to_string(),
+        serial: cert.serial(),
+        principals: cert.valid_principals().to_vec(),
+        valid_after: cert.valid_after(),
+        valid_before: cert.valid_before(),
+        expired: certificate_expired(&cert),
+    })
+}
+
 // =========================================================
 // 核心工具函数 (加密/解密)
 // =========================================================
@@ -285,6 +336,22 @@ pub async fn internal_get_secret(
     }
 }
 
+// 🟢 读取密钥附带的 OpenSSH 证书 (未附加时为 None)
+pub async fn internal_get_certificate(
+    pool: &Pool<Sqlite>,
+    id: &str,
+) -> Result<Option<String>, String> {
+    let row = sqlx::query("SELECT certificate FROM vault_keys WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(row
+        .and_then(|r| r.try_get::<Option<String>, _>("certificate").ok().flatten())
+        .filter(|cert| !cert.trim().is_empty()))
+}
+
 // =========================================================
 // Tauri Commands
 // =========================================================
@@ -472,9 +539,73 @@ pub async fn add_key(
         created_at: now,
         updated_at: now,
         last_used: None, // 新建的密钥没有使用记录
+        certificate: None, // 证书通过 set_key_certificate 单独附加
+        forwardable: false, // 通过 set_key_forwardable 单独开启
     })
 }
 
+pub(crate) async fn store_certificate(
+    pool: &Pool<Sqlite>,
+    id: &str,
+    certificate: Option<&str>,
+) -> Result<(), String> {
+    let result = sqlx::query("UPDATE vault_keys SET certificate = ?, updated_at = ? WHERE id = ?")
+        .bind(certificate)
+        .bind(Utc::now().timestamp_millis())
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err("Secret not found".to_string());
+    }
+    Ok(())
+}
+
+// 🟢 为已有密钥附加/替换/移除 OpenSSH 证书 (certificate 为空表示移除)
+#[command]
+pub async fn set_key_certificate(
+    state: State<'_, AppState>,
+    id: String,
+    certificate: Option<String>,
+) -> Result<Option<CertificateInfo>, String> {
+    let certificate = certificate.filter(|cert| !cert.trim().is_empty());
+    let info = match certificate.as_deref() {
+        Some(cert) => {
+            parse_certificate(cert)?;
+            describe_certificate(cert)
+        }
+        None => None,
+    };
+
+    store_certificate(&state.db, &id, certificate.as_deref().map(str::trim)).await?;
+    Ok(info)
+}
+
+// 🟢 标记私钥是否可被 Agent 转发使用 (仅对开启了 Agent 转发的服务器生效)
+#[command]
+pub async fn set_key_forwardable(
+    state: State<'_, AppState>,
+    id: String,
+    forwardable: bool,
+) -> Result<(), String> {
+    let result = sqlx::query(
+        "UPDATE vault_keys SET forwardable = ?, updated_at = ? WHERE id = ? AND key_type = 'private_key'",
+    )
+    .bind(forwardable)
+    .bind(Utc::now().timestamp_millis())
+    .bind(&id)
+    .execute(&state.db)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err("Private key not found".to_string());
+    }
+    Ok(())
+}
+
 #[command]
 pub async fn delete_key(state: State<'_, AppState>, id: String) -> Result<(), String> {
     let pool = &state.db;
@@ -554,6 +685,8 @@ pub async fn get_all_keys(state: State<'_, AppState>) -> Result<Vec<KeyEntry>, S
                 created_at: row.created_at,
                 updated_at: row.updated_at,
                 last_used, // 赋值
+                certificate: row.certificate.as_deref().and_then(describe_certificate),
+                forwardable: row.forwardable,
             }
         })
         .collect();
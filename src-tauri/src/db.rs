@@ -35,8 +35,14 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
         .execute(&pool)
         .await
         .map_err(|e| e.to_string())?;
-    // --- 执行建表迁移 ---
 
+    run_migrations(&pool).await?;
+
+    Ok(pool)
+}
+
+// --- 执行建表迁移 ---
+pub async fn run_migrations(pool: &Pool<Sqlite>) -> Result<(), String> {
     // 1. Vault 表 (Config & Keys)
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS vault_config (
@@ -44,7 +50,7 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
             value TEXT NOT NULL
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
@@ -58,13 +64,24 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
             salt TEXT NOT NULL,
             algorithm TEXT,
             created_at INTEGER NOT NULL,
-            updated_at INTEGER NOT NULL
+            updated_at INTEGER NOT NULL,
+            certificate TEXT,
+            forwardable BOOLEAN DEFAULT 0
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
+    // 附加的 OpenSSH 用户证书 (*-cert.pub)，证书本身是公开信息，明文存储
+    let _ = sqlx::query("ALTER TABLE vault_keys ADD COLUMN certificate TEXT;")
+        .execute(pool)
+        .await;
+    // 允许通过 Agent 转发提供给远端的私钥
+    let _ = sqlx::query("ALTER TABLE vault_keys ADD COLUMN forwardable BOOLEAN DEFAULT 0;")
+        .execute(pool)
+        .await;
+
     // 2. Server 表
     // 注意：tags 我们存为 TEXT (JSON 字符串)
     sqlx::query(
@@ -100,15 +117,64 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
             connect_timeout INTEGER DEFAULT 10,
             keep_alive_interval INTEGER DEFAULT 60,
             auto_reconnect BOOLEAN DEFAULT 0,
-            max_reconnects INTEGER DEFAULT 3        
+            max_reconnects INTEGER DEFAULT 3,
+            host_key_policy TEXT DEFAULT 'prompt',
+            jump_host_ids TEXT DEFAULT '[]',
+            agent_forwarding BOOLEAN DEFAULT 0,
+            split_transport BOOLEAN DEFAULT 0,
+            encoding TEXT DEFAULT 'utf-8',
+            record_sessions BOOLEAN DEFAULT 0,
+            record_input BOOLEAN DEFAULT 0,
+            session_log TEXT,
+            persistent_session TEXT,
+            shell_options TEXT
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
     // 尝试为旧版数据库迁移新增 theme 列 (忽略已存在错误)
-    let _ = sqlx::query("ALTER TABLE servers ADD COLUMN theme TEXT;").execute(&pool).await;
+    let _ = sqlx::query("ALTER TABLE servers ADD COLUMN theme TEXT;").execute(pool).await;
+    // 主机密钥校验策略 (strict / accept-new / prompt)
+    let _ = sqlx::query("ALTER TABLE servers ADD COLUMN host_key_policy TEXT DEFAULT 'prompt';")
+        .execute(pool)
+        .await;
+    // ProxyJump 跳板服务器 ID 列表 (JSON)
+    let _ = sqlx::query("ALTER TABLE servers ADD COLUMN jump_host_ids TEXT DEFAULT '[]';")
+        .execute(pool)
+        .await;
+    // SSH Agent 转发开关
+    let _ = sqlx::query("ALTER TABLE servers ADD COLUMN agent_forwarding BOOLEAN DEFAULT 0;")
+        .execute(pool)
+        .await;
+    // shell 与后台通道分用两条传输 (兼容限制 MaxSessions 的服务器)
+    let _ = sqlx::query("ALTER TABLE servers ADD COLUMN split_transport BOOLEAN DEFAULT 0;")
+        .execute(pool)
+        .await;
+    // 远端字符编码 (终端与 SFTP 文本读写)
+    let _ = sqlx::query("ALTER TABLE servers ADD COLUMN encoding TEXT DEFAULT 'utf-8';")
+        .execute(pool)
+        .await;
+    // 会话自动录制 (可选记录输入)
+    let _ = sqlx::query("ALTER TABLE servers ADD COLUMN record_sessions BOOLEAN DEFAULT 0;")
+        .execute(pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE servers ADD COLUMN record_input BOOLEAN DEFAULT 0;")
+        .execute(pool)
+        .await;
+    // 纯文本会话日志配置 (JSON)
+    let _ = sqlx::query("ALTER TABLE servers ADD COLUMN session_log TEXT;")
+        .execute(pool)
+        .await;
+    // 持久会话模式 (tmux / screen，为空表示关闭)
+    let _ = sqlx::query("ALTER TABLE servers ADD COLUMN persistent_session TEXT;")
+        .execute(pool)
+        .await;
+    // shell 启动选项 (TERM、尺寸、环境变量、启动命令)，JSON
+    let _ = sqlx::query("ALTER TABLE servers ADD COLUMN shell_options TEXT;")
+        .execute(pool)
+        .await;
 
     // --- [新增] 3. Snippets 表 ---
     sqlx::query(
@@ -122,7 +188,7 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
             updated_at INTEGER NOT NULL
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
@@ -140,7 +206,7 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
             updated_at INTEGER NOT NULL
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
     // 🟢 [新增] 5. Key Usages Table (密钥使用记录)
@@ -156,7 +222,7 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
             FOREIGN KEY(server_id) REFERENCES servers(id) ON DELETE CASCADE
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
@@ -173,7 +239,7 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
             global_exec_count INTEGER DEFAULT 1
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
@@ -181,13 +247,13 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
     sqlx::query(
         "CREATE INDEX IF NOT EXISTS idx_history_command ON command_history(normalized_command);",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
     sqlx::query(
         "CREATE INDEX IF NOT EXISTS idx_history_count ON command_history(global_exec_count DESC);",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
@@ -204,13 +270,13 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
             UNIQUE(command_id, server_id)
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
     // 索引：查询某服务器的高频命令
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_usage_server_rank ON command_usage(server_id, exec_count DESC);")
-        .execute(&pool).await.map_err(|e| e.to_string())?;
+        .execute(pool).await.map_err(|e| e.to_string())?;
 
     // 6.3 历史流水表
     sqlx::query(
@@ -220,16 +286,34 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
             server_id TEXT NOT NULL,
             source TEXT DEFAULT 'user',
             executed_at INTEGER NOT NULL,
+            exit_code INTEGER,
+            duration_ms INTEGER,
+            cwd TEXT,
+            session_id TEXT,
             FOREIGN KEY(command_id) REFERENCES command_history(id) ON DELETE CASCADE
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
+    // shell 集成 (OSC 133 / OSC 7) 上报的退出码、耗时与工作目录
+    let _ = sqlx::query("ALTER TABLE command_events ADD COLUMN exit_code INTEGER;")
+        .execute(pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE command_events ADD COLUMN duration_ms INTEGER;")
+        .execute(pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE command_events ADD COLUMN cwd TEXT;")
+        .execute(pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE command_events ADD COLUMN session_id TEXT;")
+        .execute(pool)
+        .await;
+
     // 索引：查询流水线
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_events_timeline ON command_events(server_id, executed_at DESC);")
-        .execute(&pool).await.map_err(|e| e.to_string())?;
+        .execute(pool).await.map_err(|e| e.to_string())?;
 
     // 7.1 规则集合 (Profile)
     sqlx::query(
@@ -242,7 +326,7 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
             updated_at INTEGER NOT NULL
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
@@ -260,7 +344,7 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
             updated_at INTEGER NOT NULL
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
@@ -282,7 +366,7 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
             FOREIGN KEY(style_id) REFERENCES highlight_styles(id)
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
@@ -290,7 +374,7 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
     sqlx::query(
         "CREATE INDEX IF NOT EXISTS idx_highlight_rules_set_id ON highlight_rules(set_id);",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
@@ -303,9 +387,63 @@ pub async fn init_db(app: &AppHandle) -> Result<Pool<Sqlite>, String> {
         FOREIGN KEY(set_id) REFERENCES highlight_rule_sets(id) ON DELETE CASCADE
     );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| e.to_string())?;
 
-    Ok(pool)
+    // 🟢 端口转发规则 (按服务器保存，auto_start 的规则在连接建立后自动启动)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS port_forwards (
+            id TEXT PRIMARY KEY,
+            server_id TEXT NOT NULL,
+            name TEXT,
+            forward_type TEXT NOT NULL DEFAULT 'local',
+            bind_address TEXT NOT NULL DEFAULT '127.0.0.1',
+            bind_port INTEGER NOT NULL,
+            target_host TEXT NOT NULL,
+            target_port INTEGER NOT NULL,
+            auto_start BOOLEAN DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY(server_id) REFERENCES servers(id) ON DELETE CASCADE
+        );",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_port_forwards_server_id ON port_forwards(server_id);",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // 🟢 输出触发器 (按服务器保存，shell 输出匹配正则时自动应答/通知)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS triggers (
+            id TEXT PRIMARY KEY,
+            server_id TEXT NOT NULL,
+            name TEXT,
+            pattern TEXT NOT NULL,
+            action TEXT NOT NULL DEFAULT 'send_text',
+            payload TEXT NOT NULL DEFAULT '',
+            append_newline BOOLEAN DEFAULT 1,
+            cooldown_ms INTEGER NOT NULL DEFAULT 1000,
+            enabled BOOLEAN DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY(server_id) REFERENCES servers(id) ON DELETE CASCADE
+        );",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_triggers_server_id ON triggers(server_id);")
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
 }
@@ -8,7 +8,7 @@ pub mod utils;
 use crate::state::AppState;
 use commands::monitor::MonitorCache;
 use commands::settings::{load_app_settings, save_app_settings, SettingsFileState};
-use commands::ssh::{HostKeyVerificationCache, SshState};
+use commands::ssh::{AuthPromptRegistry, HostKeyVerificationCache, SshState};
 use commands::vault::VaultState;
 use std::sync::Mutex;
 use tauri::{
@@ -28,6 +28,12 @@ use commands::backup::{
 };
 use commands::fs::*;
 use commands::server::*;
+use commands::ssh_config::{import_ssh_config, preview_ssh_config_import};
+use commands::recording::{
+    control_recording_playback, delete_recording, export_recording, get_recording_policy,
+    list_recordings, search_recordings, set_recording_policy, start_recording_playback,
+    start_session_recording, stop_session_recording, PlaybackRegistry,
+};
 // ================================
 // 引入 SSH 命令
 // ================================
@@ -39,6 +45,8 @@ use commands::ssh::*;
 use commands::vault::{
     add_key, check_key_associations, delete_key, get_all_keys, get_decrypted_content,
     get_vault_status, init_vault, lock_vault, unlock_vault, change_vault_password,
+    set_key_certificate,
+    set_key_forwardable,
 };
 
 // [新增] 引入 snippet 命令模块
@@ -94,8 +102,13 @@ pub fn run() {
         // 状态管理
         .manage(SshState::default())
         .manage(HostKeyVerificationCache::default())
+        .manage(AuthPromptRegistry::default())
+        .manage(RemoteForwardRegistry::default())
         .manage(MonitorCache::new())
         .manage(SettingsFileState::default())
+        .manage(PlaybackRegistry::default())
+        .manage(BroadcastRegistry::default())
+        .manage(TerminalTransferRegistry::default())
         .manage(VaultState(Mutex::new(None)))
         // 初始化窗口配置状态
         .manage(WindowConfigState {
@@ -190,15 +203,66 @@ pub fn run() {
             save_server,
             delete_server,
             update_last_connected,
+            preview_ssh_config_import,
+            import_ssh_config,
             // SSH
             connect_ssh,
+            duplicate_ssh_session,
             write_ssh,
             resize_ssh,
             touch_ssh_session,
+            set_terminal_raw_output,
+            get_scrollback_lines,
+            search_scrollback,
             disconnect_ssh,
+            // shell 集成 (OSC 133 / OSC 7)
+            get_shell_integration_script,
+            inject_shell_integration,
+            // 终端工作目录与 SFTP 面板联动
+            get_terminal_cwd,
+            set_terminal_cwd_sync,
+            open_terminal_here,
+            // tmux/screen 持久会话
+            get_persistent_session,
+            list_remote_tmux_sessions,
+            attach_tmux_session,
+            // 广播输入 (多会话同步)
+            create_broadcast_group,
+            update_broadcast_members,
+            set_broadcast_member_paused,
+            set_broadcast_safety_mode,
+            confirm_broadcast_group,
+            list_broadcast_groups,
+            delete_broadcast_group,
+            broadcast_write,
+            // 终端内 ZMODEM / trzsz 文件传输
+            respond_terminal_transfer,
+            cancel_terminal_transfer,
+            // 输出触发器 (自动应答)
+            list_triggers,
+            save_trigger,
+            delete_trigger,
+            // 会话录像
+            start_session_recording,
+            stop_session_recording,
+            list_recordings,
+            delete_recording,
+            export_recording,
+            get_recording_policy,
+            set_recording_policy,
+            start_recording_playback,
+            control_recording_playback,
+            search_recordings,
             test_connection,
             check_host_key,
             trust_host_key,
+            respond_auth_prompt,
+            list_port_forwards,
+            save_port_forward,
+            delete_port_forward,
+            start_port_forward,
+            stop_port_forward,
+            list_active_port_forwards,
             quick_connect,
             get_ssh_combined_info,
             // 监控命令
@@ -230,6 +294,8 @@ pub fn run() {
             delete_key,
             get_decrypted_content,
             get_all_keys,
+            set_key_certificate,
+            set_key_forwardable,
             get_vault_status,
             check_key_associations,
             change_vault_password,
@@ -35,6 +35,145 @@ pub enum ConnectionType {
     Socks5,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum HostKeyPolicy {
+    /// 仅接受 known_hosts 中已存在且匹配的主机密钥
+    Strict,
+    /// 首次连接自动写入 known_hosts，之后严格校验 (同 OpenSSH accept-new)
+    AcceptNew,
+    /// 未知主机需要用户通过 check_host_key / trust_host_key 确认
+    #[default]
+    Prompt,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PersistentSessionMode {
+    /// tmux new-session -A：已存在同名会话时直接附加
+    Tmux,
+    /// screen -xRR：附加到同名会话，不存在时创建
+    Screen,
+}
+
+pub const DEFAULT_SESSION_LOG_TEMPLATE: &str = "{server}/{date}.log";
+pub const DEFAULT_SESSION_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionLogRotation {
+    /// 按日期切分 (模板不含 {date} 时跨天将旧文件改名为 name.YYYY-MM-DD.log)
+    #[default]
+    Daily,
+    /// 超过 max_bytes 时滚动为 name.1.log、name.2.log ...
+    Size,
+}
+
+// 🟢 纯文本会话日志：去除控制序列、逐行加时间戳，路径模板相对于应用数据目录下的 session-logs
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLogConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 支持 {server} {host} {user} {session} {date} {time}
+    #[serde(default = "default_session_log_template")]
+    pub path_template: String,
+    /// 写入前按 ssh_log::redact_secrets 脱敏 (同时遮蔽 IP；多行 PEM 块整体遮蔽)
+    #[serde(default)]
+    pub redact: bool,
+    #[serde(default)]
+    pub rotation: SessionLogRotation,
+    #[serde(default = "default_session_log_max_bytes")]
+    pub max_bytes: u64,
+}
+
+fn default_session_log_template() -> String {
+    DEFAULT_SESSION_LOG_TEMPLATE.to_string()
+}
+
+fn default_session_log_max_bytes() -> u64 {
+    DEFAULT_SESSION_LOG_MAX_BYTES
+}
+
+impl Default for SessionLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path_template: default_session_log_template(),
+            redact: false,
+            rotation: SessionLogRotation::default(),
+            max_bytes: DEFAULT_SESSION_LOG_MAX_BYTES,
+        }
+    }
+}
+
+pub const DEFAULT_TERM_TYPE: &str = "xterm-256color";
+pub const DEFAULT_PTY_COLS: u32 = 80;
+pub const DEFAULT_PTY_ROWS: u32 = 24;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellEnvVar {
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+// 🟢 shell 通道启动选项：PTY 类型与初始尺寸、环境变量 (set_env)、工作目录、启动命令
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellOptions {
+    #[serde(default = "default_term_type")]
+    pub term_type: String,
+    #[serde(default = "default_pty_cols")]
+    pub cols: u32,
+    #[serde(default = "default_pty_rows")]
+    pub rows: u32,
+    /// 远端 sshd 需在 AcceptEnv 中放行，否则会被忽略
+    #[serde(default)]
+    pub env: Vec<ShellEnvVar>,
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// 出现第一个提示符后逐行输入
+    #[serde(default)]
+    pub startup_commands: Option<String>,
+    /// 在 startup_commands 之后输入的代码片段
+    #[serde(default)]
+    pub startup_snippet_id: Option<String>,
+    /// 以 exec 运行固定命令 (如 htop) 代替登录 shell，设置后忽略启动命令
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+fn default_term_type() -> String {
+    DEFAULT_TERM_TYPE.to_string()
+}
+
+fn default_pty_cols() -> u32 {
+    DEFAULT_PTY_COLS
+}
+
+fn default_pty_rows() -> u32 {
+    DEFAULT_PTY_ROWS
+}
+
+impl Default for ShellOptions {
+    fn default() -> Self {
+        Self {
+            term_type: default_term_type(),
+            cols: DEFAULT_PTY_COLS,
+            rows: DEFAULT_PTY_ROWS,
+            env: Vec::new(),
+            working_dir: None,
+            startup_commands: None,
+            startup_snippet_id: None,
+            command: None,
+        }
+    }
+}
+
 // =========================================================
 // ServerConfig 主配置结构体 (用于 CRUD)
 // =========================================================
@@ -112,6 +251,54 @@ pub struct ServerConfig {
     pub keep_alive_interval: Option<u32>,
     pub auto_reconnect: Option<bool>,
     pub max_reconnects: Option<u32>,
+
+    #[serde(default)]
+    pub host_key_policy: Option<HostKeyPolicy>,
+
+    // 🟢 ProxyJump：按顺序经过的跳板服务器 ID (存为 JSON 字符串)
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub jump_host_ids: Vec<String>,
+
+    // 🟢 Agent 转发 (auth-agent-req@openssh.com)，默认关闭
+    #[sqlx(default)]
+    #[serde(default)]
+    pub agent_forwarding: bool,
+
+    // 🟢 拆分模式：shell 与后台 (监控/SFTP) 各用一条传输，用于限制 MaxSessions 的服务器
+    #[sqlx(default)]
+    #[serde(default)]
+    pub split_transport: bool,
+
+    // 🟢 远端字符编码 (utf-8 / gbk / big5 / shift_jis / latin1)，为空按 UTF-8 处理；作用于终端与 SFTP 文本内容；SFTP 文件名不转换，无法解码的文件名在列表中标记而非报错
+    #[sqlx(default)]
+    #[serde(default)]
+    pub encoding: Option<String>,
+
+    // 🟢 连接时自动录制会话 (asciicast v2)，可选同时记录键盘输入
+    #[sqlx(default)]
+    #[serde(default)]
+    pub record_sessions: bool,
+
+    #[sqlx(default)]
+    #[serde(default)]
+    pub record_input: bool,
+
+    // 🟢 纯文本会话日志配置 (数据库中以 JSON 存储)
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub session_log: Option<SessionLogConfig>,
+
+    // 🟢 持久会话：连接时启动或附加远端 tmux/screen 会话，断线重连后恢复原有任务；
+    // 工作目录、启动命令只在新建会话时输入，固定命令作为新会话内的程序运行
+    #[sqlx(default)]
+    #[serde(default)]
+    pub persistent_session: Option<PersistentSessionMode>,
+
+    // 🟢 TERM / 初始尺寸 / 环境变量 / 启动命令 (数据库中以 JSON 存储)，为空使用默认 shell
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub shell_options: Option<ShellOptions>,
 }
 
 // 默认值函数
@@ -155,15 +342,57 @@ pub struct SshConfig {
     pub password: Option<String>,
     pub private_key: Option<String>,
     pub passphrase: Option<String>,
+    // 🟢 与 private_key 配套的 OpenSSH 用户证书 (*-cert.pub 内容)
+    #[serde(default)]
+    pub certificate: Option<String>,
 
     pub password_id: Option<String>,
     pub password_source: Option<String>,
 
+    // 🟢 AuthType::Agent: 通过 SSH_AUTH_SOCK 中的 ssh-agent 身份认证
+    #[serde(default)]
+    pub use_agent: bool,
+
     // 🟢 [关键修复 2] 这里就是报错的根源！必须手动加上这 4 个字段
     pub connect_timeout: Option<u32>,
     pub keep_alive_interval: Option<u32>,
     pub auto_reconnect: Option<bool>,
     pub max_reconnects: Option<u32>,
+
+    #[serde(default)]
+    pub host_key_policy: Option<HostKeyPolicy>,
+
+    // 🟢 已解析的跳板机配置，按连接顺序排列 (第一跳使用自身的代理设置)
+    #[serde(default)]
+    pub jump_hosts: Vec<SshConfig>,
+
+    #[serde(default)]
+    pub agent_forwarding: bool,
+
+    // 🟢 标记为可转发的 Vault 私钥 (已解密的私钥, 口令)；为空时转发本地 ssh-agent
+    #[serde(skip)]
+    pub agent_forward_keys: Vec<(String, Option<String>)>,
+
+    #[serde(default)]
+    pub split_transport: bool,
+
+    #[serde(default)]
+    pub encoding: Option<String>,
+
+    #[serde(default)]
+    pub record_sessions: bool,
+
+    #[serde(default)]
+    pub record_input: bool,
+
+    #[serde(default)]
+    pub session_log: Option<SessionLogConfig>,
+
+    #[serde(default)]
+    pub persistent_session: Option<PersistentSessionMode>,
+
+    #[serde(default)]
+    pub shell_options: Option<ShellOptions>,
 }
 
 // =========================================================
@@ -212,6 +441,99 @@ pub struct Proxy {
     pub updated_at: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardType {
+    /// 本地监听，经 direct-tcpip 转发到远端目标 (ssh -L)
+    #[default]
+    Local,
+    /// 服务端监听 (tcpip-forward)，连接经 forwarded-tcpip 回到本地目标 (ssh -R)
+    Remote,
+    /// 本地 SOCKS5 监听，按 CONNECT 请求动态打开 direct-tcpip (ssh -D)
+    Dynamic,
+}
+
+// 🟢 端口转发规则 (port_forwards 表)
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct PortForwardRule {
+    #[serde(default)]
+    pub id: String,
+    pub server_id: String,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub forward_type: ForwardType,
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+    pub bind_port: u16,
+    // 动态转发 (SOCKS5) 无固定目标
+    #[serde(default)]
+    pub target_host: String,
+    #[serde(default)]
+    pub target_port: u16,
+    #[serde(default)]
+    pub auto_start: bool,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+fn default_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerAction {
+    /// 向 shell 发送 payload 文本
+    #[default]
+    SendText,
+    /// payload 为 vault 条目 ID，发送解密后的密码
+    SendSecret,
+    /// 仅通知前端，payload 为提示文本
+    Notify,
+    /// payload 为代码片段 ID，发送片段内容
+    RunSnippet,
+}
+
+// 🟢 输出触发器 (triggers 表)：shell 输出匹配 pattern (正则) 时执行动作
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerRule {
+    #[serde(default)]
+    pub id: String,
+    pub server_id: String,
+    pub name: Option<String>,
+    pub pattern: String,
+    #[serde(default)]
+    pub action: TriggerAction,
+    #[serde(default)]
+    pub payload: String,
+    // 发送类动作末尾追加回车
+    #[serde(default = "default_true")]
+    pub append_newline: bool,
+    // 同一规则两次触发的最小间隔
+    #[serde(default = "default_trigger_cooldown_ms")]
+    pub cooldown_ms: u32,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_trigger_cooldown_ms() -> u32 {
+    1000
+}
+
 // ... existing code ...
 
 // 🟢 [新增] 用于接收前端测试连接的 Payload
@@ -240,6 +562,9 @@ pub struct TestConnectionPayload {
     // 高级设置
     pub connect_timeout: Option<u32>,
     pub proxy_id: Option<String>,
+    pub host_key_policy: Option<HostKeyPolicy>,
+    #[serde(default)]
+    pub jump_host_ids: Vec<String>,
 }
 
 // =========================================================
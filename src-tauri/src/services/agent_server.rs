@@ -1,4 +1,6 @@
+use crate::commands::ssh::core::{ScrollbackPage, ScrollbackSearchResult};
 use crate::commands::ssh::state::{SshState, SshWriteRequest, SshResizeRequest};
+use crate::commands::ssh::{query_scrollback, read_scrollback, ScrollbackQuery};
 use crate::commands::vault::VaultState;
 use crate::commands::fs::filesystem::FileEntry;
 use futures_util::{SinkExt, StreamExt};
@@ -23,6 +25,13 @@ struct AgentRequest {
     rows: Option<u32>,
     path: Option<String>,
     content: Option<String>,
+    // 回滚分页/搜索参数
+    pattern: Option<String>,
+    start_line: Option<u64>,
+    limit: Option<usize>,
+    context_lines: Option<usize>,
+    before_line: Option<u64>,
+    case_sensitive: Option<bool>,
 }
 
 #[derive(Serialize, Debug)]
@@ -113,6 +122,19 @@ struct AgentTerminalContentResponse {
     error: Option<String>,
 }
 
+#[derive(Serialize, Debug)]
+struct AgentScrollbackResponse {
+    status: String,
+    action: String,
+    session_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<ScrollbackPage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    search: Option<ScrollbackSearchResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
 #[derive(Serialize, Debug)]
 struct StreamDataResponse {
     event: String,
@@ -836,7 +858,13 @@ fn run_server(app: AppHandle, manager: Arc<Mutex<AgentServerManager>>, port: u16
                             let content_res = {
                                 let map = ssh_state.sessions.lock().unwrap();
                                 map.get(&session_id).map(|conn| {
-                                    conn.output_history.lock().map(|h| h.clone()).unwrap_or_default()
+                                    let encoding = crate::commands::ssh::utils::resolve_encoding(
+                                        conn.config.encoding.as_deref(),
+                                    );
+                                    conn.output_history
+                                        .lock()
+                                        .map(|h| h.decode(encoding))
+                                        .unwrap_or_default()
                                 })
                             };
 
@@ -861,6 +889,65 @@ fn run_server(app: AppHandle, manager: Arc<Mutex<AgentServerManager>>, port: u16
                                 let _ = tx.send(Message::Text(resp_text)).await;
                             }
                         }
+                        "get_scrollback" => {
+                            let session_id = req.session_id.unwrap_or_default();
+                            let ssh_state = app_handle.state::<SshState>();
+                            let resp = match read_scrollback(&ssh_state, &session_id, req.start_line, req.limit) {
+                                Ok(page) => AgentScrollbackResponse {
+                                    status: "success".to_string(),
+                                    action: "get_scrollback".to_string(),
+                                    session_id,
+                                    page: Some(page),
+                                    search: None,
+                                    error: None,
+                                },
+                                Err(e) => AgentScrollbackResponse {
+                                    status: "error".to_string(),
+                                    action: "get_scrollback".to_string(),
+                                    session_id,
+                                    page: None,
+                                    search: None,
+                                    error: Some(e),
+                                },
+                            };
+
+                            if let Ok(resp_text) = serde_json::to_string(&resp) {
+                                let _ = tx.send(Message::Text(resp_text)).await;
+                            }
+                        }
+                        "search_scrollback" => {
+                            let session_id = req.session_id.unwrap_or_default();
+                            let query = ScrollbackQuery {
+                                pattern: req.pattern.unwrap_or_default(),
+                                case_sensitive: req.case_sensitive,
+                                context_lines: req.context_lines,
+                                limit: req.limit,
+                                before_line: req.before_line,
+                            };
+                            let ssh_state = app_handle.state::<SshState>();
+                            let resp = match query_scrollback(&ssh_state, &session_id, &query) {
+                                Ok(result) => AgentScrollbackResponse {
+                                    status: "success".to_string(),
+                                    action: "search_scrollback".to_string(),
+                                    session_id,
+                                    page: None,
+                                    search: Some(result),
+                                    error: None,
+                                },
+                                Err(e) => AgentScrollbackResponse {
+                                    status: "error".to_string(),
+                                    action: "search_scrollback".to_string(),
+                                    session_id,
+                                    page: None,
+                                    search: None,
+                                    error: Some(e),
+                                },
+                            };
+
+                            if let Ok(resp_text) = serde_json::to_string(&resp) {
+                                let _ = tx.send(Message::Text(resp_text)).await;
+                            }
+                        }
                         "subscribe" => {
                             let Some(session_id) = req.session_id else {
                                 let resp = AgentResponse {
@@ -960,7 +1047,7 @@ fn run_server(app: AppHandle, manager: Arc<Mutex<AgentServerManager>>, port: u16
                             let resp = match write_tx_res {
                                 Some(Ok(write_tx)) => {
                                     let (result_tx, result_rx) = tokio::sync::oneshot::channel();
-                                    let request = SshWriteRequest { data, result_tx };
+                                    let request = SshWriteRequest { data, secret: false, result_tx };
                                     if write_tx.send(request).await.is_ok() && result_rx.await.is_ok() {
                                         AgentResponse {
                                             status: "success".to_string(),
@@ -0,0 +1,86 @@
+// 🟢 流式去除终端控制序列 (CSI / OSC / DCS 等)，转义序列可以跨数据块
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum StripState {
+    #[default]
+    Ground,
+    Escape,
+    EscapeIntermediate,
+    Csi,
+    // OSC 以 BEL 或 ST 结束；DCS/SOS/PM/APC 只以 ST 结束
+    Osc,
+    OscEscape,
+    Text,
+    TextEscape,
+}
+
+#[derive(Default)]
+pub struct AnsiStripper {
+    state: StripState,
+}
+
+impl AnsiStripper {
+    // 输出只保留可见文本、换行与制表符；'\r' 丢弃，退格删除上一个字符
+    pub fn push(&mut self, input: &str, output: &mut String) {
+        for ch in input.chars() {
+            self.state = match self.state {
+                StripState::Ground => match ch {
+                    '\x1b' => StripState::Escape,
+                    '\u{9b}' => StripState::Csi,
+                    '\u{9d}' => StripState::Osc,
+                    '\u{90}' | '\u{98}' | '\u{9e}' | '\u{9f}' => StripState::Text,
+                    '\n' | '\t' => {
+                        output.push(ch);
+                        StripState::Ground
+                    }
+                    '\x08' => {
+                        if !output.ends_with('\n') {
+                            output.pop();
+                        }
+                        StripState::Ground
+                    }
+                    ch if ch.is_control() => StripState::Ground,
+                    ch => {
+                        output.push(ch);
+                        StripState::Ground
+                    }
+                },
+                StripState::Escape => match ch {
+                    '[' => StripState::Csi,
+                    ']' => StripState::Osc,
+                    'P' | 'X' | '^' | '_' => StripState::Text,
+                    ' '..='/' => StripState::EscapeIntermediate,
+                    _ => StripState::Ground,
+                },
+                StripState::EscapeIntermediate => StripState::Ground,
+                StripState::Csi => match ch {
+                    '\u{40}'..='\u{7e}' => StripState::Ground,
+                    _ => StripState::Csi,
+                },
+                StripState::Osc => match ch {
+                    '\x07' | '\u{9c}' => StripState::Ground,
+                    '\x1b' => StripState::OscEscape,
+                    _ => StripState::Osc,
+                },
+                StripState::OscEscape => match ch {
+                    '\\' => StripState::Ground,
+                    _ => StripState::Osc,
+                },
+                StripState::Text => match ch {
+                    '\u{9c}' => StripState::Ground,
+                    '\x1b' => StripState::TextEscape,
+                    _ => StripState::Text,
+                },
+                StripState::TextEscape => match ch {
+                    '\\' => StripState::Ground,
+                    _ => StripState::Text,
+                },
+            };
+        }
+    }
+}
+
+pub fn strip_ansi(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    AnsiStripper::default().push(input, &mut output);
+    output
+}
@@ -1,2 +1,3 @@
+pub mod ansi;
 pub mod crypto;
 pub mod ssh_log;
@@ -13,7 +13,7 @@ use tauri::{AppHandle, Manager};
 
 const SSH_DIAGNOSTIC_LOG_DIR: &str = "logs";
 const SSH_DIAGNOSTIC_LOG_PREFIX: &str = "ssh-diagnostics";
-const REDACTED_VALUE: &str = "[REDACTED]";
+pub const REDACTED_VALUE: &str = "[REDACTED]";
 
 static SSH_DIAGNOSTIC_LOGGER: OnceLock<SshDiagnosticLogger> = OnceLock::new();
 
@@ -329,7 +329,20 @@ pub fn mask_path(path: &str) -> String {
 }
 
 pub fn sanitize_text(input: &str) -> String {
-    let mut sanitized = input.trim().replace('\r', "");
+    let sanitized = redact_secrets(input.trim());
+
+    const MAX_LOG_TEXT_LEN: usize = 320;
+    if sanitized.chars().count() > MAX_LOG_TEXT_LEN {
+        let truncated: String = sanitized.chars().take(MAX_LOG_TEXT_LEN).collect();
+        return format!("{}...(truncated)", truncated);
+    }
+
+    sanitized
+}
+
+// 🟢 只遮蔽密钥/口令/令牌与 IP，不截断 (会话日志需要保留完整输出)
+pub fn redact_secrets(input: &str) -> String {
+    let mut sanitized = input.replace('\r', "");
     sanitized = pem_block_regex()
         .replace_all(&sanitized, REDACTED_VALUE)
         .into_owned();
@@ -352,16 +365,17 @@ pub fn sanitize_text(input: &str) -> String {
     sanitized = ipv6_regex()
         .replace_all(&sanitized, |caps: &regex::Captures| mask_host(&caps[0]))
         .into_owned();
-
-    const MAX_LOG_TEXT_LEN: usize = 320;
-    if sanitized.chars().count() > MAX_LOG_TEXT_LEN {
-        let truncated: String = sanitized.chars().take(MAX_LOG_TEXT_LEN).collect();
-        return format!("{}...(truncated)", truncated);
-    }
-
     sanitized
 }
 
+// 最后一个 PEM 块只有 BEGIN 还没有 END，逐行处理的调用方需要继续收集后续行
+pub fn has_unterminated_pem_block(text: &str) -> bool {
+    let Some(begin) = pem_begin_regex().find_iter(text).last() else {
+        return false;
+    };
+    !pem_end_regex().is_match(&text[begin.end()..])
+}
+
 fn log(level: SshLogLevel, record: SshLogRecord) {
     let Some(logger) = SSH_DIAGNOSTIC_LOGGER.get() else {
         return;
@@ -571,6 +585,16 @@ fn pem_block_regex() -> &'static Regex {
     REGEX.get_or_init(|| Regex::new(r"(?s)-----BEGIN [^-]+-----.*?-----END [^-]+-----").unwrap())
 }
 
+fn pem_begin_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"-----BEGIN [^-]+-----").unwrap())
+}
+
+fn pem_end_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"-----END [^-]+-----").unwrap())
+}
+
 fn secret_assignment_regex() -> &'static Regex {
     static REGEX: OnceLock<Regex> = OnceLock::new();
     REGEX.get_or_init(|| {
@@ -161,7 +161,17 @@ export const FileList = ({ sessionId }: Props) => {
                             <div className="w-6 flex justify-center shrink-0">
                                 {file.isDir ? <FolderIcon className="w-5 h-5" /> : <FileIcon ext={file.extension} className="w-5 h-5" />}
                             </div>
-                            <div className="truncate font-medium text-slate-700 dark:text-slate-200" title={file.name}>{file.name}</div>
+                            <div
+                                className={clsx(
+                                    "truncate font-medium",
+                                    file.undecodableName
+                                        ? "text-amber-600 dark:text-amber-400 italic"
+                                        : "text-slate-700 dark:text-slate-200"
+                                )}
+                                title={file.undecodableName ? `${file.name}\n${t('fs.undecodableName')}` : file.name}
+                            >
+                                {file.name}
+                            </div>
                             <div className="text-right font-mono text-slate-500 text-xs shrink-0">{file.isDir ? '-' : formatBytes(file.size)}</div>
                             <div className="font-mono text-slate-500 text-xs truncate shrink-0">{file.permissions}</div>
                             <div className="text-slate-500 text-xs truncate shrink-0">{file.owner}</div>
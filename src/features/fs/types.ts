@@ -9,6 +9,7 @@ export interface FileEntry {
   owner: string;
   group: string;
   extension: string; // 用于匹配图标
+  undecodableName?: boolean; // 文件名含无法解码的字节，显示名可能与远端不一致
 }
 
 // 排序选项
@@ -263,7 +263,7 @@ export const useTerminalConnection = (
         if (code === 13) { // Enter
           const command = cmdBuffer.current.trim();
           if (command.length > 0 && serverConfig?.id) {
-            HistoryService.recordCommand(serverConfig.id, command).catch(() => {});
+            HistoryService.recordCommand(serverConfig.id, command, 'user', sessionId).catch(() => {});
           }
           cmdBuffer.current = '';
         } else if (code === 127) { // Backspace
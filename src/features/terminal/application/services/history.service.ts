@@ -1,6 +1,10 @@
 import { invoke } from '@tauri-apps/api/core';
 
 export const HistoryService = {
-  recordCommand: async (serverId: string, command: string, source: 'user' | 'snippet' = 'user') => 
-    invoke('record_command_history', { serverId, command, source }),
+  recordCommand: async (
+    serverId: string,
+    command: string,
+    source: 'user' | 'snippet' = 'user',
+    sessionId?: string,
+  ) => invoke('record_command_history', { serverId, command, source, sessionId }),
 };
\ No newline at end of file
@@ -125,7 +125,7 @@ export const TerminalService = {
     return invoke('connect_ssh', { serverId, sessionId });
   },
 
-  disconnectSsh: async (id: string) => {
+  disconnectSsh: async (id: string, keepPersistent = false) => {
     try {
       await flushWriteQueue(id);
     } catch {
@@ -133,7 +133,7 @@ export const TerminalService = {
     }
 
     try {
-      return await invoke('disconnect_ssh', { id });
+      return await invoke('disconnect_ssh', { id, keepPersistent });
     } finally {
       disposeSshWriteQueue(id);
     }
@@ -510,7 +510,8 @@ export const en = {
         connectTip:
           "Please connect to an SSH server to manage files"
       },
-      empty: "This folder is empty"
+      empty: "This folder is empty",
+      undecodableName: "The file name could not be decoded exactly; opening, renaming or deleting it may fail"
     },
 
     snippet: {
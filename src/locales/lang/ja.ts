@@ -483,7 +483,8 @@ export const ja = {
         connectTip:
           "ファイルを管理するには SSH サーバーに接続してください"
       },
-      empty: "このフォルダーは空です"
+      empty: "このフォルダーは空です",
+      undecodableName: "ファイル名を正確にデコードできません。開く・名前変更・削除が失敗する場合があります"
     },
 
     snippet: {
@@ -482,7 +482,8 @@ export const vi = {
         noConnection: "Chưa kết nối",
         connectTip: "Vui lòng kết nối máy chủ SSH để quản lý tệp"
       },
-      empty: "Thư mục này trống"
+      empty: "Thư mục này trống",
+      undecodableName: "Không thể giải mã chính xác tên tệp; mở, đổi tên hoặc xóa có thể thất bại"
     },
 
     snippet: {
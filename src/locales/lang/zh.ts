@@ -523,7 +523,8 @@ keys: {
       "timeoutDesc": "SFTP 握手响应时间过长。",
       "waiting": "正在等待 SFTP 连接..."
       },
-      empty: "此文件夹为空"
+      empty: "此文件夹为空",
+      undecodableName: "文件名无法按原样解码，打开、重命名或删除可能失败"
     },
     snippet:{
       copy_hint:"点击复制",
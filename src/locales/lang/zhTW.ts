@@ -477,7 +477,8 @@ export const zhTW = {
         noConnection: "未連線",
         connectTip: "請先連線至 SSH 伺服器以管理檔案"
       },
-      empty: "此資料夾為空"
+      empty: "此資料夾為空",
+      undecodableName: "檔案名稱無法按原樣解碼，開啟、重新命名或刪除可能失敗"
     },
 
     snippet: {